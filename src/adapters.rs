@@ -0,0 +1,261 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Result, Unpack};
+use std::io::{self, BufReader, BufWriter};
+
+/// Buffered writer with methods to pack values directly
+///
+/// Small values are collected in an internal buffer and written to the
+/// underlying bytestream in larger blocks. The buffer is flushed when it is
+/// full, on [`PackWriter::flush`] and when the writer is dropped
+pub struct PackWriter<W: io::Write> {
+    inner: BufWriter<W>,
+}
+
+impl<W: io::Write> PackWriter<W> {
+    /// Creates a writer with the default buffer capacity
+    pub fn new(writer: W) -> Self {
+        Self {
+            inner: BufWriter::new(writer),
+        }
+    }
+
+    /// Creates a writer with the given buffer capacity
+    pub fn with_capacity(capacity: usize, writer: W) -> Self {
+        Self {
+            inner: BufWriter::with_capacity(capacity, writer),
+        }
+    }
+
+    /// Packs a value into the bytestream
+    pub fn put<T: Pack + ?Sized>(&mut self, value: &T) -> pack::Result<usize> {
+        value.pack_into(&mut self.inner)
+    }
+
+    /// Packs all values of an iterator back-to-back into the bytestream
+    pub fn put_all<T: Pack>(&mut self, values: impl IntoIterator<Item = T>) -> pack::Result<usize> {
+        let mut written = 0;
+
+        for value in values {
+            written += value.pack_into(&mut self.inner)?;
+        }
+
+        Ok(written)
+    }
+
+    /// Returns a reference to the underlying bytestream
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    /// Flushes the buffer and returns the underlying bytestream
+    pub fn into_inner(self) -> io::Result<W> {
+        self.inner.into_inner().map_err(|error| error.into_error())
+    }
+}
+
+impl<W: io::Write> io::Write for PackWriter<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.inner.write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Buffered reader with methods to unpack values directly
+///
+/// The underlying bytesource is read in larger blocks, so unpacking many
+/// small values does not result in many small reads
+///
+/// This type intentionally does not implement `Read`, as its `take` method
+/// would be shadowed by `Read::take`
+pub struct UnpackReader<R: io::Read> {
+    inner: BufReader<R>,
+}
+
+impl<R: io::Read> UnpackReader<R> {
+    /// Creates a reader with the default buffer capacity
+    pub fn new(reader: R) -> Self {
+        Self {
+            inner: BufReader::new(reader),
+        }
+    }
+
+    /// Creates a reader with the given buffer capacity
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            inner: BufReader::with_capacity(capacity, reader),
+        }
+    }
+
+    /// Unpacks the next value from the bytesource
+    pub fn take<T: Unpack>(&mut self) -> Result<T> {
+        T::unpack_from_buffered(&mut self.inner)
+    }
+
+    /// Unpacks the next `len` values from the bytesource
+    pub fn take_n<T: Unpack>(&mut self, len: usize) -> Result<Vec<T>> {
+        let mut result = Vec::with_capacity(len);
+
+        for _i in 0..len {
+            result.push(T::unpack_from_buffered(&mut self.inner)?);
+        }
+
+        Ok(result)
+    }
+
+    /// Returns a reference to the underlying bytesource
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    /// Returns the underlying bytesource
+    ///
+    /// Bytes that have already been buffered but not unpacked are lost
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+/// Reader that can unpack values without consuming them
+///
+/// Bytes read while peeking are kept in an internal buffer and returned
+/// again by subsequent reads. This allows protocols in which a leading tag
+/// decides which struct to unpack next, while the tag itself is part of that
+/// struct
+pub struct PeekReader<R: io::Read> {
+    inner: R,
+    pending: Vec<u8>,
+    position: usize,
+}
+
+impl<R: io::Read> PeekReader<R> {
+    /// Creates a reader without any peeked bytes
+    pub fn new(reader: R) -> Self {
+        Self {
+            inner: reader,
+            pending: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Unpacks the next value without consuming it
+    ///
+    /// The next read (or peek) starts at the same position as this peek
+    pub fn peek<T: Unpack>(&mut self) -> Result<T> {
+        let mut reader = Peeking {
+            inner: &mut self.inner,
+            pending: &mut self.pending,
+            cursor: self.position,
+        };
+        T::unpack_from(&mut reader)
+    }
+
+    /// Returns the bytes that have been peeked but not yet consumed
+    pub fn peeked(&self) -> &[u8] {
+        &self.pending[self.position..]
+    }
+
+    /// Returns the underlying bytesource
+    ///
+    /// Bytes that have been peeked but not consumed are lost
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> io::Read for PeekReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.pending.len() {
+            return self.inner.read(buffer);
+        }
+
+        let peeked = &self.pending[self.position..];
+        let len = peeked.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&peeked[..len]);
+        self.position += len;
+
+        if self.position == self.pending.len() {
+            self.pending.clear();
+            self.position = 0;
+        }
+
+        Ok(len)
+    }
+}
+
+struct Peeking<'a, R> {
+    inner: &'a mut R,
+    pending: &'a mut Vec<u8>,
+    cursor: usize,
+}
+
+impl<R: io::Read> io::Read for Peeking<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.cursor < self.pending.len() {
+            let peeked = &self.pending[self.cursor..];
+            let len = peeked.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&peeked[..len]);
+            self.cursor += len;
+            return Ok(len);
+        }
+
+        let len = self.inner.read(buffer)?;
+        self.pending.extend_from_slice(&buffer[..len]);
+        self.cursor += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn put_values() {
+        let mut writer = PackWriter::new(Vec::new());
+        writer.put(&2u16).unwrap();
+        writer.put("a").unwrap();
+        writer.put_all([1u8, 2]).unwrap();
+
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(
+            bytes,
+            [0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x61, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn take_values() {
+        let bytes = [0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x61, 0x01, 0x02];
+        let mut reader = UnpackReader::new(bytes.as_ref());
+        assert_eq!(reader.take::<u16>().unwrap(), 2);
+        assert_eq!(reader.take::<String>().unwrap(), "a");
+        assert_eq!(reader.take_n::<u8>(2).unwrap(), [1, 2]);
+        assert!(reader.take::<u8>().is_err());
+    }
+
+    #[test]
+    fn peek_value() {
+        let bytes = [0x01, 0x00, 0x02];
+        let mut reader = PeekReader::new(bytes.as_ref());
+        assert_eq!(reader.peek::<u8>().unwrap(), 1);
+        assert_eq!(reader.peek::<u8>().unwrap(), 1);
+        assert_eq!(reader.peeked(), [0x01]);
+
+        assert_eq!(u8::unpack_from(&mut reader).unwrap(), 1);
+        assert_eq!(u16::unpack_from(&mut reader).unwrap(), 2);
+    }
+
+    #[test]
+    fn peek_longer_than_read() {
+        let bytes = [0x00, 0x02, 0x00, 0x03];
+        let mut reader = PeekReader::new(bytes.as_ref());
+        assert_eq!(reader.peek::<u32>().unwrap(), 0x00020003);
+        assert_eq!(u16::unpack_from(&mut reader).unwrap(), 2);
+        assert_eq!(reader.peek::<u16>().unwrap(), 3);
+        assert_eq!(u16::unpack_from(&mut reader).unwrap(), 3);
+        assert!(reader.peek::<u8>().is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! Container of multiple named sections in a single file
+//!
+//! A stack starts with the magic `STCK` and the format version as `u16`,
+//! followed by the packed values of all sections back to back. The table of
+//! contents is appended at the end and the last 8 bytes hold its offset as
+//! `u64`, so a reader locates any section with two seeks without reading
+//! the others
+//!
+//! ```
+//! use serial_container::archive::{Stack, StackWriter};
+//! use std::io::Cursor;
+//!
+//! let mut writer = StackWriter::new(Cursor::new(Vec::new())).unwrap();
+//! writer.add("count", &3u32).unwrap();
+//! writer.add("name", "stacker").unwrap();
+//! let file = writer.finish().unwrap();
+//!
+//! let mut stack = Stack::open(file).unwrap();
+//! assert_eq!(stack.get::<String>("name").unwrap(), "stacker");
+//! assert_eq!(stack.get::<u32>("count").unwrap(), 3);
+//! ```
+
+use crate::pack::{self, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::any::type_name;
+use std::collections::HashSet;
+use std::io::{self, Seek, SeekFrom};
+
+const MAGIC: [u8; 4] = *b"STCK";
+const VERSION: u16 = 1;
+
+/// Entry of the table of contents of a stack
+///
+/// The type name is recorded for information only. It is produced by
+/// `std::any::type_name` and is not checked when a section is read, as it
+/// may change between compiler versions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Entry {
+    pub name: String,
+    pub type_name: String,
+    pub offset: u64,
+    pub len: u64,
+}
+
+impl Pack for Entry {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = self.name.pack_into(writer)?;
+        written += self.type_name.pack_into(writer)?;
+        written += self.offset.pack_into(writer)?;
+        written += self.len.pack_into(writer)?;
+        Ok(written)
+    }
+}
+
+impl Unpack for Entry {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        Ok(Entry {
+            name: String::unpack_from(reader)?,
+            type_name: String::unpack_from(reader)?,
+            offset: u64::unpack_from(reader)?,
+            len: u64::unpack_from(reader)?,
+        })
+    }
+}
+
+/// Writer appending named sections to a stack
+///
+/// The table of contents is only written by [`StackWriter::finish`], a
+/// stack that was not finished cannot be opened
+pub struct StackWriter<W: io::Write> {
+    writer: W,
+    offset: u64,
+    entries: Vec<Entry>,
+    names: HashSet<String>,
+}
+
+impl<W: io::Write> StackWriter<W> {
+    /// Starts a new stack by writing its header
+    pub fn new(mut writer: W) -> pack::Result<Self> {
+        writer.write_all(&MAGIC)?;
+        let written = VERSION.pack_into(&mut writer)?;
+
+        Ok(Self {
+            writer,
+            offset: (MAGIC.len() + written) as u64,
+            entries: Vec::new(),
+            names: HashSet::new(),
+        })
+    }
+
+    /// Packs a value as a new section with the given name
+    ///
+    /// Fails with `pack::Error::InvalidValue` if a section with the same
+    /// name was already added
+    pub fn add<T: Pack + ?Sized>(&mut self, name: &str, value: &T) -> pack::Result<()> {
+        if self.names.contains(name) {
+            return Err(pack::Error::InvalidValue(format!(
+                "duplicate section {}",
+                name
+            )));
+        }
+
+        // pack into a buffer first, so a failing value leaves no partial
+        // section behind that would shift the offsets of later sections
+        let bytes = value.pack_to_vec()?;
+        self.writer.write_all(&bytes)?;
+        let len = bytes.len() as u64;
+        self.names.insert(name.to_owned());
+        self.entries.push(Entry {
+            name: name.to_owned(),
+            type_name: type_name::<T>().to_owned(),
+            offset: self.offset,
+            len,
+        });
+        self.offset += len;
+        Ok(())
+    }
+
+    /// Returns the entries of all sections added so far
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Writes the table of contents and returns the underlying bytestream
+    pub fn finish(mut self) -> pack::Result<W> {
+        self.entries.as_slice().pack_into(&mut self.writer)?;
+        self.offset.pack_into(&mut self.writer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Stack opened for random access reads of its sections
+pub struct Stack<R: io::Read + Seek> {
+    reader: R,
+    entries: Vec<Entry>,
+}
+
+impl<R: io::Read + Seek> Stack<R> {
+    /// Opens a stack by reading its header and table of contents
+    ///
+    /// Fails with `Error::InvalidValue` if the magic does not match and with
+    /// `Error::UnsupportedVersion` for stacks of a newer format
+    pub fn open(mut reader: R) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut magic = [0x00; 4];
+        reader.read_exact(&mut magic)?;
+
+        if magic != MAGIC {
+            return Err(Error::InvalidValue(String::from("not a stack")));
+        }
+
+        let version = u16::unpack_from(&mut reader)?;
+
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        reader.seek(SeekFrom::End(-8))?;
+        let offset = u64::unpack_from(&mut reader)?;
+        reader.seek(SeekFrom::Start(offset))?;
+        let entries = Vec::unpack_from(&mut reader)?;
+        Ok(Self { reader, entries })
+    }
+
+    /// Returns the entries of all sections in the order they were added
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Returns the entry of the section with the given name
+    pub fn entry(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Unpacks the section with the given name
+    ///
+    /// Fails with `Error::InvalidValue` if there is no such section and
+    /// with `Error::TrailingBytes` if the value does not consume the whole
+    /// section
+    pub fn get<T: Unpack>(&mut self, name: &str) -> Result<T> {
+        let bytes = self.get_raw(name)?;
+        T::unpack_exact(&bytes)
+    }
+
+    /// Reads the packed bytes of the section with the given name
+    pub fn get_raw(&mut self, name: &str) -> Result<Vec<u8>> {
+        let entry = self
+            .entry(name)
+            .ok_or_else(|| Error::InvalidValue(format!("missing section {}", name)))?;
+        let (offset, len) = (entry.offset, entry.len as usize);
+        self.reader.seek(SeekFrom::Start(offset))?;
+        read_bytes(&mut self.reader, len)
+    }
+
+    /// Returns the underlying bytesource
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn stack() -> Cursor<Vec<u8>> {
+        let mut writer = StackWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer.add("numbers", &[1u16, 2, 3]).unwrap();
+        writer.add("flag", &true).unwrap();
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn table_of_contents() {
+        let stack = Stack::open(stack()).unwrap();
+        let entries = stack.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "numbers");
+        assert_eq!(entries[0].type_name, "[u16; 3]");
+        assert_eq!((entries[0].offset, entries[0].len), (6, 10));
+        assert_eq!((entries[1].offset, entries[1].len), (16, 1));
+    }
+
+    #[test]
+    fn random_access() {
+        let mut stack = Stack::open(stack()).unwrap();
+        assert!(stack.get::<bool>("flag").unwrap());
+        assert_eq!(stack.get::<Vec<u16>>("numbers").unwrap(), [1, 2, 3]);
+        assert!(matches!(
+            stack.get::<u8>("missing"),
+            Err(Error::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_stacks() {
+        let mut writer = StackWriter::new(Vec::new()).unwrap();
+        writer.add("a", &1u8).unwrap();
+        assert!(writer.add("a", &2u8).is_err());
+
+        let mut bytes = stack().into_inner();
+        bytes[0] = b'X';
+        assert!(matches!(
+            Stack::open(Cursor::new(bytes)),
+            Err(Error::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn add_after_failed_section() {
+        struct Faulty;
+
+        impl Pack for Faulty {
+            fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+                writer.write_all(&[1, 2, 3])?;
+                Err(pack::Error::InvalidValue("faulty".into()))
+            }
+        }
+
+        let mut writer = StackWriter::new(Cursor::new(Vec::new())).unwrap();
+        assert!(writer.add("faulty", &Faulty).is_err());
+        writer.add("flag", &true).unwrap();
+        let mut stack = Stack::open(writer.finish().unwrap()).unwrap();
+        assert!(stack.get::<bool>("flag").unwrap());
+    }
+}
@@ -0,0 +1,81 @@
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+crate::async_impl::async_impls!();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn pack_async_u16() {
+        let mut bytes = Vec::new();
+        let written = block_on(2u16.pack_into_async(&mut bytes)).unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(bytes, [0x00, 0x02]);
+    }
+
+    #[test]
+    fn pack_async_str() {
+        let mut bytes = Vec::new();
+        block_on("abc".pack_into_async(&mut bytes)).unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63]);
+    }
+
+    #[test]
+    fn unpack_async_bool() {
+        let bytes = [0xFF];
+        let value = block_on(bool::unpack_from_async(&mut bytes.as_ref())).unwrap();
+        assert!(!value);
+    }
+
+    #[test]
+    fn unpack_async_non_zero_option_u32() {
+        type Value = Option<NonZeroU32>;
+        let bytes = [0x00, 0x00, 0x00, 0x02];
+        let value = block_on(Value::unpack_from_async(&mut bytes.as_ref())).unwrap();
+        assert_eq!(value, NonZeroU32::new(2));
+    }
+
+    #[test]
+    fn unpack_async_string() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63];
+        let value = block_on(String::unpack_from_async(&mut bytes.as_ref())).unwrap();
+        assert_eq!(value, "abc");
+    }
+
+    #[test]
+    fn unpack_async_hash_set() {
+        type Set = HashSet<u8>;
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x01, 0x02];
+        let value = block_on(Set::unpack_from_async(&mut bytes.as_ref())).unwrap();
+        assert_eq!(value, HashSet::from([1, 2]));
+    }
+
+    #[test]
+    fn unpack_async_truncated() {
+        let bytes = [0x00];
+        let result = block_on(u16::unpack_from_async(&mut bytes.as_ref()));
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn unpack_async_forged_length() {
+        let bytes = [0xFF, 0xFF, 0xFF, 0xFF, 0x61];
+        let result = block_on(String::unpack_from_async(&mut bytes.as_ref()));
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn unpack_next_values() {
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0x61, 0x00, 0x00, 0x00, 0x00];
+        let mut reader = bytes.as_ref();
+        let mut decoder = Decoder::<String>::new();
+        let first = block_on(unpack_next(&mut decoder, &mut reader)).unwrap();
+        let second = block_on(unpack_next(&mut decoder, &mut reader)).unwrap();
+        let end = block_on(unpack_next(&mut decoder, &mut reader)).unwrap();
+        assert_eq!(first.as_deref(), Some("a"));
+        assert_eq!(second.as_deref(), Some(""));
+        assert_eq!(end, None);
+    }
+}
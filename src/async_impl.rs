@@ -0,0 +1,316 @@
+/// Generates the async traits and their implementations for one runtime
+///
+/// The invoking module has to import `AsyncRead`, `AsyncReadExt`,
+/// `AsyncWrite` and `AsyncWriteExt` of the respective runtime, so that the
+/// traits behave exactly the same no matter which runtime is used
+macro_rules! async_impls {
+    () => {
+        use std::collections::binary_heap::*;
+        use std::collections::btree_map::*;
+        use std::collections::btree_set::*;
+        use std::collections::hash_map::*;
+        use std::collections::hash_set::*;
+        use std::future::Future;
+        use std::io;
+        use std::num::*;
+        use std::rc::Rc;
+        use std::sync::Arc;
+        use $crate::decoder::Decoder;
+        use $crate::pack::{self, Pack};
+        use $crate::unpack::{initial_capacity, Error, Result, Unpack};
+
+        /// Describes the ability to serialize this struct into an asynchronous
+        /// bytestream
+        ///
+        /// This trait is implemented for every type implementing [`Pack`]. The
+        /// value is packed into an intermediate buffer first, which is then
+        /// written with a single call to `write_all`
+        pub trait AsyncPack {
+            /// Tries to serialize this struct into an asynchronous bytestream
+            ///
+            /// Serialization may fail for the same reasons as [`Pack::pack_into`]
+            fn pack_into_async<W: AsyncWrite + Unpin + Send>(
+                &self,
+                writer: &mut W,
+            ) -> impl Future<Output = pack::Result<usize>> + Send;
+        }
+
+        impl<T: Pack + Sync + ?Sized> AsyncPack for T {
+            async fn pack_into_async<W: AsyncWrite + Unpin + Send>(
+                &self,
+                writer: &mut W,
+            ) -> pack::Result<usize> {
+                let buffer = self.pack_to_vec()?;
+                writer.write_all(&buffer).await?;
+                Ok(buffer.len())
+            }
+        }
+
+        /// Describes the ability to deserialize a struct from an asynchronous
+        /// bytesource
+        ///
+        /// Implementations have to produce the same results as the corresponding
+        /// [`Unpack`] implementations
+        ///
+        /// The returned futures are not cancellation safe: if a future is dropped
+        /// before it completes (e.g. as a losing branch of `select!`), the bytes it
+        /// has already read are lost. Use [`unpack_next`] where this matters
+        pub trait AsyncUnpack {
+            /// Tries to deserialize this struct from an asynchronous sequence of bytes
+            ///
+            /// Deserialization may fail for the same reasons as [`Unpack::unpack_from`]
+            fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> impl Future<Output = Result<Self>> + Send
+            where
+                Self: Sized;
+        }
+
+        /// Reads from an asynchronous bytesource until `decoder` yields a value
+        ///
+        /// In contrast to [`AsyncUnpack`], this function is cancellation safe:
+        /// every chunk is moved into the decoder as soon as it has been read, so
+        /// dropping the future never loses any bytes. Calling this function again
+        /// with the same decoder resumes decoding where the dropped call stopped
+        ///
+        /// Returns `Ok(None)` if the bytesource ends cleanly between two values
+        /// and an `UnexpectedEof` error if it ends in the middle of a value
+        pub async fn unpack_next<T: Unpack, R: AsyncRead + Unpin + Send>(
+            decoder: &mut Decoder<T>,
+            reader: &mut R,
+        ) -> Result<Option<T>> {
+            let mut buffer = [0x00; 4096];
+
+            loop {
+                if let Some(value) = decoder.poll_item()? {
+                    return Ok(Some(value));
+                }
+
+                let read = reader.read(&mut buffer).await.map_err(Error::IO)?;
+
+                if read == 0 {
+                    return if decoder.is_empty() {
+                        Ok(None)
+                    } else {
+                        Err(Error::IO(io::ErrorKind::UnexpectedEof.into()))
+                    };
+                }
+
+                decoder.push(&buffer[..read]);
+            }
+        }
+
+        /// Reads exactly `len` bytes without allocating more than the
+        /// bytesource actually provides
+        async fn read_bytes<R: AsyncRead + Unpin + Send>(
+            reader: &mut R,
+            len: usize,
+        ) -> Result<Vec<u8>> {
+            let mut bytes = Vec::with_capacity(initial_capacity::<u8>(len));
+            let read = (&mut *reader)
+                .take(len as u64)
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(Error::IO)?;
+
+            if read < len {
+                return Err(Error::IO(io::ErrorKind::UnexpectedEof.into()));
+            }
+
+            Ok(bytes)
+        }
+
+        async fn unpack_fixed<T: Unpack, R: AsyncRead + Unpin + Send, const N: usize>(
+            reader: &mut R,
+        ) -> Result<T> {
+            let mut bytes = [0x00; N];
+            reader.read_exact(&mut bytes).await.map_err(Error::IO)?;
+            T::unpack_from(&mut bytes.as_ref())
+        }
+
+        $crate::async_impl::impl_fixed! {
+            bool => 1,
+            u8 => 1,
+            NonZeroU8 => 1,
+            Option<NonZeroU8> => 1,
+            u16 => 2,
+            NonZeroU16 => 2,
+            Option<NonZeroU16> => 2,
+            u32 => 4,
+            NonZeroU32 => 4,
+            Option<NonZeroU32> => 4,
+            u64 => 8,
+            NonZeroU64 => 8,
+            Option<NonZeroU64> => 8,
+            u128 => 16,
+            NonZeroU128 => 16,
+            Option<NonZeroU128> => 16,
+            i16 => 2,
+            NonZeroI16 => 2,
+            Option<NonZeroI16> => 2,
+            i32 => 4,
+            NonZeroI32 => 4,
+            Option<NonZeroI32> => 4,
+            i64 => 8,
+            NonZeroI64 => 8,
+            Option<NonZeroI64> => 8,
+            i128 => 16,
+            NonZeroI128 => 16,
+            Option<NonZeroI128> => 16,
+            f32 => 4,
+            f64 => 8,
+        }
+
+        impl AsyncUnpack for String {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                let len = u32::unpack_from_async(reader).await? as usize;
+                let bytes = read_bytes(reader, len).await?;
+                String::from_utf8(bytes).map_err(Error::UTF8)
+            }
+        }
+
+        impl<T: AsyncUnpack + Send> AsyncUnpack for Vec<T> {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                let len = u32::unpack_from_async(reader).await? as usize;
+                let mut result = Vec::with_capacity(initial_capacity::<T>(len));
+
+                for _i in 0..len {
+                    result.push(T::unpack_from_async(reader).await?);
+                }
+
+                Ok(result)
+            }
+        }
+
+        impl<T: AsyncUnpack> AsyncUnpack for Box<T> {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                T::unpack_from_async(reader).await.map(Box::new)
+            }
+        }
+
+        impl<T: AsyncUnpack> AsyncUnpack for Rc<T> {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                T::unpack_from_async(reader).await.map(Rc::new)
+            }
+        }
+
+        impl<T: AsyncUnpack> AsyncUnpack for Arc<T> {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                T::unpack_from_async(reader).await.map(Arc::new)
+            }
+        }
+
+        impl<K: AsyncUnpack + Send + Eq + std::hash::Hash, V: AsyncUnpack + Send> AsyncUnpack
+            for HashMap<K, V>
+        {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                let len = u32::unpack_from_async(reader).await? as usize;
+                let mut result = HashMap::with_capacity(initial_capacity::<(K, V)>(len));
+
+                for _i in 0..len {
+                    let key = K::unpack_from_async(reader).await?;
+                    let value = V::unpack_from_async(reader).await?;
+                    result.insert(key, value);
+                }
+
+                Ok(result)
+            }
+        }
+
+        impl<T: AsyncUnpack + Send + Eq + std::hash::Hash> AsyncUnpack for HashSet<T> {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                let len = u32::unpack_from_async(reader).await? as usize;
+                let mut result = HashSet::with_capacity(initial_capacity::<T>(len));
+
+                for _i in 0..len {
+                    let value = T::unpack_from_async(reader).await?;
+                    result.insert(value);
+                }
+
+                Ok(result)
+            }
+        }
+
+        impl<K: AsyncUnpack + Send + Ord, V: AsyncUnpack + Send> AsyncUnpack for BTreeMap<K, V> {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                let len = u32::unpack_from_async(reader).await? as usize;
+                let mut result = BTreeMap::new();
+
+                for _i in 0..len {
+                    let key = K::unpack_from_async(reader).await?;
+                    let value = V::unpack_from_async(reader).await?;
+                    result.insert(key, value);
+                }
+
+                Ok(result)
+            }
+        }
+
+        impl<T: AsyncUnpack + Send + Ord> AsyncUnpack for BTreeSet<T> {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                let len = u32::unpack_from_async(reader).await? as usize;
+                let mut result = BTreeSet::new();
+
+                for _i in 0..len {
+                    let value = T::unpack_from_async(reader).await?;
+                    result.insert(value);
+                }
+
+                Ok(result)
+            }
+        }
+
+        impl<T: AsyncUnpack + Send + Ord> AsyncUnpack for BinaryHeap<T> {
+            async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                reader: &mut R,
+            ) -> Result<Self> {
+                let len = u32::unpack_from_async(reader).await? as usize;
+                let mut result = BinaryHeap::new();
+
+                for _i in 0..len {
+                    let value = T::unpack_from_async(reader).await?;
+                    result.push(value);
+                }
+
+                Ok(result)
+            }
+        }
+    };
+}
+
+/// Implements `AsyncUnpack` for types of a fixed packed size by reading the
+/// whole value at once and delegating to its `Unpack` implementation
+macro_rules! impl_fixed {
+    ($($type:ty => $size:literal),* $(,)?) => {
+        $(
+            impl AsyncUnpack for $type {
+                async fn unpack_from_async<R: AsyncRead + Unpin + Send>(
+                    reader: &mut R,
+                ) -> Result<Self> {
+                    unpack_fixed::<Self, R, $size>(reader).await
+                }
+            }
+        )*
+    };
+}
+
+pub(crate) use async_impls;
+pub(crate) use impl_fixed;
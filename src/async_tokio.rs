@@ -0,0 +1,150 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+crate::async_impl::async_impls!();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pack_async_u16() {
+        let mut bytes = Vec::new();
+        let written = 2u16.pack_into_async(&mut bytes).await.unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(bytes, [0x00, 0x02]);
+    }
+
+    #[tokio::test]
+    async fn pack_async_str() {
+        let mut bytes = Vec::new();
+        "abc".pack_into_async(&mut bytes).await.unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63]);
+    }
+
+    #[tokio::test]
+    async fn unpack_async_bool() {
+        let bytes = [0xFF];
+        let value = bool::unpack_from_async(&mut bytes.as_ref()).await.unwrap();
+        assert!(!value);
+    }
+
+    #[tokio::test]
+    async fn unpack_async_non_zero_option_u32() {
+        type Value = Option<NonZeroU32>;
+        let bytes = [0x00, 0x00, 0x00, 0x02];
+        let value = Value::unpack_from_async(&mut bytes.as_ref()).await.unwrap();
+        assert_eq!(value, NonZeroU32::new(2));
+    }
+
+    #[tokio::test]
+    async fn unpack_async_i128() {
+        let bytes = [0xFF; 16];
+        let value = i128::unpack_from_async(&mut bytes.as_ref()).await.unwrap();
+        assert_eq!(value, -1);
+    }
+
+    #[tokio::test]
+    async fn unpack_async_f64() {
+        let bytes = [0xBF, 0xF0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let value = f64::unpack_from_async(&mut bytes.as_ref()).await.unwrap();
+        assert_eq!(value, -1.0);
+    }
+
+    #[tokio::test]
+    async fn unpack_async_string() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63];
+        let value = String::unpack_from_async(&mut bytes.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(value, "abc");
+    }
+
+    #[tokio::test]
+    async fn unpack_async_array() {
+        type Array = Vec<u8>;
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03];
+        let value = Array::unpack_from_async(&mut bytes.as_ref()).await.unwrap();
+        assert_eq!(value, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn unpack_async_box() {
+        type Value = Box<u16>;
+        let bytes = [0x00, 0x02];
+        let value = Value::unpack_from_async(&mut bytes.as_ref()).await.unwrap();
+        assert_eq!(value, Box::new(2));
+    }
+
+    #[tokio::test]
+    async fn unpack_async_btree_map() {
+        type Map = BTreeMap<u8, u16>;
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x02];
+        let value = Map::unpack_from_async(&mut bytes.as_ref()).await.unwrap();
+        assert_eq!(value, BTreeMap::from([(1, 2)]));
+    }
+
+    #[tokio::test]
+    async fn unpack_async_truncated() {
+        let bytes = [0x00];
+        let result = u16::unpack_from_async(&mut bytes.as_ref()).await;
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[tokio::test]
+    async fn unpack_async_forged_length() {
+        let bytes = [0xFF, 0xFF, 0xFF, 0xFF, 0x61];
+        let result = String::unpack_from_async(&mut bytes.as_ref()).await;
+        assert!(matches!(result, Err(Error::IO(_))));
+        let result = Vec::<u8>::unpack_from_async(&mut bytes.as_ref()).await;
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[tokio::test]
+    async fn unpack_next_values() {
+        let bytes = [0x00, 0x01, 0x00, 0x02];
+        let mut reader = bytes.as_ref();
+        let mut decoder = Decoder::<u16>::new();
+        assert_eq!(
+            unpack_next(&mut decoder, &mut reader).await.unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            unpack_next(&mut decoder, &mut reader).await.unwrap(),
+            Some(2)
+        );
+        assert_eq!(unpack_next(&mut decoder, &mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn unpack_next_truncated() {
+        let bytes = [0x00, 0x01, 0x00];
+        let mut reader = bytes.as_ref();
+        let mut decoder = Decoder::<u16>::new();
+        assert_eq!(
+            unpack_next(&mut decoder, &mut reader).await.unwrap(),
+            Some(1)
+        );
+        assert!(unpack_next(&mut decoder, &mut reader).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unpack_next_survives_cancellation() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let mut decoder = Decoder::<String>::new();
+        client
+            .write_all(&[0x00, 0x00, 0x00, 0x03, 0x61])
+            .await
+            .unwrap();
+
+        tokio::select! {
+            biased;
+            _ = unpack_next(&mut decoder, &mut server) => panic!("value is incomplete"),
+            _ = std::future::ready(()) => {}
+        }
+
+        assert_eq!(decoder.buffered().len(), 5);
+        client.write_all(&[0x62, 0x63]).await.unwrap();
+        let value = unpack_next(&mut decoder, &mut server).await.unwrap();
+        assert_eq!(value.as_deref(), Some("abc"));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::unpack::{Error, Result, Unpack};
+use std::io;
+
+/// Describes the ability to deserialize a struct borrowing from a byte slice
+///
+/// In contrast to [`Unpack`], implementors may hold references into the
+/// input, so strings and byte arrays can be read as `&'a str` and `&'a [u8]`
+/// views without allocating. The wire format is the same as for `String`
+/// and `Vec<u8>`
+///
+/// This trait is implemented for every type implementing [`Unpack`], so
+/// borrowed and owned values can be mixed within one struct
+pub trait UnpackRef<'a>: Sized {
+    /// Tries to deserialize this struct from the start of `bytes`
+    ///
+    /// On success `bytes` is advanced past the consumed bytes
+    fn unpack_ref(bytes: &mut &'a [u8]) -> Result<Self>;
+}
+
+fn split_prefixed<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let (len, remaining) = u32::unpack_from_slice(bytes)?;
+    let len = len as usize;
+
+    if remaining.len() < len {
+        return Err(Error::IO(io::ErrorKind::UnexpectedEof.into()));
+    }
+
+    let (value, remaining) = remaining.split_at(len);
+    *bytes = remaining;
+    Ok(value)
+}
+
+impl<'a> UnpackRef<'a> for &'a [u8] {
+    fn unpack_ref(bytes: &mut &'a [u8]) -> Result<Self> {
+        split_prefixed(bytes)
+    }
+}
+
+impl<'a> UnpackRef<'a> for &'a str {
+    fn unpack_ref(bytes: &mut &'a [u8]) -> Result<Self> {
+        let mut remaining = *bytes;
+        let value = split_prefixed(&mut remaining)?;
+        let value = std::str::from_utf8(value).map_err(Error::UTF8Ref)?;
+        *bytes = remaining;
+        Ok(value)
+    }
+}
+
+impl<'a, T: Unpack> UnpackRef<'a> for T {
+    fn unpack_ref(bytes: &mut &'a [u8]) -> Result<Self> {
+        let (value, remaining) = T::unpack_from_slice(bytes)?;
+        *bytes = remaining;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpack_borrowed_str() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63, 0xFF];
+        let mut remaining = bytes.as_ref();
+        let value = <&str>::unpack_ref(&mut remaining).unwrap();
+        assert_eq!(value, "abc");
+        assert_eq!(remaining, [0xFF]);
+    }
+
+    #[test]
+    fn unpack_borrowed_bytes_and_owned() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x01, 0x02, 0x00, 0x03];
+        let mut remaining = bytes.as_ref();
+        let value = <&[u8]>::unpack_ref(&mut remaining).unwrap();
+        let number = u16::unpack_ref(&mut remaining).unwrap();
+        assert_eq!((value, number), ([0x01, 0x02].as_ref(), 3));
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn unpack_borrowed_truncated() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x61];
+        let mut remaining = bytes.as_ref();
+        assert!(<&str>::unpack_ref(&mut remaining).is_err());
+        assert_eq!(remaining.len(), 5);
+    }
+
+    #[test]
+    fn unpack_borrowed_invalid_utf8() {
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0xFF];
+        let result = <&str>::unpack_ref(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::UTF8Ref(_))));
+    }
+}
@@ -0,0 +1,430 @@
+//! Interoperability with [CBOR](https://cbor.io)
+//!
+//! Values are transcoded through their [`Schema`] like in the
+//! [`msgpack`](crate::msgpack) module. Only definite-length items are
+//! written and accepted. The canonical mode additionally sorts map keys by
+//! their encoded bytes as required by the deterministic encoding of
+//! RFC 8949, so equal values always produce identical bytes (e.g. for COSE)
+//!
+//! Floats are written in the shortest of the single and double precision
+//! formats that preserves their value. The canonical mode also considers
+//! half precision and writes every NaN as the half precision quiet NaN
+//! `0xF9 0x7E 0x00`, as the deterministic encoding requires
+//!
+//! # Example
+//!
+//! ```
+//! use serial_container::cbor::{from_cbor, to_cbor};
+//!
+//! let bytes = to_cbor(&[1u32, 500]).unwrap();
+//! assert_eq!(bytes, [0x82, 0x01, 0x19, 0x01, 0xF4]);
+//!
+//! let value: [u32; 2] = from_cbor(&bytes).unwrap();
+//! assert_eq!(value, [1, 500]);
+//! ```
+
+use crate::pack::Pack;
+use crate::schema::{Describe, Schema};
+use crate::unpack::{check_depth, read_bytes, Error, Result, Unpack};
+use crate::value::Value;
+use std::io;
+
+/// Converts a packable value into CBOR
+pub fn to_cbor<T: Pack + Describe + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    encode(value, false)
+}
+
+/// Converts a packable value into canonical CBOR with sorted map keys
+pub fn to_cbor_canonical<T: Pack + Describe + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    encode(value, true)
+}
+
+/// Converts CBOR into an unpackable value
+///
+/// Fails with `Error::TrailingBytes` if the bytes are not consumed entirely
+pub fn from_cbor<T: Unpack + Describe>(bytes: &[u8]) -> Result<T> {
+    from_cbor_with_schema(&T::schema(), bytes).and_then(|packed| T::unpack_exact(&packed))
+}
+
+/// Converts CBOR into the packed format described by the schema
+pub fn from_cbor_with_schema(schema: &Schema, mut bytes: &[u8]) -> Result<Vec<u8>> {
+    let value = read_value(&mut bytes)?;
+
+    if !bytes.is_empty() {
+        return Err(Error::TrailingBytes(bytes.len()));
+    }
+
+    let mut packed = Vec::new();
+    value.pack_with_schema(schema, &mut packed)?;
+    Ok(packed)
+}
+
+/// Writes a value as CBOR and returns the number of bytes written
+///
+/// Integers outside of the range representable by CBOR (-2^64 to 2^64 - 1)
+/// fail with `Error::InvalidValue`
+pub fn write_value(value: &Value, writer: &mut impl io::Write) -> Result<usize> {
+    write(value, false, writer)
+}
+
+/// Writes a value as canonical CBOR and returns the number of bytes written
+pub fn write_value_canonical(value: &Value, writer: &mut impl io::Write) -> Result<usize> {
+    write(value, true, writer)
+}
+
+/// Reads a single CBOR item
+///
+/// Tags and indefinite-length items are not supported and fail with
+/// `Error::InvalidValue`, as do arrays and maps nested too deeply
+pub fn read_value(reader: &mut impl io::Read) -> Result<Value> {
+    read_nested(reader, 0)
+}
+
+fn read_nested(reader: &mut impl io::Read, depth: usize) -> Result<Value> {
+    check_depth(depth)?;
+    let initial = u8::unpack_from(reader)?;
+    let major = initial >> 5;
+    let info = initial & 0x1F;
+
+    if major == 7 {
+        return read_simple(reader, info);
+    }
+
+    let argument = read_argument(reader, info)?;
+    let value = match major {
+        0 => Value::Int(argument.into()),
+        1 => Value::Int(-1 - i128::from(argument)),
+        2 => Value::Bytes(read_bytes(reader, length(argument)?)?),
+        3 => {
+            let bytes = read_bytes(reader, length(argument)?)?;
+            Value::Str(String::from_utf8(bytes).map_err(Error::UTF8)?)
+        }
+        4 => {
+            let mut items = Vec::new();
+
+            for _i in 0..argument {
+                items.push(read_nested(reader, depth + 1)?);
+            }
+
+            Value::List(items)
+        }
+        5 => {
+            let mut entries = Vec::new();
+
+            for _i in 0..argument {
+                let key = read_nested(reader, depth + 1)?;
+                let value = read_nested(reader, depth + 1)?;
+                entries.push((key, value));
+            }
+
+            Value::Map(entries)
+        }
+        _ => return Err(Error::InvalidValue("CBOR tags are not supported".into())),
+    };
+    Ok(value)
+}
+
+fn encode<T: Pack + Describe + ?Sized>(value: &T, canonical: bool) -> Result<Vec<u8>> {
+    let packed = value.pack_to_vec()?;
+    let value = Value::unpack_with_schema(&T::schema(), &mut packed.as_slice())?;
+    let mut bytes = Vec::new();
+    write(&value, canonical, &mut bytes)?;
+    Ok(bytes)
+}
+
+fn write(value: &Value, canonical: bool, writer: &mut impl io::Write) -> Result<usize> {
+    let written = match value {
+        Value::Null => 0xF6u8.pack_into(writer)?,
+        Value::Bool(false) => 0xF4u8.pack_into(writer)?,
+        Value::Bool(true) => 0xF5u8.pack_into(writer)?,
+        Value::Int(value) if *value >= 0 => match u64::try_from(*value) {
+            Ok(value) => write_head(0, value, writer)?,
+            Err(_) => return Err(out_of_range(*value)),
+        },
+        Value::Int(value) => match u64::try_from(-1 - *value) {
+            Ok(argument) => write_head(1, argument, writer)?,
+            Err(_) => return Err(out_of_range(*value)),
+        },
+        Value::Float(value) if canonical && value.is_nan() => {
+            0xF9u8.pack_into(writer)? + 0x7E00u16.pack_into(writer)?
+        }
+        Value::Float(value) if canonical && half_from_f64(*value).is_some() => {
+            let half = half_from_f64(*value).expect("value fits into half precision");
+            0xF9u8.pack_into(writer)? + half.pack_into(writer)?
+        }
+        Value::Float(value) if (*value as f32) as f64 == *value || value.is_nan() => {
+            0xFAu8.pack_into(writer)? + (*value as f32).pack_into(writer)?
+        }
+        Value::Float(value) => 0xFBu8.pack_into(writer)? + value.pack_into(writer)?,
+        Value::Str(value) => {
+            let written = write_head(3, value.len() as u64, writer)?;
+            writer.write_all(value.as_bytes())?;
+            written + value.len()
+        }
+        Value::Bytes(value) => {
+            let written = write_head(2, value.len() as u64, writer)?;
+            writer.write_all(value)?;
+            written + value.len()
+        }
+        Value::List(items) => {
+            let mut written = write_head(4, items.len() as u64, writer)?;
+
+            for item in items {
+                written += write(item, canonical, writer)?;
+            }
+
+            written
+        }
+        Value::Map(entries) if canonical => {
+            let mut encoded = Vec::with_capacity(entries.len());
+
+            for (key, value) in entries {
+                let mut key_bytes = Vec::new();
+                write(key, canonical, &mut key_bytes)?;
+                encoded.push((key_bytes, value));
+            }
+
+            encoded.sort_by(|(left, _), (right, _)| left.cmp(right));
+            let mut written = write_head(5, entries.len() as u64, writer)?;
+
+            for (key, value) in encoded {
+                writer.write_all(&key)?;
+                written += key.len();
+                written += write(value, canonical, writer)?;
+            }
+
+            written
+        }
+        Value::Map(entries) => {
+            let mut written = write_head(5, entries.len() as u64, writer)?;
+
+            for (key, value) in entries {
+                written += write(key, canonical, writer)?;
+                written += write(value, canonical, writer)?;
+            }
+
+            written
+        }
+    };
+    Ok(written)
+}
+
+fn write_head(major: u8, argument: u64, writer: &mut impl io::Write) -> Result<usize> {
+    let major = major << 5;
+    let written = if argument < 24 {
+        (major | argument as u8).pack_into(writer)?
+    } else if argument <= 0xFF {
+        (major | 24).pack_into(writer)? + (argument as u8).pack_into(writer)?
+    } else if argument <= 0xFFFF {
+        (major | 25).pack_into(writer)? + (argument as u16).pack_into(writer)?
+    } else if argument <= 0xFFFF_FFFF {
+        (major | 26).pack_into(writer)? + (argument as u32).pack_into(writer)?
+    } else {
+        (major | 27).pack_into(writer)? + argument.pack_into(writer)?
+    };
+    Ok(written)
+}
+
+fn read_argument(reader: &mut impl io::Read, info: u8) -> Result<u64> {
+    let argument = match info {
+        0..=23 => info.into(),
+        24 => u8::unpack_from(reader)?.into(),
+        25 => u16::unpack_from(reader)?.into(),
+        26 => u32::unpack_from(reader)?.into(),
+        27 => u64::unpack_from(reader)?,
+        31 => {
+            return Err(Error::InvalidValue(
+                "indefinite-length CBOR items are not supported".into(),
+            ))
+        }
+        info => {
+            return Err(Error::InvalidValue(format!(
+                "invalid CBOR additional information {}",
+                info
+            )))
+        }
+    };
+    Ok(argument)
+}
+
+fn read_simple(reader: &mut impl io::Read, info: u8) -> Result<Value> {
+    let value = match info {
+        20 => Value::Bool(false),
+        21 => Value::Bool(true),
+        22 | 23 => Value::Null,
+        25 => Value::Float(half_to_f64(u16::unpack_from(reader)?)),
+        26 => Value::Float(f32::unpack_from(reader)?.into()),
+        27 => Value::Float(f64::unpack_from(reader)?),
+        info => {
+            return Err(Error::InvalidValue(format!(
+                "unsupported CBOR simple value {}",
+                info
+            )))
+        }
+    };
+    Ok(value)
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let exponent = i32::from((bits >> 10) & 0x1F);
+    let mantissa = f64::from(bits & 0x03FF);
+    let value = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        exponent => (mantissa + 1024.0) * 2f64.powi(exponent - 25),
+    };
+
+    if bits & 0x8000 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+/// Returns the half precision bits of a float if they represent it exactly
+fn half_from_f64(value: f64) -> Option<u16> {
+    let sign = if value.is_sign_negative() { 0x8000 } else { 0 };
+    let magnitude = value.abs();
+
+    let bits = if value.is_nan() {
+        return None;
+    } else if magnitude == 0.0 {
+        sign
+    } else if magnitude.is_infinite() {
+        sign | 0x7C00
+    } else if magnitude < 2f64.powi(-14) {
+        // subnormal half floats are multiples of 2^-24
+        let mantissa = magnitude * 2f64.powi(24);
+        if mantissa.fract() != 0.0 {
+            return None;
+        }
+        sign | mantissa as u16
+    } else {
+        let exponent = ((magnitude.to_bits() >> 52) & 0x07FF) as i32 - 1023;
+        if exponent > 15 {
+            return None;
+        }
+        let mantissa = (magnitude / 2f64.powi(exponent) - 1.0) * 1024.0;
+        if mantissa.fract() != 0.0 {
+            return None;
+        }
+        sign | (((exponent + 15) as u16) << 10) | mantissa as u16
+    };
+
+    Some(bits)
+}
+
+fn length(argument: u64) -> Result<usize> {
+    usize::try_from(argument)
+        .map_err(|_| Error::InvalidValue(format!("CBOR length {} is too large", argument)))
+}
+
+fn out_of_range(value: i128) -> Error {
+    Error::InvalidValue(format!("{} cannot be represented in CBOR", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: Value) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let written = write_value(&value, &mut bytes).unwrap();
+        assert_eq!(written, bytes.len());
+        assert_eq!(read_value(&mut bytes.as_slice()).unwrap(), value);
+        bytes
+    }
+
+    #[test]
+    fn encode_integers() {
+        assert_eq!(round_trip(Value::Int(10)), [0x0A]);
+        assert_eq!(round_trip(Value::Int(-1)), [0x20]);
+        assert_eq!(round_trip(Value::Int(1000)), [0x19, 0x03, 0xE8]);
+        assert_eq!(round_trip(Value::Int(-(1 << 64))).len(), 9);
+    }
+
+    #[test]
+    fn encode_floats() {
+        assert_eq!(
+            round_trip(Value::Float(1.5)),
+            [0xFA, 0x3F, 0xC0, 0x00, 0x00]
+        );
+        assert_eq!(round_trip(Value::Float(0.1)).len(), 9);
+    }
+
+    #[test]
+    fn encode_canonical_floats() {
+        fn canonical(value: f64) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            write_value_canonical(&Value::Float(value), &mut bytes).unwrap();
+            bytes
+        }
+
+        assert_eq!(canonical(0.0), [0xF9, 0x00, 0x00]);
+        assert_eq!(canonical(-0.0), [0xF9, 0x80, 0x00]);
+        assert_eq!(canonical(1.5), [0xF9, 0x3E, 0x00]);
+        assert_eq!(canonical(65504.0), [0xF9, 0x7B, 0xFF]);
+        assert_eq!(canonical(5.960464477539063e-8), [0xF9, 0x00, 0x01]);
+        assert_eq!(canonical(f64::NEG_INFINITY), [0xF9, 0xFC, 0x00]);
+        assert_eq!(canonical(f64::NAN), [0xF9, 0x7E, 0x00]);
+        assert_eq!(canonical(-f64::NAN), [0xF9, 0x7E, 0x00]);
+        assert_eq!(canonical(100000.0), [0xFA, 0x47, 0xC3, 0x50, 0x00]);
+        assert_eq!(canonical(1.1).len(), 9);
+
+        for value in [1.0, -4.0, 0.00006103515625, 1.1920928955078125e-7] {
+            let bytes = canonical(value);
+            assert_eq!(bytes.len(), 3);
+            assert_eq!(
+                read_value(&mut bytes.as_slice()).unwrap(),
+                Value::Float(value)
+            );
+        }
+    }
+
+    #[test]
+    fn decode_half_float() {
+        let value = read_value(&mut [0xF9, 0x3C, 0x00].as_ref()).unwrap();
+        assert_eq!(value, Value::Float(1.0));
+        let value = read_value(&mut [0xF9, 0xC4, 0x00].as_ref()).unwrap();
+        assert_eq!(value, Value::Float(-4.0));
+    }
+
+    #[test]
+    fn canonical_sorts_keys() {
+        let value = Value::Map(vec![
+            (Value::Str("bb".into()), Value::Null),
+            (Value::Str("a".into()), Value::Bool(true)),
+        ]);
+        let mut bytes = Vec::new();
+        write_value_canonical(&value, &mut bytes).unwrap();
+        assert_eq!(bytes, [0xA2, 0x61, 0x61, 0xF5, 0x62, 0x62, 0x62, 0xF6]);
+    }
+
+    #[test]
+    fn struct_round_trip() {
+        let schema = Schema::structure("Tagged")
+            .field("name", Schema::String)
+            .field("raw", Schema::sequence(Schema::U8));
+        let mut packed = "id".pack_to_vec().unwrap();
+        [1u8, 2].pack_into(&mut packed).unwrap();
+
+        let value = Value::unpack_with_schema(&schema, &mut packed.as_slice()).unwrap();
+        let mut bytes = Vec::new();
+        write_value(&value, &mut bytes).unwrap();
+        assert_eq!(from_cbor_with_schema(&schema, &bytes).unwrap(), packed);
+    }
+
+    #[test]
+    fn reject_indefinite_length() {
+        let result = read_value(&mut [0x9F, 0xFF].as_ref());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn read_deeply_nested_array() {
+        let bytes = vec![0x81; 100_000];
+        let result = read_value(&mut bytes.as_slice());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
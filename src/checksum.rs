@@ -0,0 +1,260 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use std::fmt::{self, Debug, Formatter};
+use std::hash::Hasher;
+use std::io;
+use std::marker::PhantomData;
+
+/// Describes a checksum computed incrementally over a sequence of bytes
+pub trait Checksum: Default {
+    /// Type of the final checksum, which is packed behind the payload
+    type Value: Pack + Unpack + PartialEq;
+
+    /// Adds bytes to the checksum
+    fn update(&mut self, bytes: &[u8]);
+
+    /// Returns the checksum over all bytes added so far
+    fn finish(&self) -> Self::Value;
+}
+
+/// CRC-32 checksum (IEEE 802.3, as used by zlib and PNG)
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32 {
+    state: u32,
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            value = if value & 1 == 1 {
+                (value >> 1) ^ 0xEDB8_8320
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+
+        table[index] = value;
+        index += 1;
+    }
+
+    table
+};
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self { state: 0xFFFF_FFFF }
+    }
+}
+
+impl Checksum for Crc32 {
+    type Value = u32;
+
+    fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            let index = (self.state ^ *byte as u32) & 0xFF;
+            self.state = (self.state >> 8) ^ CRC32_TABLE[index as usize];
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+/// Wrapper that packs a value followed by a checksum over its packed bytes
+///
+/// The checksum is verified on unpacking, which fails with
+/// `Error::ChecksumMismatch` if the payload has been corrupted. The payload
+/// keeps its usual wire format, only the checksum is appended
+pub struct Checksummed<T, C = Crc32> {
+    pub value: T,
+    marker: PhantomData<fn() -> C>,
+}
+
+impl<T, C> Checksummed<T, C> {
+    /// Wraps a value to be packed with a checksum
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Clone, C> Clone for Checksummed<T, C> {
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<T: Debug, C> Debug for Checksummed<T, C> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_tuple("Checksummed")
+            .field(&self.value)
+            .finish()
+    }
+}
+
+impl<T: Pack, C: Checksum> Pack for Checksummed<T, C> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut hashing = Hashing {
+            inner: writer,
+            checksum: C::default(),
+        };
+        let written = self.value.pack_into(&mut hashing)?;
+        let checksum = hashing.checksum.finish();
+        Ok(written + checksum.pack_into(hashing.inner)?)
+    }
+}
+
+impl<T: Unpack, C: Checksum> Unpack for Checksummed<T, C> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut hashing = Hashing {
+            inner: reader,
+            checksum: C::default(),
+        };
+        let value = T::unpack_from(&mut hashing)?;
+        let expected = hashing.checksum.finish();
+
+        if C::Value::unpack_from(hashing.inner)? != expected {
+            return Err(Error::ChecksumMismatch);
+        }
+
+        Ok(Self::new(value))
+    }
+}
+
+/// Writer feeding all bytes into a `Hasher` instead of storing them
+#[derive(Debug, Clone, Default)]
+pub struct HashWriter<H> {
+    hasher: H,
+}
+
+impl<H: Hasher> HashWriter<H> {
+    /// Creates a writer feeding the given hasher
+    pub fn new(hasher: H) -> Self {
+        Self { hasher }
+    }
+
+    /// Returns the hash of all bytes written so far
+    pub fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    /// Returns the hasher
+    pub fn into_inner(self) -> H {
+        self.hasher
+    }
+}
+
+impl<H: Hasher> io::Write for HashWriter<H> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.hasher.write(buffer);
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hashes the packed representation of a value without materializing it
+///
+/// Equal values of the same type are always packed with the same sequence
+/// of writes and therefore result in equal hashes. For content-addressed
+/// storage use a hasher whose output is stable across releases and
+/// platforms, which `DefaultHasher` is not
+pub fn hash_packed<H: Hasher + Default, T: Pack + ?Sized>(value: &T) -> pack::Result<u64> {
+    let mut writer = HashWriter::new(H::default());
+    value.pack_into(&mut writer)?;
+    Ok(writer.finish())
+}
+
+/// Reader or writer updating a checksum with every byte passing through
+struct Hashing<'a, S, C> {
+    inner: &'a mut S,
+    checksum: C,
+}
+
+impl<R: io::Read, C: Checksum> io::Read for Hashing<'_, R, C> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buffer)?;
+        self.checksum.update(&buffer[..len]);
+        Ok(len)
+    }
+}
+
+impl<W: io::Write, C: Checksum> io::Write for Hashing<'_, W, C> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buffer)?;
+        self.checksum.update(&buffer[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        let mut checksum = Crc32::default();
+        checksum.update(b"12345");
+        checksum.update(b"6789");
+        assert_eq!(checksum.finish(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn pack_checksummed() {
+        let bytes = Checksummed::<_>::new(2u16).pack_to_vec().unwrap();
+        let mut checksum = Crc32::default();
+        checksum.update(&[0x00, 0x02]);
+        let mut expected = vec![0x00, 0x02];
+        expected.extend_from_slice(&checksum.finish().to_be_bytes());
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn checksummed_round_trip() {
+        let bytes = Checksummed::<_>::new("abc").pack_to_vec().unwrap();
+        let value = Checksummed::<String>::unpack_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(value.into_inner(), "abc");
+    }
+
+    #[test]
+    fn unpack_corrupted() {
+        let mut bytes = Checksummed::<_>::new("abc").pack_to_vec().unwrap();
+        bytes[5] ^= 0x01;
+        let result = Checksummed::<String>::unpack_from(&mut bytes.as_slice());
+        assert!(matches!(result, Err(Error::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn hash_packed_bytes() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let mut expected = DefaultHasher::new();
+        expected.write(&[0x00, 0x00, 0x00, 0x02]);
+        expected.write(b"ab");
+        let hash = hash_packed::<DefaultHasher, _>("ab").unwrap();
+        assert_eq!(hash, expected.finish());
+        assert_ne!(hash, hash_packed::<DefaultHasher, _>("ac").unwrap());
+    }
+}
@@ -0,0 +1,391 @@
+//! Splitting of large packed values into chunks of limited size
+//!
+//! Transports like UDP or message queues limit the size of a single
+//! message. [`split`] packs a value and cuts it into [`Chunk`]s that each fit
+//! into such a message, a [`Reassembler`] on the receiving side collects the
+//! chunks in any order and unpacks the value once all of them arrived
+//!
+//! ```
+//! use serial_container::chunk::{self, Reassembler};
+//!
+//! let value = [7u32; 100];
+//! let chunks = chunk::split(&value, 1, 64).unwrap();
+//! assert_eq!(chunks.len(), 9);
+//!
+//! let mut reassembler = Reassembler::<Vec<u32>>::new();
+//! let mut result = None;
+//! for chunk in chunks.into_iter().rev() {
+//!     result = reassembler.push(chunk).unwrap();
+//! }
+//! assert_eq!(result.unwrap(), value);
+//! ```
+
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io;
+use std::marker::PhantomData;
+
+/// Number of bytes every packed chunk occupies in addition to its payload
+pub const HEADER_SIZE: usize = 16;
+
+/// Part of a packed value together with its position
+///
+/// Packed as the message id, the index of the chunk, the total number of
+/// chunks (`u32` each) and the length-prefixed payload
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chunk {
+    pub message_id: u32,
+    pub index: u32,
+    pub count: u32,
+    pub payload: Vec<u8>,
+}
+
+impl Pack for Chunk {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = self.message_id.pack_into(writer)?;
+        written += self.index.pack_into(writer)?;
+        written += self.count.pack_into(writer)?;
+        written += self.payload.as_slice().pack_into(writer)?;
+        Ok(written)
+    }
+}
+
+impl Unpack for Chunk {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let message_id = u32::unpack_from(reader)?;
+        let index = u32::unpack_from(reader)?;
+        let count = u32::unpack_from(reader)?;
+        let len = u32::unpack_from(reader)? as usize;
+
+        Ok(Chunk {
+            message_id,
+            index,
+            count,
+            payload: read_bytes(reader, len)?,
+        })
+    }
+}
+
+/// Packs a value and splits it into chunks whose packed size does not
+/// exceed `max_size`
+///
+/// Fails with `ErrorKind::InvalidInput` if `max_size` leaves no room for a
+/// payload besides the header and with `Error::LengthOverflow` if the value
+/// needs more than `u32::MAX` chunks. A value packing to zero bytes still
+/// results in a single empty chunk
+pub fn split<T: Pack + ?Sized>(
+    value: &T,
+    message_id: u32,
+    max_size: usize,
+) -> pack::Result<Vec<Chunk>> {
+    if max_size <= HEADER_SIZE {
+        return Err(pack::Error::IO(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("chunks need to be larger than {} bytes", HEADER_SIZE),
+        )));
+    }
+
+    let bytes = value.pack_to_vec()?;
+    let mut parts = bytes
+        .chunks(max_size - HEADER_SIZE)
+        .map(<[u8]>::to_vec)
+        .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        parts.push(Vec::new());
+    }
+
+    let count = checked_len(parts.len())?;
+
+    Ok(parts
+        .into_iter()
+        .zip(0..count)
+        .map(|(payload, index)| Chunk {
+            message_id,
+            index,
+            count,
+            payload,
+        })
+        .collect())
+}
+
+/// Default limit of messages a [`Reassembler`] collects chunks for at once
+pub const DEFAULT_MAX_PENDING: usize = 64;
+
+/// Default limit of chunks a single message may consist of
+pub const DEFAULT_MAX_CHUNKS: u32 = 4096;
+
+/// Default limit of payload bytes a [`Reassembler`] buffers in total
+pub const DEFAULT_MAX_BUFFERED: usize = 16 * 1024 * 1024;
+
+/// Bounds of the state a [`Reassembler`] keeps for incomplete messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Number of incomplete messages, the oldest one is evicted to make room
+    /// for a new message
+    pub max_pending: usize,
+    /// Number of chunks per message, chunks of larger messages are rejected
+    pub max_chunks: u32,
+    /// Number of payload bytes of all incomplete messages, the oldest
+    /// messages are evicted to make room for a new chunk
+    pub max_buffered: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_pending: DEFAULT_MAX_PENDING,
+            max_chunks: DEFAULT_MAX_CHUNKS,
+            max_buffered: DEFAULT_MAX_BUFFERED,
+        }
+    }
+}
+
+/// Collector of chunks which unpacks values once they are complete
+///
+/// Chunks of several messages may be interleaved and arrive in any order.
+/// Duplicated chunks are ignored. The buffered chunks are bounded by
+/// [`Limits`], so peers sending incomplete messages can not exhaust the
+/// memory of the receiver
+pub struct Reassembler<T> {
+    pending: HashMap<u32, Pending>,
+    order: VecDeque<u32>,
+    buffered: usize,
+    limits: Limits,
+    marker: PhantomData<fn() -> T>,
+}
+
+struct Pending {
+    count: u32,
+    size: usize,
+    parts: BTreeMap<u32, Vec<u8>>,
+}
+
+impl<T: Unpack> Reassembler<T> {
+    /// Creates a reassembler without any pending messages and the default
+    /// [`Limits`]
+    pub fn new() -> Self {
+        Self::with_limits(Limits::default())
+    }
+
+    /// Creates a reassembler without any pending messages and the given
+    /// limits
+    pub fn with_limits(limits: Limits) -> Self {
+        Self {
+            pending: HashMap::new(),
+            order: VecDeque::new(),
+            buffered: 0,
+            limits,
+            marker: PhantomData,
+        }
+    }
+
+    /// Adds a chunk and returns the unpacked value if it completed its
+    /// message
+    ///
+    /// Fails with `Error::InvalidValue` if the chunk contradicts previous
+    /// chunks of the same message or its message has more chunks than
+    /// allowed, and with `Error::LimitExceeded` if the chunks of its message
+    /// do not fit into the buffer. In both cases the message is discarded
+    pub fn push(&mut self, chunk: Chunk) -> Result<Option<T>> {
+        if chunk.index >= chunk.count {
+            return Err(Error::InvalidValue(format!(
+                "chunk {} of {} is out of range",
+                chunk.index, chunk.count
+            )));
+        }
+
+        if chunk.count > self.limits.max_chunks {
+            self.discard(chunk.message_id);
+            return Err(Error::InvalidValue(format!(
+                "message {} has {} chunks, but at most {} are allowed",
+                chunk.message_id, chunk.count, self.limits.max_chunks
+            )));
+        }
+
+        if !self.pending.contains_key(&chunk.message_id) {
+            while self.pending.len() >= self.limits.max_pending.max(1) {
+                self.evict_oldest();
+            }
+
+            self.order.push_back(chunk.message_id);
+            self.pending.insert(
+                chunk.message_id,
+                Pending {
+                    count: chunk.count,
+                    size: 0,
+                    parts: BTreeMap::new(),
+                },
+            );
+        }
+
+        if self.pending[&chunk.message_id].count != chunk.count {
+            self.discard(chunk.message_id);
+            return Err(Error::InvalidValue(format!(
+                "chunk count of message {} changed",
+                chunk.message_id
+            )));
+        }
+
+        if !self.pending[&chunk.message_id]
+            .parts
+            .contains_key(&chunk.index)
+        {
+            while self.buffered + chunk.payload.len() > self.limits.max_buffered {
+                if self.evict_oldest() == Some(chunk.message_id) {
+                    return Err(Error::LimitExceeded {
+                        limit: self.limits.max_buffered,
+                    });
+                }
+            }
+
+            let pending = self.pending.get_mut(&chunk.message_id).unwrap();
+            self.buffered += chunk.payload.len();
+            pending.size += chunk.payload.len();
+            pending.parts.insert(chunk.index, chunk.payload);
+        }
+
+        let pending = &self.pending[&chunk.message_id];
+        if pending.parts.len() < pending.count as usize {
+            return Ok(None);
+        }
+
+        let pending = self.remove(chunk.message_id).unwrap();
+        let bytes = pending.parts.into_values().flatten().collect::<Vec<_>>();
+        T::unpack_exact(&bytes).map(Some)
+    }
+
+    /// Unpacks a chunk from a received message and adds it
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<Option<T>> {
+        self.push(Chunk::unpack_exact(bytes)?)
+    }
+
+    /// Returns the number of messages waiting for further chunks
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns the number of payload bytes buffered for incomplete messages
+    pub fn buffered(&self) -> usize {
+        self.buffered
+    }
+
+    /// Discards the chunks received so far for a message, e.g. after a
+    /// timeout
+    pub fn discard(&mut self, message_id: u32) -> bool {
+        self.remove(message_id).is_some()
+    }
+
+    fn remove(&mut self, message_id: u32) -> Option<Pending> {
+        let pending = self.pending.remove(&message_id)?;
+        self.order.retain(|id| *id != message_id);
+        self.buffered -= pending.size;
+        Some(pending)
+    }
+
+    fn evict_oldest(&mut self) -> Option<u32> {
+        let message_id = *self.order.front()?;
+        self.remove(message_id);
+        Some(message_id)
+    }
+}
+
+impl<T: Unpack> Default for Reassembler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_fit_into_limit() {
+        let chunks = split(&[1u8; 50], 9, 30).unwrap();
+        assert_eq!(chunks.len(), 4);
+
+        for chunk in &chunks {
+            assert!(chunk.pack_to_vec().unwrap().len() <= 30);
+            assert_eq!((chunk.message_id, chunk.count), (9, 4));
+        }
+
+        assert!(split(&1u8, 0, HEADER_SIZE).is_err());
+    }
+
+    #[test]
+    fn interleaved_messages() {
+        let first = split("first message", 1, 20).unwrap();
+        let second = split("second message", 2, 20).unwrap();
+        let mut reassembler = Reassembler::<String>::new();
+
+        for (left, right) in first.iter().zip(&second).skip(1) {
+            assert!(reassembler.push(left.clone()).unwrap().is_none());
+            let bytes = right.pack_to_vec().unwrap();
+            assert!(reassembler.push_bytes(&bytes).unwrap().is_none());
+        }
+
+        assert_eq!(reassembler.pending(), 2);
+        assert!(reassembler.push(second[1].clone()).unwrap().is_none());
+        let value = reassembler.push(second[0].clone()).unwrap();
+        assert_eq!(value.unwrap(), "second message");
+        assert!(reassembler.discard(1));
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn contradicting_chunks() {
+        let mut reassembler = Reassembler::<String>::new();
+        let mut chunks = split("abcdefgh", 1, 20).unwrap();
+        reassembler.push(chunks.remove(0)).unwrap();
+
+        let mut chunk = chunks.remove(0);
+        chunk.count += 1;
+        let result = reassembler.push(chunk);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn limit_chunks_per_message() {
+        let limits = Limits {
+            max_chunks: 2,
+            ..Limits::default()
+        };
+        let mut reassembler = Reassembler::<String>::with_limits(limits);
+        let chunks = split("abcdefgh", 1, 20).unwrap();
+        assert_eq!(chunks.len(), 3);
+
+        let result = reassembler.push(chunks[0].clone());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn evict_oldest_messages() {
+        let limits = Limits {
+            max_pending: 2,
+            max_buffered: 10,
+            ..Limits::default()
+        };
+        let mut reassembler = Reassembler::<String>::with_limits(limits);
+        let first = split("abcdefgh", 1, 20).unwrap();
+        let second = split("abcdefgh", 2, 20).unwrap();
+        let third = split("abcdefgh", 3, 20).unwrap();
+
+        reassembler.push(first[0].clone()).unwrap();
+        reassembler.push(second[0].clone()).unwrap();
+        reassembler.push(third[0].clone()).unwrap();
+        assert_eq!(reassembler.pending(), 2);
+        assert!(!reassembler.discard(1));
+
+        reassembler.push(third[1].clone()).unwrap();
+        assert_eq!(reassembler.buffered(), 8);
+        assert!(!reassembler.discard(2));
+
+        let result = reassembler.push(third[2].clone());
+        assert!(matches!(result, Err(Error::LimitExceeded { limit: 10 })));
+        assert_eq!((reassembler.pending(), reassembler.buffered()), (0, 0));
+    }
+}
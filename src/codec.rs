@@ -0,0 +1,184 @@
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use bytes::{Buf, BufMut, BytesMut};
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::marker::PhantomData;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Default limit for the payload length of a single frame (8 MiB)
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// Codec for sending and receiving packed values over a `tokio_util` transport
+///
+/// Every value is sent as a frame consisting of the payload length as `u32`
+/// followed by the packed value. Frames announcing a payload longer than the
+/// configured maximum are rejected before any buffer is reserved for them
+pub struct StackerCodec<T> {
+    max_frame_length: usize,
+    marker: PhantomData<fn(T) -> T>,
+}
+
+impl<T> StackerCodec<T> {
+    /// Creates a codec accepting frames up to [`DEFAULT_MAX_FRAME_LENGTH`]
+    pub fn new() -> Self {
+        Self::with_max_frame_length(DEFAULT_MAX_FRAME_LENGTH)
+    }
+
+    /// Creates a codec accepting frames up to the given payload length
+    pub fn with_max_frame_length(max_frame_length: usize) -> Self {
+        Self {
+            max_frame_length,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the maximum payload length of a single frame
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+}
+
+impl<T> Default for StackerCodec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for StackerCodec<T> {
+    fn clone(&self) -> Self {
+        Self::with_max_frame_length(self.max_frame_length)
+    }
+}
+
+impl<T> Debug for StackerCodec<T> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_struct("StackerCodec")
+            .field("max_frame_length", &self.max_frame_length)
+            .finish()
+    }
+}
+
+fn frame_too_long(len: usize, max: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "frame of {} bytes exceeds the maximum of {} bytes",
+            len, max
+        ),
+    )
+}
+
+impl<T: Pack> Encoder<T> for StackerCodec<T> {
+    type Error = pack::Error;
+
+    fn encode(&mut self, item: T, destination: &mut BytesMut) -> pack::Result<()> {
+        let start = destination.len();
+        destination.put_u32(0);
+        item.pack_into(&mut destination.writer())?;
+
+        let len = destination.len() - start - 4;
+        if len > self.max_frame_length {
+            destination.truncate(start);
+            return Err(pack::Error::IO(frame_too_long(len, self.max_frame_length)));
+        }
+
+        let prefix = checked_len(len).inspect_err(|_| destination.truncate(start))?;
+        destination[start..start + 4].copy_from_slice(&prefix.to_be_bytes());
+        Ok(())
+    }
+}
+
+impl<T: Unpack> Decoder for StackerCodec<T> {
+    type Item = T;
+    type Error = Error;
+
+    fn decode(&mut self, source: &mut BytesMut) -> Result<Option<T>> {
+        if source.len() < 4 {
+            return Ok(None);
+        }
+
+        let mut header = [0x00; 4];
+        header.copy_from_slice(&source[..4]);
+        let len = u32::from_be_bytes(header) as usize;
+
+        if len > self.max_frame_length {
+            return Err(Error::IO(frame_too_long(len, self.max_frame_length)));
+        }
+
+        if source.len() < 4 + len {
+            source.reserve(4 + len - source.len());
+            return Ok(None);
+        }
+
+        source.advance(4);
+        let frame = source.split_to(len);
+        let mut payload = frame.as_ref();
+        let value = T::unpack_from(&mut payload)?;
+
+        if !payload.is_empty() {
+            return Err(Error::TrailingBytes(payload.len()));
+        }
+
+        Ok(Some(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_frame() {
+        let mut codec = StackerCodec::new();
+        let mut buffer = BytesMut::new();
+        codec.encode(2u16, &mut buffer).unwrap();
+        codec.encode(3u16, &mut buffer).unwrap();
+        assert_eq!(
+            buffer.as_ref(),
+            [0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03]
+        );
+    }
+
+    #[test]
+    fn encode_frame_too_long() {
+        let mut codec = StackerCodec::with_max_frame_length(1);
+        let mut buffer = BytesMut::new();
+        assert!(codec.encode(2u16, &mut buffer).is_err());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn decode_frame() {
+        let mut codec = StackerCodec::<u16>::new();
+        let mut buffer = BytesMut::from(&[0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00][..]);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(2));
+        assert_eq!(buffer.as_ref(), [0x00]);
+    }
+
+    #[test]
+    fn decode_partial_frame() {
+        let mut codec = StackerCodec::<u16>::new();
+        let mut buffer = BytesMut::from(&[0x00, 0x00, 0x00, 0x02, 0x00][..]);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+
+        buffer.put_u8(0x02);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn decode_frame_with_trailing_bytes() {
+        let mut codec = StackerCodec::<u8>::new();
+        let mut buffer = BytesMut::from(&[0x00, 0x00, 0x00, 0x02, 0x00, 0x02][..]);
+        let result = codec.decode(&mut buffer);
+        assert!(matches!(result, Err(Error::TrailingBytes(1))));
+    }
+
+    #[test]
+    fn decode_frame_too_long() {
+        let mut codec = StackerCodec::<Vec<u8>>::with_max_frame_length(16);
+        let mut buffer = BytesMut::from(&[0x00, 0x00, 0x01, 0x00][..]);
+        assert!(codec.decode(&mut buffer).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! Columnar encoding of sequences of structs
+//!
+//! [`Columnar`] packs a `Vec` of structs as a struct of arrays: all values
+//! of the first field, then all values of the second field and so on. Similar
+//! values end up next to each other, which compresses far better, and
+//! single columns can be read with [`unpack_column`] without decoding the
+//! others
+//!
+//! The fields are located by the [`Schema`] of the struct, so the struct has
+//! to implement [`Describe`] in accordance with its `Pack` and `Unpack`
+//! implementations. The encoding consists of the number of rows (`u32`) and
+//! the number of columns (`u32`), followed by every column as its length in
+//! bytes (`u32`) and the packed field values
+
+use crate::pack::{self, checked_len, Pack};
+use crate::schema::{Describe, Schema};
+use crate::skip::{skip_with_schema, Reading};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::io::{self, Read};
+
+/// Wrapper packing a sequence of structs column by column
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Columnar<T>(pub Vec<T>);
+
+impl<T: Pack + Describe> Pack for Columnar<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let schema = T::schema();
+        let fields =
+            fields(&schema).map_err(|error| pack::Error::InvalidValue(error.to_string()))?;
+        let mut columns = vec![Vec::new(); fields.len()];
+        let mut row = Vec::new();
+
+        for value in &self.0 {
+            row.clear();
+            value.pack_into(&mut row)?;
+            let mut remaining = row.as_slice();
+
+            for ((_, schema), column) in fields.iter().zip(&mut columns) {
+                let start = row.len() - remaining.len();
+                skip_with_schema(schema, &mut Reading(&mut remaining))
+                    .map_err(|error| pack::Error::InvalidValue(error.to_string()))?;
+                column.extend_from_slice(&row[start..row.len() - remaining.len()]);
+            }
+        }
+
+        let mut written = checked_len(self.0.len())?.pack_into(writer)?;
+        written += checked_len(columns.len())?.pack_into(writer)?;
+
+        for column in &columns {
+            written += checked_len(column.len())?.pack_into(writer)?;
+            writer.write_all(column)?;
+            written += column.len();
+        }
+
+        Ok(written)
+    }
+}
+
+impl<T: Unpack + Describe> Unpack for Columnar<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let schema = T::schema();
+        let fields = fields(&schema)?;
+        let rows = read_header(reader, fields.len())?;
+        let mut columns = Vec::with_capacity(fields.len());
+
+        for _field in fields {
+            let len = u32::unpack_from(reader)? as usize;
+            columns.push(read_bytes(reader, len)?);
+        }
+
+        let mut cursors: Vec<&[u8]> = columns.iter().map(Vec::as_slice).collect();
+        let mut values = Vec::new();
+        let mut row = Vec::new();
+
+        for _i in 0..rows {
+            row.clear();
+
+            for ((_, schema), cursor) in fields.iter().zip(&mut cursors) {
+                let start = *cursor;
+                let len = skip_with_schema(schema, &mut Reading(cursor))? as usize;
+                row.extend_from_slice(&start[..len]);
+            }
+
+            values.push(T::unpack_exact(&row)?);
+        }
+
+        if let Some(remaining) = cursors.iter().find(|cursor| !cursor.is_empty()) {
+            return Err(Error::TrailingBytes(remaining.len()));
+        }
+
+        Ok(Columnar(values))
+    }
+}
+
+/// Unpacks the values of a single field from a [`Columnar`] sequence of
+/// structs of type `T`, skipping all other columns
+///
+/// Fails with `Error::InvalidValue` if `T` has no field of the given name
+pub fn unpack_column<T: Describe + ?Sized, F: Unpack>(
+    reader: &mut impl io::Read,
+    name: &str,
+) -> Result<Vec<F>> {
+    let schema = T::schema();
+    let fields = fields(&schema)?;
+    let index = fields
+        .iter()
+        .position(|(field, _)| field == name)
+        .ok_or_else(|| Error::InvalidValue(format!("unknown field {}", name)))?;
+    let rows = read_header(reader, fields.len())?;
+    let mut values = Vec::new();
+
+    for current in 0..fields.len() {
+        let len = u32::unpack_from(reader)? as u64;
+
+        if current != index {
+            let skipped = io::copy(&mut reader.by_ref().take(len), &mut io::sink())?;
+
+            if skipped < len {
+                return Err(Error::IO(io::ErrorKind::UnexpectedEof.into()));
+            }
+
+            continue;
+        }
+
+        let column = read_bytes(reader, len as usize)?;
+        let mut remaining = column.as_slice();
+
+        for _i in 0..rows {
+            values.push(F::unpack_from(&mut remaining)?);
+        }
+
+        if !remaining.is_empty() {
+            return Err(Error::TrailingBytes(remaining.len()));
+        }
+    }
+
+    Ok(values)
+}
+
+fn fields(schema: &Schema) -> Result<&[(String, Schema)]> {
+    match schema {
+        Schema::Struct { fields, .. } => Ok(fields),
+        _ => Err(Error::InvalidValue(String::from(
+            "only structs can be packed column by column",
+        ))),
+    }
+}
+
+/// Reads the number of rows and checks the number of columns
+fn read_header(reader: &mut impl io::Read, expected: usize) -> Result<u32> {
+    let rows = u32::unpack_from(reader)?;
+    let found = u32::unpack_from(reader)? as usize;
+
+    if found != expected {
+        return Err(Error::LengthMismatch { expected, found });
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Sample {
+        sensor: u16,
+        label: String,
+        value: f32,
+    }
+
+    impl Pack for Sample {
+        fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+            let mut written = self.sensor.pack_into(writer)?;
+            written += self.label.as_str().pack_into(writer)?;
+            written += self.value.pack_into(writer)?;
+            Ok(written)
+        }
+    }
+
+    impl Unpack for Sample {
+        fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+            Ok(Sample {
+                sensor: u16::unpack_from(reader)?,
+                label: String::unpack_from(reader)?,
+                value: f32::unpack_from(reader)?,
+            })
+        }
+    }
+
+    impl Describe for Sample {
+        fn schema() -> Schema {
+            Schema::structure("Sample")
+                .field("sensor", Schema::U16)
+                .field("label", Schema::String)
+                .field("value", Schema::F32)
+        }
+    }
+
+    fn samples() -> Columnar<Sample> {
+        Columnar(vec![
+            Sample {
+                sensor: 1,
+                label: "a".into(),
+                value: 0.5,
+            },
+            Sample {
+                sensor: 2,
+                label: "bc".into(),
+                value: 1.5,
+            },
+        ])
+    }
+
+    #[test]
+    fn pack_columns() {
+        let bytes = samples().pack_to_vec().unwrap();
+        assert_eq!(
+            bytes[..16],
+            [0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0x00, 0x01, 0x00, 0x02]
+        );
+        assert_eq!(Columnar::<Sample>::unpack_exact(&bytes).unwrap(), samples());
+    }
+
+    #[test]
+    fn unpack_single_column() {
+        let bytes = samples().pack_to_vec().unwrap();
+        let mut reader = bytes.as_slice();
+        let labels: Vec<String> = unpack_column::<Sample, _>(&mut reader, "label").unwrap();
+        assert_eq!(labels, ["a", "bc"]);
+        assert!(reader.is_empty());
+
+        let values: Vec<f32> = unpack_column::<Sample, _>(&mut bytes.as_slice(), "value").unwrap();
+        assert_eq!(values, [0.5, 1.5]);
+    }
+
+    #[test]
+    fn column_count_mismatch() {
+        let bytes = [0, 0, 0, 0, 0, 0, 0, 2];
+        let result = Columnar::<Sample>::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::LengthMismatch { .. })));
+    }
+}
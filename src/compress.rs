@@ -0,0 +1,320 @@
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Read};
+use std::marker::PhantomData;
+
+/// Default limit of bytes a compressed payload may expand to when unpacking
+pub const DEFAULT_MAX_LEN: usize = 64 * 1024 * 1024;
+
+/// Describes a compression algorithm usable with [`Compressed`]
+pub trait Algorithm {
+    /// Identifier of the algorithm, which is recorded in the header
+    const ID: u8;
+
+    /// Upper bound for the uncompressed length accepted when unpacking
+    ///
+    /// The length is read from untrusted input and a few megabytes of
+    /// compressed data can expand to gigabytes, so longer payloads are
+    /// rejected before anything is decompressed
+    const MAX_LEN: usize = DEFAULT_MAX_LEN;
+
+    /// Reader yielding the decompressed bytes of a compressed bytesource
+    type Decoder<R: io::Read>: io::Read;
+
+    /// Compresses a whole buffer
+    fn compress(bytes: &[u8]) -> io::Result<Vec<u8>>;
+
+    /// Wraps a bytesource of compressed bytes that expand to `len` bytes
+    ///
+    /// The length is read from untrusted input, so implementations must not
+    /// allocate `len` bytes before checking that the input can expand to it
+    fn decoder<R: io::Read>(reader: R, len: usize) -> io::Result<Self::Decoder<R>>;
+}
+
+/// Deflate compression (RFC 1951) at the default level
+///
+/// Unpacking accepts payloads expanding to at most `LIMIT` bytes
+#[cfg(feature = "flate2")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Deflate<const LIMIT: usize = DEFAULT_MAX_LEN>;
+
+#[cfg(feature = "flate2")]
+impl<const LIMIT: usize> Algorithm for Deflate<LIMIT> {
+    const ID: u8 = 1;
+    const MAX_LEN: usize = LIMIT;
+
+    type Decoder<R: io::Read> = flate2::read::DeflateDecoder<R>;
+
+    fn compress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+        use std::io::Write;
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(bytes)?;
+        encoder.finish()
+    }
+
+    fn decoder<R: io::Read>(reader: R, _len: usize) -> io::Result<Self::Decoder<R>> {
+        Ok(flate2::read::DeflateDecoder::new(reader))
+    }
+}
+
+/// LZ4 block compression
+///
+/// Unpacking accepts payloads expanding to at most `LIMIT` bytes. A block
+/// can only be decompressed as a whole, so the compressed bytes are
+/// buffered before decoding
+#[cfg(feature = "lz4_flex")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lz4<const LIMIT: usize = DEFAULT_MAX_LEN>;
+
+#[cfg(feature = "lz4_flex")]
+impl<const LIMIT: usize> Algorithm for Lz4<LIMIT> {
+    const ID: u8 = 2;
+    const MAX_LEN: usize = LIMIT;
+
+    type Decoder<R: io::Read> = io::Cursor<Vec<u8>>;
+
+    fn compress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(lz4_flex::block::compress(bytes))
+    }
+
+    fn decoder<R: io::Read>(mut reader: R, len: usize) -> io::Result<Self::Decoder<R>> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        // an LZ4 block expands by at most 255 times its size
+        if len > bytes.len().saturating_mul(255) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} bytes of LZ4 cannot expand to {} bytes",
+                    bytes.len(),
+                    len
+                ),
+            ));
+        }
+
+        lz4_flex::block::decompress(&bytes, len)
+            .map(io::Cursor::new)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// Zstandard compression at the default level
+///
+/// Unpacking accepts payloads expanding to at most `LIMIT` bytes
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Zstd<const LIMIT: usize = DEFAULT_MAX_LEN>;
+
+#[cfg(feature = "zstd")]
+impl<const LIMIT: usize> Algorithm for Zstd<LIMIT> {
+    const ID: u8 = 3;
+    const MAX_LEN: usize = LIMIT;
+
+    type Decoder<R: io::Read> = zstd::stream::read::Decoder<'static, io::BufReader<R>>;
+
+    fn compress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+        zstd::bulk::compress(bytes, 0)
+    }
+
+    fn decoder<R: io::Read>(reader: R, _len: usize) -> io::Result<Self::Decoder<R>> {
+        zstd::stream::read::Decoder::new(reader)
+    }
+}
+
+/// Wrapper that packs a value in compressed form
+///
+/// The packed value is preceded by a header consisting of the algorithm
+/// identifier as `u8`, the uncompressed length as `u32` and the compressed
+/// length as `u32`. Unpacking fails if the header names another algorithm,
+/// if the recorded length exceeds [`Algorithm::MAX_LEN`] or if the payload
+/// does not expand to exactly the recorded length. The value is unpacked
+/// while the payload is decompressed, without buffering it as a whole
+pub struct Compressed<T, A> {
+    pub value: T,
+    marker: PhantomData<fn() -> A>,
+}
+
+impl<T, A> Compressed<T, A> {
+    /// Wraps a value to be packed in compressed form
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Clone, A> Clone for Compressed<T, A> {
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<T: Debug, A> Debug for Compressed<T, A> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_tuple("Compressed")
+            .field(&self.value)
+            .finish()
+    }
+}
+
+impl<T: Pack, A: Algorithm> Pack for Compressed<T, A> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let payload = self.value.pack_to_vec()?;
+        let compressed = A::compress(&payload)?;
+
+        let mut written = A::ID.pack_into(writer)?;
+        written += checked_len(payload.len())?.pack_into(writer)?;
+        written += checked_len(compressed.len())?.pack_into(writer)?;
+        writer.write_all(&compressed)?;
+        Ok(written + compressed.len())
+    }
+}
+
+impl<T: Unpack, A: Algorithm> Unpack for Compressed<T, A> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let id = u8::unpack_from(reader)?;
+
+        if id != A::ID {
+            return Err(Error::InvalidValue(format!(
+                "expected compression algorithm {} but found {}",
+                A::ID,
+                id
+            )));
+        }
+
+        let len = u32::unpack_from(reader)? as usize;
+
+        if len > A::MAX_LEN {
+            return Err(Error::InvalidValue(format!(
+                "payload of {} bytes exceeds the limit of {} bytes",
+                len,
+                A::MAX_LEN
+            )));
+        }
+
+        let compressed_len = u32::unpack_from(reader)?;
+        let mut compressed = reader.take(compressed_len.into());
+        let mut decoder = A::decoder(&mut compressed, len)?;
+        let mut payload = io::BufReader::new((&mut decoder).take(len as u64));
+        let value = T::unpack_from_buffered(&mut payload)?;
+
+        let trailing = io::copy(&mut payload, &mut io::sink())?;
+        if trailing > 0 {
+            return Err(Error::TrailingBytes(trailing as usize));
+        }
+
+        let missing = payload.into_inner().limit() as usize;
+        if missing > 0 || decoder.read(&mut [0x00])? > 0 {
+            return Err(Error::InvalidValue(format!(
+                "payload does not expand to exactly {} bytes",
+                len
+            )));
+        }
+
+        // skip compressed bytes after the end of the stream
+        drop(decoder);
+        io::copy(&mut compressed, &mut io::sink())?;
+        Ok(Self::new(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<A: Algorithm>() {
+        let value: Vec<u16> = (0..512).map(|x| x % 7).collect();
+        let bytes = Compressed::<_, A>::new(value.as_slice())
+            .pack_to_vec()
+            .unwrap();
+        assert_eq!(bytes[0], A::ID);
+        assert!(bytes.len() < value.len() * 2);
+
+        let result = Compressed::<Vec<u16>, A>::unpack_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(result.into_inner(), value);
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn deflate_round_trip() {
+        round_trip::<Deflate>();
+    }
+
+    #[cfg(feature = "lz4_flex")]
+    #[test]
+    fn lz4_round_trip() {
+        round_trip::<Lz4>();
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trip() {
+        round_trip::<Zstd>();
+    }
+
+    #[cfg(all(feature = "flate2", feature = "lz4_flex"))]
+    #[test]
+    fn unpack_other_algorithm() {
+        let bytes = Compressed::<_, Lz4>::new(2u16).pack_to_vec().unwrap();
+        let result = Compressed::<u16, Deflate>::unpack_from(&mut bytes.as_slice());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    fn forged_length<A: Algorithm>() {
+        let mut bytes = Compressed::<_, A>::new(2u16).pack_to_vec().unwrap();
+        bytes[1..5].copy_from_slice(&u32::MAX.to_be_bytes());
+        let result = Compressed::<u16, A>::unpack_from(&mut bytes.as_slice());
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn deflate_forged_length() {
+        forged_length::<Deflate>();
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn deflate_exceeds_limit() {
+        let bytes = Compressed::<_, Deflate>::new([0u8; 4096].as_slice())
+            .pack_to_vec()
+            .unwrap();
+        let result = Compressed::<Vec<u8>, Deflate<1024>>::unpack_from(&mut bytes.as_slice());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn deflate_unpack_followed_by_value() {
+        let mut bytes = Compressed::<_, Deflate>::new([7u16; 64].as_slice())
+            .pack_to_vec()
+            .unwrap();
+        9u8.pack_into(&mut bytes).unwrap();
+        let mut reader = bytes.as_slice();
+        let result = Compressed::<Vec<u16>, Deflate>::unpack_from(&mut reader).unwrap();
+        assert_eq!(result.into_inner(), vec![7u16; 64]);
+        assert_eq!(u8::unpack_from(&mut reader).unwrap(), 9);
+    }
+
+    #[cfg(feature = "lz4_flex")]
+    #[test]
+    fn lz4_forged_length() {
+        forged_length::<Lz4>();
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_forged_length() {
+        forged_length::<Zstd>();
+    }
+}
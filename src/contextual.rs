@@ -0,0 +1,290 @@
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{initial_capacity, Error, Result, Unpack};
+use std::collections::HashMap;
+use std::io;
+
+/// Describes the ability to serialize this struct with the help of a context
+///
+/// The context holds state outside of the struct itself, e.g. a string
+/// table, the negotiated protocol version or a salt, and is passed mutably,
+/// so it can also be filled while packing. Every type implementing [`Pack`]
+/// implements this trait for any context by ignoring it, so fields without
+/// context dependencies are packed with `pack_with` as well
+pub trait PackWith<C: ?Sized> {
+    /// Tries to serialize this struct into a bytestream using the context
+    fn pack_with(&self, context: &mut C, writer: &mut impl io::Write) -> pack::Result<usize>;
+
+    /// Tries to serialize this struct into a byte-vector using the context
+    fn pack_to_vec_with(&self, context: &mut C) -> pack::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.pack_with(context, &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+impl<C: ?Sized, T: Pack + ?Sized> PackWith<C> for T {
+    fn pack_with(&self, _context: &mut C, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.pack_into(writer)
+    }
+}
+
+/// Describes the ability to deserialize a struct with the help of a context
+///
+/// Counterpart of [`PackWith`]. Every type implementing [`Unpack`]
+/// implements this trait for any context by ignoring it
+pub trait UnpackWith<C: ?Sized>: Sized {
+    /// Tries to deserialize this struct from a bytesource using the context
+    fn unpack_with(context: &mut C, reader: &mut impl io::Read) -> Result<Self>;
+
+    /// Tries to deserialize this struct from a byte slice that has to
+    /// contain exactly this struct using the context
+    fn unpack_exact_with(context: &mut C, bytes: &[u8]) -> Result<Self> {
+        let mut remaining = bytes;
+        let value = Self::unpack_with(context, &mut remaining)?;
+
+        if !remaining.is_empty() {
+            return Err(Error::TrailingBytes(remaining.len()));
+        }
+
+        Ok(value)
+    }
+}
+
+impl<C: ?Sized, T: Unpack> UnpackWith<C> for T {
+    fn unpack_with(_context: &mut C, reader: &mut impl io::Read) -> Result<Self> {
+        Self::unpack_from(reader)
+    }
+}
+
+/// Packs a length-prefixed sequence of values using the context
+///
+/// The wire format is the same as for slices packed with [`Pack`]
+pub fn pack_slice_with<C: ?Sized, T: PackWith<C>>(
+    values: &[T],
+    context: &mut C,
+    writer: &mut impl io::Write,
+) -> pack::Result<usize> {
+    let mut written = checked_len(values.len())?.pack_into(writer)?;
+
+    for value in values {
+        written += value.pack_with(context, writer)?;
+    }
+
+    Ok(written)
+}
+
+/// Unpacks a length-prefixed sequence of values using the context
+pub fn unpack_vec_with<C: ?Sized, T: UnpackWith<C>>(
+    context: &mut C,
+    reader: &mut impl io::Read,
+) -> Result<Vec<T>> {
+    let len = u32::unpack_from(reader)? as usize;
+    let mut values = Vec::with_capacity(initial_capacity::<T>(len));
+
+    for _i in 0..len {
+        values.push(T::unpack_with(context, reader)?);
+    }
+
+    Ok(values)
+}
+
+/// Context interning strings across a stream or file section
+///
+/// The first occurrence of a string is packed as a `u32` zero followed by
+/// the string itself and is assigned the next index in the table. Every
+/// further occurrence is packed as its index plus one only, which shrinks
+/// streams with repetitive labels or keys considerably
+///
+/// Reader and writer have to start with the same table, usually an empty
+/// one, and process the same sequence of values. Clear the table at the
+/// start of every independently readable section
+#[derive(Debug, Clone, Default)]
+pub struct StringTable {
+    indices: HashMap<String, u32>,
+    strings: Vec<String>,
+}
+
+impl StringTable {
+    /// Creates an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of interned strings
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns whether no string has been interned yet
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Returns the string with the given index
+    pub fn get(&self, index: u32) -> Option<&str> {
+        self.strings.get(index as usize).map(String::as_str)
+    }
+
+    /// Removes all interned strings, e.g. at the start of a new section
+    pub fn clear(&mut self) {
+        self.indices.clear();
+        self.strings.clear();
+    }
+
+    /// Packs a string, either in full or as a reference to its first
+    /// occurrence
+    pub fn pack_str(&mut self, string: &str, writer: &mut impl io::Write) -> pack::Result<usize> {
+        if let Some(index) = self.indices.get(string) {
+            return (index + 1).pack_into(writer);
+        }
+
+        let index = checked_len(self.strings.len())?;
+        let written = 0u32.pack_into(writer)? + string.pack_into(writer)?;
+        self.indices.insert(string.to_owned(), index);
+        self.strings.push(string.to_owned());
+        Ok(written)
+    }
+
+    /// Unpacks a string packed with [`StringTable::pack_str`]
+    pub fn unpack_string(&mut self, reader: &mut impl io::Read) -> Result<String> {
+        match u32::unpack_from(reader)? {
+            0 => {
+                let string = String::unpack_from(reader)?;
+                self.indices
+                    .insert(string.clone(), checked_len(self.strings.len())?);
+                self.strings.push(string.clone());
+                Ok(string)
+            }
+            reference => self.get(reference - 1).map(str::to_owned).ok_or_else(|| {
+                Error::InvalidValue(format!("unknown string reference {}", reference))
+            }),
+        }
+    }
+}
+
+/// Wrapper that packs a string through a [`StringTable`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interned<S>(pub S);
+
+impl<S: AsRef<str>> PackWith<StringTable> for Interned<S> {
+    fn pack_with(
+        &self,
+        context: &mut StringTable,
+        writer: &mut impl io::Write,
+    ) -> pack::Result<usize> {
+        context.pack_str(self.0.as_ref(), writer)
+    }
+}
+
+impl UnpackWith<StringTable> for Interned<String> {
+    fn unpack_with(context: &mut StringTable, reader: &mut impl io::Read) -> Result<Self> {
+        context.unpack_string(reader).map(Interned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Strings(Vec<String>);
+
+    #[derive(Debug, PartialEq)]
+    struct Label {
+        id: u16,
+        name: String,
+    }
+
+    impl PackWith<Strings> for Label {
+        fn pack_with(
+            &self,
+            context: &mut Strings,
+            writer: &mut impl io::Write,
+        ) -> pack::Result<usize> {
+            let index = match context.0.iter().position(|name| *name == self.name) {
+                Some(index) => index,
+                None => {
+                    context.0.push(self.name.clone());
+                    context.0.len() - 1
+                }
+            };
+            Ok(self.id.pack_with(context, writer)? + checked_len(index)?.pack_into(writer)?)
+        }
+    }
+
+    impl UnpackWith<Strings> for Label {
+        fn unpack_with(context: &mut Strings, reader: &mut impl io::Read) -> Result<Self> {
+            let id = u16::unpack_with(context, reader)?;
+            let index = u32::unpack_from(reader)? as usize;
+            let name =
+                context.0.get(index).cloned().ok_or_else(|| {
+                    Error::InvalidValue(format!("unknown string index {}", index))
+                })?;
+            Ok(Label { id, name })
+        }
+    }
+
+    #[test]
+    fn context_round_trip() {
+        let labels = [
+            Label {
+                id: 1,
+                name: "a".into(),
+            },
+            Label {
+                id: 2,
+                name: "b".into(),
+            },
+            Label {
+                id: 3,
+                name: "a".into(),
+            },
+        ];
+        let mut strings = Strings::default();
+        let mut bytes = Vec::new();
+        pack_slice_with(&labels, &mut strings, &mut bytes).unwrap();
+        assert_eq!(strings.0, ["a", "b"]);
+        assert_eq!(bytes.len(), 4 + 3 * 6);
+
+        let decoded: Vec<Label> = unpack_vec_with(&mut strings, &mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded, labels);
+    }
+
+    #[test]
+    fn missing_context_entry() {
+        let bytes = [0x00, 0x01, 0x00, 0x00, 0x00, 0x05];
+        let result = Label::unpack_exact_with(&mut Strings::default(), &bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn plain_types_ignore_context() {
+        let bytes = 7u16.pack_to_vec_with(&mut ()).unwrap();
+        assert_eq!(bytes, [0x00, 0x07]);
+        assert_eq!(u16::unpack_exact_with(&mut (), &bytes).unwrap(), 7);
+    }
+
+    #[test]
+    fn interned_strings() {
+        let labels = [Interned("info"), Interned("warn"), Interned("info")];
+        let mut table = StringTable::new();
+        let mut bytes = Vec::new();
+        pack_slice_with(&labels, &mut table, &mut bytes).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(bytes.len(), 4 + 12 + 12 + 4);
+        assert_eq!(bytes[28..], [0x00, 0x00, 0x00, 0x01]);
+
+        let mut table = StringTable::new();
+        let decoded: Vec<Interned<String>> =
+            unpack_vec_with(&mut table, &mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded[2].0, "info");
+        assert_eq!(table.get(1), Some("warn"));
+    }
+
+    #[test]
+    fn unknown_string_reference() {
+        let bytes = [0x00, 0x00, 0x00, 0x01];
+        let result = Interned::<String>::unpack_exact_with(&mut StringTable::new(), &bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,113 @@
+//! Helpers for protocols sending one packed value per datagram
+
+use crate::pack::{self, Pack};
+use crate::unpack::{self, Unpack};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+/// Largest payload of a UDP datagram over IPv4
+pub const MAX_PAYLOAD_SIZE: usize = 65_507;
+
+/// Largest payload of a UDP datagram that is never fragmented, as every
+/// IPv4 host has to accept datagrams of 576 bytes
+pub const SAFE_PAYLOAD_SIZE: usize = 508;
+
+/// Error that may occur while packing a datagram
+#[derive(Debug)]
+pub enum DatagramError {
+    /// The packed value needs `size` bytes, but the buffer only holds
+    /// `capacity` bytes
+    TooLarge {
+        size: usize,
+        capacity: usize,
+    },
+    Pack(pack::Error),
+}
+
+impl Display for DatagramError {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DatagramError::TooLarge { size, capacity } => write!(
+                destination,
+                "packed value needs {} bytes, but the datagram holds only {}",
+                size, capacity
+            ),
+            DatagramError::Pack(error) => error.fmt(destination),
+        }
+    }
+}
+
+impl error::Error for DatagramError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            DatagramError::Pack(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<pack::Error> for DatagramError {
+    fn from(error: pack::Error) -> Self {
+        DatagramError::Pack(error)
+    }
+}
+
+/// Packs a value into a buffer sized for a single datagram
+///
+/// Returns the number of bytes written to the start of the buffer, which
+/// is the slice to send. Fails with [`DatagramError::TooLarge`] reporting
+/// the required size if the value does not fit
+pub fn pack_datagram<T: Pack + ?Sized>(
+    value: &T,
+    buffer: &mut [u8],
+) -> Result<usize, DatagramError> {
+    let capacity = buffer.len();
+
+    match value.pack_into_slice(buffer) {
+        Err(pack::Error::IO(error)) if error.kind() == io::ErrorKind::WriteZero => {
+            let size = value.pack_into(&mut io::sink())?;
+            Err(DatagramError::TooLarge { size, capacity })
+        }
+        result => Ok(result?),
+    }
+}
+
+/// Unpacks a value from a received datagram, which has to contain exactly
+/// this value
+///
+/// Fails with `Error::TrailingBytes` if bytes are left over
+pub fn unpack_datagram<T: Unpack>(datagram: &[u8]) -> unpack::Result<T> {
+    T::unpack_exact(datagram)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unpack::Error;
+
+    #[test]
+    fn datagram_round_trip() {
+        let mut buffer = [0u8; SAFE_PAYLOAD_SIZE];
+        let len = pack_datagram("position", &mut buffer).unwrap();
+        assert_eq!(len, 12);
+        let value: String = unpack_datagram(&buffer[..len]).unwrap();
+        assert_eq!(value, "position");
+
+        let result = unpack_datagram::<String>(&buffer[..len + 1]);
+        assert!(matches!(result, Err(Error::TrailingBytes(1))));
+    }
+
+    #[test]
+    fn datagram_too_large() {
+        let mut buffer = [0u8; 16];
+        let result = pack_datagram(&[0u32; 4], &mut buffer);
+        assert!(matches!(
+            result,
+            Err(DatagramError::TooLarge {
+                size: 20,
+                capacity: 16
+            })
+        ));
+    }
+}
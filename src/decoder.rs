@@ -0,0 +1,168 @@
+use crate::unpack::{Error, Result, Unpack};
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::marker::PhantomData;
+
+/// Push-based decoder for values arriving in arbitrary chunks
+///
+/// The decoder does not perform any IO itself. Bytes are pushed into an
+/// internal buffer as they arrive (e.g. from a non-blocking socket) and
+/// complete values are taken out as soon as enough bytes are available.
+/// A value that is split across several chunks is decoded once its last
+/// byte has been pushed
+///
+/// Incomplete values are detected by the `UnexpectedEof` error of the
+/// underlying [`Unpack`] implementation, so decoding is retried from the
+/// start of the pending value whenever new bytes arrive
+pub struct Decoder<T> {
+    buffer: Vec<u8>,
+    position: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Decoder<T> {
+    /// Creates a decoder with an empty buffer
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            position: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Appends a chunk of bytes to the internal buffer
+    pub fn push(&mut self, chunk: &[u8]) {
+        if self.position > 0 && self.position * 2 >= self.buffer.len() {
+            self.buffer.drain(..self.position);
+            self.position = 0;
+        }
+
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Returns the bytes that have been pushed but not yet decoded
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer[self.position..]
+    }
+
+    /// Returns true if there are no pending bytes
+    pub fn is_empty(&self) -> bool {
+        self.position == self.buffer.len()
+    }
+
+    /// Discards all pending bytes, e.g. after a decoding error
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.position = 0;
+    }
+}
+
+impl<T: Unpack> Decoder<T> {
+    /// Tries to decode the next value from the pending bytes
+    ///
+    /// Returns `Ok(None)` if the pending bytes do not contain a complete
+    /// value yet. Any other error is returned as is and leaves the pending
+    /// bytes untouched, so the same error will occur again until the buffer
+    /// is cleared
+    pub fn poll_item(&mut self) -> Result<Option<T>> {
+        let mut remaining = self.buffered();
+
+        match T::unpack_from(&mut remaining) {
+            Ok(value) => {
+                self.position = self.buffer.len() - remaining.len();
+                Ok(Some(value))
+            }
+            Err(Error::IO(error)) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Appends a chunk of bytes and decodes all values that are complete
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<T>> {
+        self.push(chunk);
+        let mut result = Vec::new();
+
+        loop {
+            let pending = self.buffered().len();
+
+            match self.poll_item()? {
+                Some(value) => result.push(value),
+                None => break,
+            }
+
+            if self.buffered().len() == pending {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl<T> Default for Decoder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for Decoder<T> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_struct("Decoder")
+            .field("buffered", &self.buffered().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_complete_values() {
+        let mut decoder = Decoder::<u16>::new();
+        let values = decoder.feed(&[0x00, 0x01, 0x00, 0x02]).unwrap();
+        assert_eq!(values, [1, 2]);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn decode_across_chunks() {
+        let mut decoder = Decoder::<String>::new();
+        assert!(decoder.feed(&[0x00, 0x00]).unwrap().is_empty());
+        assert!(decoder.feed(&[0x00, 0x03, 0x61]).unwrap().is_empty());
+
+        let values = decoder.feed(&[0x62, 0x63, 0x00]).unwrap();
+        assert_eq!(values, ["abc"]);
+        assert_eq!(decoder.buffered(), [0x00]);
+    }
+
+    #[test]
+    fn decode_byte_by_byte() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x02];
+        let mut decoder = Decoder::<Vec<u16>>::new();
+        let mut values = Vec::new();
+
+        for byte in bytes {
+            values.extend(decoder.feed(&[byte]).unwrap());
+        }
+
+        assert_eq!(values, [vec![1, 2]]);
+    }
+
+    #[test]
+    fn poll_item_without_bytes() {
+        let mut decoder = Decoder::<u32>::new();
+        assert_eq!(decoder.poll_item().unwrap(), None);
+    }
+
+    #[test]
+    fn decode_invalid_value() {
+        let mut decoder = Decoder::<String>::new();
+        assert!(decoder.feed(&[0x00, 0x00, 0x00, 0x01, 0xFF]).is_err());
+        assert_eq!(decoder.buffered().len(), 5);
+
+        decoder.clear();
+        assert!(decoder.is_empty());
+    }
+}
@@ -0,0 +1,314 @@
+//! Snapshot and delta streams of a changing state
+//!
+//! A [`DeltaStream`] writes a full snapshot of the initial state followed by
+//! a [`Patch`] per update, which is usually much smaller than the whole
+//! state. A [`DeltaReader`] replays the stream and reconstructs the state
+//! after every entry, the common pattern for replicated game state and
+//! materialized views
+//!
+//! Every entry is packed as a kind (`u8`, 0 for a snapshot and 1 for a
+//! delta) followed by the framed snapshot or patch. Writing another snapshot
+//! from time to time bounds the work needed by late joining readers
+
+use crate::diff::{apply, diff, Patch};
+use crate::pack::{self, Pack};
+use crate::schema::Describe;
+use crate::unpack::{Error, Result, Unpack};
+use std::io::{self, Read};
+
+/// Entry of a delta stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry<T> {
+    Snapshot(T),
+    Delta(Patch),
+}
+
+impl<T: Pack> Pack for Entry<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        match self {
+            Entry::Snapshot(value) => Ok(0u8.pack_into(writer)? + value.pack_framed(writer)?),
+            Entry::Delta(patch) => Ok(1u8.pack_into(writer)? + patch.pack_framed(writer)?),
+        }
+    }
+}
+
+impl<T: Unpack> Unpack for Entry<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        match u8::unpack_from(reader)? {
+            0 => Ok(Entry::Snapshot(T::unpack_framed(reader)?)),
+            1 => Ok(Entry::Delta(Patch::unpack_framed(reader)?)),
+            kind => Err(Error::InvalidValue(format!(
+                "unknown delta stream entry {}",
+                kind
+            ))),
+        }
+    }
+}
+
+/// Writer of a snapshot followed by deltas of every update
+pub struct DeltaStream<T, W> {
+    writer: W,
+    state: T,
+    entries: usize,
+}
+
+impl<T: Pack + Describe, W: io::Write> DeltaStream<T, W> {
+    /// Creates a stream starting with a snapshot of the initial state
+    pub fn new(mut writer: W, initial: T) -> pack::Result<Self> {
+        0u8.pack_into(&mut writer)?;
+        initial.pack_framed(&mut writer)?;
+
+        Ok(Self {
+            writer,
+            state: initial,
+            entries: 1,
+        })
+    }
+
+    /// Writes the changes from the current to the new state and returns the
+    /// number of bytes written
+    pub fn update(&mut self, state: T) -> Result<usize> {
+        let patch = diff(&self.state, &state)?;
+        let written = Entry::<T>::Delta(patch).pack_into(&mut self.writer)?;
+        self.state = state;
+        self.entries += 1;
+        Ok(written)
+    }
+
+    /// Writes a full snapshot of the current state and returns the number
+    /// of bytes written
+    pub fn snapshot(&mut self) -> pack::Result<usize> {
+        let written =
+            0u8.pack_into(&mut self.writer)? + self.state.pack_framed(&mut self.writer)?;
+        self.entries += 1;
+        Ok(written)
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> &T {
+        &self.state
+    }
+
+    /// Returns the number of entries written so far
+    pub fn len(&self) -> usize {
+        self.entries
+    }
+
+    /// Returns whether no entries were written, which is never the case as
+    /// every stream starts with a snapshot
+    pub fn is_empty(&self) -> bool {
+        self.entries == 0
+    }
+
+    /// Returns a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reader reconstructing the state from a delta stream
+pub struct DeltaReader<T, R> {
+    reader: R,
+    state: Option<T>,
+    position: usize,
+}
+
+impl<T: Pack + Unpack + Describe, R: io::Read> DeltaReader<T, R> {
+    /// Creates a reader at the start of a delta stream
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            state: None,
+            position: 0,
+        }
+    }
+
+    /// Reads the next entry and returns the state after it, or `None` if
+    /// the stream ended cleanly between two entries
+    ///
+    /// Fails with `Error::InvalidValue` if the stream starts with a delta.
+    /// The state is only replaced once an entry was applied successfully
+    pub fn next_state(&mut self) -> Result<Option<&T>> {
+        let mut kind = [0x00];
+
+        loop {
+            match self.reader.read(&mut kind) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(Error::IO(error)),
+            }
+        }
+
+        let entry = Entry::<T>::unpack_from(&mut kind.as_ref().chain(&mut self.reader))?;
+
+        let state = match (entry, &self.state) {
+            (Entry::Snapshot(state), _) => state,
+            (Entry::Delta(patch), Some(state)) => apply(state, &patch)?,
+            (Entry::Delta(_), None) => {
+                return Err(Error::InvalidValue(String::from(
+                    "delta stream does not start with a snapshot",
+                )))
+            }
+        };
+
+        self.position += 1;
+        Ok(Some(self.state.insert(state)))
+    }
+
+    /// Returns the state after the last entry read, if any
+    pub fn state(&self) -> Option<&T> {
+        self.state.as_ref()
+    }
+
+    /// Returns the number of entries read so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<T: Pack + Unpack + Describe, R: io::Read + io::Seek> DeltaReader<T, R> {
+    /// Returns the state after the entry with the given index
+    ///
+    /// Earlier states are reconstructed by replaying the stream from the
+    /// start of the reader. Fails with `UnexpectedEof` if the stream has
+    /// fewer entries
+    pub fn state_at(&mut self, index: usize) -> Result<&T> {
+        if index + 1 < self.position {
+            self.reader.seek(io::SeekFrom::Start(0))?;
+            self.state = None;
+            self.position = 0;
+        }
+
+        while self.position <= index {
+            if self.next_state()?.is_none() {
+                return Err(Error::IO(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("delta stream has only {} entries", self.position),
+                )));
+            }
+        }
+
+        Ok(self.state.as_ref().expect("state after reading an entry"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Schema;
+    use crate::value::Value;
+    use std::io::Cursor;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Player {
+        name: String,
+        x: i32,
+        y: i32,
+    }
+
+    impl Pack for Player {
+        fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+            let mut written = self.name.as_str().pack_into(writer)?;
+            written += self.x.pack_into(writer)?;
+            written += self.y.pack_into(writer)?;
+            Ok(written)
+        }
+    }
+
+    impl Unpack for Player {
+        fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+            Ok(Player {
+                name: String::unpack_from(reader)?,
+                x: i32::unpack_from(reader)?,
+                y: i32::unpack_from(reader)?,
+            })
+        }
+    }
+
+    impl Describe for Player {
+        fn schema() -> Schema {
+            Schema::structure("Player")
+                .field("name", Schema::String)
+                .field("x", Schema::I32)
+                .field("y", Schema::I32)
+        }
+    }
+
+    fn player(x: i32, y: i32) -> Player {
+        Player {
+            name: "a fairly long player name".into(),
+            x,
+            y,
+        }
+    }
+
+    fn write_stream() -> Vec<u8> {
+        let mut stream = DeltaStream::new(Vec::new(), player(0, 0)).unwrap();
+        let full = player(1, 0).pack_to_vec().unwrap().len();
+        assert!(stream.update(player(1, 0)).unwrap() < full);
+        stream.update(player(1, 2)).unwrap();
+        stream.snapshot().unwrap();
+        stream.update(player(3, 2)).unwrap();
+        assert_eq!(stream.len(), 5);
+        stream.into_inner()
+    }
+
+    #[test]
+    fn replay_stream() {
+        let bytes = write_stream();
+        let mut reader = DeltaReader::<Player, _>::new(bytes.as_slice());
+        let mut states = Vec::new();
+
+        while let Some(state) = reader.next_state().unwrap() {
+            states.push((state.x, state.y));
+        }
+
+        assert_eq!(states, [(0, 0), (1, 0), (1, 2), (1, 2), (3, 2)]);
+        assert_eq!(reader.position(), 5);
+    }
+
+    #[test]
+    fn reconstruct_state_at() {
+        let mut reader = DeltaReader::<Player, _>::new(Cursor::new(write_stream()));
+        assert_eq!(reader.state_at(4).unwrap(), &player(3, 2));
+        assert_eq!(reader.state_at(1).unwrap(), &player(1, 0));
+        assert_eq!(reader.state_at(1).unwrap(), &player(1, 0));
+        assert_eq!(reader.state_at(2).unwrap(), &player(1, 2));
+        assert!(matches!(reader.state_at(5), Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn reject_missing_snapshot() {
+        let bytes = Entry::<Player>::Delta(Patch::default())
+            .pack_to_vec()
+            .unwrap();
+        let mut reader = DeltaReader::<Player, _>::new(bytes.as_slice());
+        assert!(matches!(reader.next_state(), Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn keep_state_after_invalid_delta() {
+        let mut bytes = Entry::Snapshot(player(1, 2)).pack_to_vec().unwrap();
+        let patch = Patch {
+            changes: vec![(vec![String::from("z")], Value::Int(0))],
+        };
+        Entry::<Player>::Delta(patch).pack_into(&mut bytes).unwrap();
+
+        let mut reader = DeltaReader::<Player, _>::new(bytes.as_slice());
+        reader.next_state().unwrap();
+        assert!(matches!(reader.next_state(), Err(Error::InvalidValue(_))));
+        assert_eq!(reader.state(), Some(&player(1, 2)));
+        assert_eq!(reader.position(), 1);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::pack::{self, checked_len, pack_fixed, Pack};
+use crate::unpack::{Result, Unpack};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::io;
+
+/// Wrapper that packs a float in a canonical form
+///
+/// Logically equal floats may differ in their bit patterns: NaN can carry
+/// arbitrary payloads and zero can be signed. This wrapper packs every NaN
+/// as the standard quiet NaN and negative zero as positive zero, so equal
+/// values always produce identical bytes (e.g. for hashing packed output)
+///
+/// The wire format is the same as for the plain float, so values packed
+/// with this wrapper can be unpacked as `f32`/`f64` and vice versa
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct CanonicalFloat<T>(pub T);
+
+impl Pack for CanonicalFloat<f32> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let value = if self.0.is_nan() {
+            f32::NAN
+        } else if self.0 == 0.0 {
+            0.0
+        } else {
+            self.0
+        };
+        value.pack_into(writer)
+    }
+}
+
+impl Pack for CanonicalFloat<f64> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let value = if self.0.is_nan() {
+            f64::NAN
+        } else if self.0 == 0.0 {
+            0.0
+        } else {
+            self.0
+        };
+        value.pack_into(writer)
+    }
+}
+
+impl Unpack for CanonicalFloat<f32> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        f32::unpack_from(reader).map(CanonicalFloat)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        f32::unpack_from_buffered(reader).map(CanonicalFloat)
+    }
+}
+
+impl Unpack for CanonicalFloat<f64> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        f64::unpack_from(reader).map(CanonicalFloat)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        f64::unpack_from_buffered(reader).map(CanonicalFloat)
+    }
+}
+
+pack_fixed!(CanonicalFloat<f32>, CanonicalFloat<f64>);
+
+/// Wrapper that packs a `HashMap` with its entries ordered by key
+///
+/// The iteration order of a `HashMap` is random, so packing the same map
+/// twice may produce different bytes. This wrapper sorts the entries before
+/// packing, so equal maps always produce identical bytes (e.g. for signing
+/// or caching packed output)
+///
+/// The wire format is the same as for the plain `HashMap`
+#[derive(Debug, Clone, Default)]
+pub struct SortedMap<K, V>(pub HashMap<K, V>);
+
+/// Wrapper that packs a `HashSet` with its values in ascending order
+///
+/// See [`SortedMap`] for the reasoning. The wire format is the same as for
+/// the plain `HashSet`
+#[derive(Debug, Clone, Default)]
+pub struct SortedSet<T>(pub HashSet<T>);
+
+impl<K: Pack + Ord, V: Pack> Pack for SortedMap<K, V> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+
+        let len = checked_len(entries.len())?;
+        let mut written = len.pack_into(writer)?;
+
+        for (key, value) in entries {
+            written += key.pack_into(writer)?;
+            written += value.pack_into(writer)?;
+        }
+
+        Ok(written)
+    }
+}
+
+impl<T: Pack + Ord> Pack for SortedSet<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut values: Vec<_> = self.0.iter().collect();
+        values.sort();
+
+        let len = checked_len(values.len())?;
+        let mut written = len.pack_into(writer)?;
+
+        for value in values {
+            written += value.pack_into(writer)?;
+        }
+
+        Ok(written)
+    }
+}
+
+impl<K: Unpack + Eq + Hash, V: Unpack> Unpack for SortedMap<K, V> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        HashMap::unpack_from(reader).map(SortedMap)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        HashMap::unpack_from_buffered(reader).map(SortedMap)
+    }
+
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()> {
+        self.0.unpack_into(reader)
+    }
+}
+
+impl<T: Unpack + Eq + Hash> Unpack for SortedSet<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        HashSet::unpack_from(reader).map(SortedSet)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        HashSet::unpack_from_buffered(reader).map(SortedSet)
+    }
+
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()> {
+        self.0.unpack_into(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::PackFixed;
+
+    #[test]
+    fn canonical_float_sizes() {
+        assert_eq!(CanonicalFloat::<f32>::SIZE, 4);
+        assert_eq!(CanonicalFloat::<f64>::SIZE, 8);
+        assert_eq!(<CanonicalFloat<f64> as Unpack>::PACKED_SIZE, Some(8));
+    }
+
+    #[test]
+    fn pack_canonical_f32_nan() {
+        let value = CanonicalFloat(f32::from_bits(0xFFC0_0001));
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x7F, 0xC0, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn pack_canonical_f32_negative_zero() {
+        let value = CanonicalFloat(-0.0f32);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn pack_canonical_f32_regular() {
+        let value = CanonicalFloat(-1.0f32);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0xBF, 0x80, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn pack_canonical_f64_nan() {
+        let value = CanonicalFloat(f64::from_bits(0x7FF0_0000_0000_0001));
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x7F, 0xF8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn pack_canonical_f64_negative_zero() {
+        let value = CanonicalFloat(-0.0f64);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn unpack_canonical_f64() {
+        let bytes = [0xBF, 0xF0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let value = CanonicalFloat::<f64>::unpack_from(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, CanonicalFloat(-1.0));
+    }
+
+    #[test]
+    fn pack_sorted_map() {
+        let map: HashMap<u8, u8> = (0..32).map(|x| (31 - x, x)).collect();
+        let bytes = SortedMap(map).pack_to_vec().unwrap();
+        assert_eq!(bytes[..4], [0x00, 0x00, 0x00, 0x20]);
+
+        for (index, entry) in bytes[4..].chunks(2).enumerate() {
+            assert_eq!(entry, [index as u8, 31 - index as u8]);
+        }
+    }
+
+    #[test]
+    fn pack_sorted_set() {
+        let set: HashSet<u8> = [3, 1, 2].into_iter().collect();
+        let bytes = SortedSet(set).pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03]);
+    }
+
+    #[test]
+    fn unpack_sorted_map() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x01, 0x0A, 0x02, 0x0B];
+        let value = SortedMap::<u8, u8>::unpack_from(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value.0, HashMap::from([(1, 10), (2, 11)]));
+    }
+}
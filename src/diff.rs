@@ -0,0 +1,269 @@
+//! Structural diff and patch of packed values
+//!
+//! [`diff`] decodes two values of the same type into [`Value`]s according
+//! to the schema of the type and collects the fields that differ. The
+//! resulting [`Patch`] is packable itself, so state synchronization can send
+//! only the changed fields instead of whole snapshots, and [`apply`] rebuilds
+//! the new value from the old one on the other side
+//!
+//! Fields of nested structs are compared one by one, all other values
+//! (sequences, maps, primitives) are replaced as a whole
+//!
+//! ```
+//! use serial_container::diff::{apply, diff};
+//! # use serial_container::pack::{self, Pack};
+//! # use serial_container::schema::{Describe, Schema};
+//! # use serial_container::unpack::{Result, Unpack};
+//! # use std::io;
+//! #
+//! # #[derive(Debug, PartialEq)]
+//! # struct Point { x: i32, y: i32 }
+//! #
+//! # impl Pack for Point {
+//! #     fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+//! #         Ok(self.x.pack_into(writer)? + self.y.pack_into(writer)?)
+//! #     }
+//! # }
+//! #
+//! # impl Unpack for Point {
+//! #     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+//! #         Ok(Point { x: i32::unpack_from(reader)?, y: i32::unpack_from(reader)? })
+//! #     }
+//! # }
+//! #
+//! # impl Describe for Point {
+//! #     fn schema() -> Schema {
+//! #         Schema::structure("Point").field("x", Schema::I32).field("y", Schema::I32)
+//! #     }
+//! # }
+//! let old = Point { x: 1, y: 2 };
+//! let new = Point { x: 1, y: 5 };
+//!
+//! let patch = diff(&old, &new).unwrap();
+//! assert_eq!(patch.len(), 1);
+//! assert_eq!(apply(&old, &patch).unwrap(), new);
+//! ```
+
+use crate::pack::{self, checked_len, Pack};
+use crate::schema::{Describe, Schema};
+use crate::unpack::{Error, Result, Unpack};
+use crate::value::Value;
+use std::io;
+
+/// Changed fields between two values, each given by the path of field names
+/// leading to it and its new value
+///
+/// Packed as a length-prefixed sequence of changes, each consisting of the
+/// length-prefixed path and the tagged [`Value`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Patch {
+    pub changes: Vec<(Vec<String>, Value)>,
+}
+
+impl Patch {
+    /// Returns the number of changed fields
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns whether both values were equal
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Pack for Patch {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = checked_len(self.changes.len())?.pack_into(writer)?;
+
+        for (path, value) in &self.changes {
+            written += checked_len(path.len())?.pack_into(writer)?;
+
+            for name in path {
+                written += name.as_str().pack_into(writer)?;
+            }
+
+            written += value.pack_into(writer)?;
+        }
+
+        Ok(written)
+    }
+}
+
+impl Unpack for Patch {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut changes = Vec::new();
+
+        for _i in 0..u32::unpack_from(reader)? {
+            let mut path = Vec::new();
+
+            for _j in 0..u32::unpack_from(reader)? {
+                path.push(String::unpack_from(reader)?);
+            }
+
+            changes.push((path, Value::unpack_from(reader)?));
+        }
+
+        Ok(Patch { changes })
+    }
+}
+
+/// Returns the fields that differ between the old and the new value
+pub fn diff<T: Pack + Describe + ?Sized>(old: &T, new: &T) -> Result<Patch> {
+    let schema = T::schema();
+    let old = to_value(&schema, old)?;
+    let new = to_value(&schema, new)?;
+    let mut patch = Patch::default();
+    collect(&schema, &mut Vec::new(), old, new, &mut patch);
+    Ok(patch)
+}
+
+/// Applies a patch created by [`diff`] to the old value and returns the new
+/// value
+///
+/// Fails with `Error::InvalidValue` if a path of the patch does not lead to
+/// a field of the type or a value does not fit the schema of its field
+pub fn apply<T: Pack + Unpack + Describe>(old: &T, patch: &Patch) -> Result<T> {
+    let schema = T::schema();
+    let mut value = to_value(&schema, old)?;
+
+    for (path, change) in &patch.changes {
+        let mut target = &mut value;
+
+        for name in path {
+            target = field_mut(target, name).ok_or_else(|| {
+                Error::InvalidValue(format!("patch refers to unknown field {}", path.join(".")))
+            })?;
+        }
+
+        *target = change.clone();
+    }
+
+    let mut bytes = Vec::new();
+    value.pack_with_schema(&schema, &mut bytes)?;
+    T::unpack_exact(&bytes)
+}
+
+fn to_value<T: Pack + ?Sized>(schema: &Schema, value: &T) -> Result<Value> {
+    let bytes = value.pack_to_vec()?;
+    Value::unpack_with_schema(schema, &mut bytes.as_slice())
+}
+
+fn collect(schema: &Schema, path: &mut Vec<String>, old: Value, new: Value, patch: &mut Patch) {
+    if old == new {
+        return;
+    }
+
+    match (schema, old, new) {
+        (Schema::Struct { fields, .. }, Value::Map(old), Value::Map(new)) => {
+            for ((name, schema), ((_, old), (_, new))) in
+                fields.iter().zip(old.into_iter().zip(new))
+            {
+                path.push(name.clone());
+                collect(schema, path, old, new, patch);
+                path.pop();
+            }
+        }
+        (_, _, new) => patch.changes.push((path.clone(), new)),
+    }
+}
+
+fn field_mut<'a>(value: &'a mut Value, name: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Map(entries) => entries
+            .iter_mut()
+            .find(|(key, _)| matches!(key, Value::Str(key) if key == name))
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Inner {
+        id: u16,
+        tags: Vec<u8>,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Outer {
+        name: String,
+        inner: Inner,
+    }
+
+    impl Pack for Outer {
+        fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+            let mut written = self.name.as_str().pack_into(writer)?;
+            written += self.inner.id.pack_into(writer)?;
+            written += self.inner.tags.as_slice().pack_into(writer)?;
+            Ok(written)
+        }
+    }
+
+    impl Unpack for Outer {
+        fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+            Ok(Outer {
+                name: String::unpack_from(reader)?,
+                inner: Inner {
+                    id: u16::unpack_from(reader)?,
+                    tags: Vec::unpack_from(reader)?,
+                },
+            })
+        }
+    }
+
+    impl Describe for Outer {
+        fn schema() -> Schema {
+            let inner = Schema::structure("Inner")
+                .field("id", Schema::U16)
+                .field("tags", Vec::<u8>::schema());
+            Schema::structure("Outer")
+                .field("name", Schema::String)
+                .field("inner", inner)
+        }
+    }
+
+    fn outer(name: &str, id: u16) -> Outer {
+        Outer {
+            name: name.into(),
+            inner: Inner {
+                id,
+                tags: vec![1, 2],
+            },
+        }
+    }
+
+    #[test]
+    fn diff_nested_field() {
+        let patch = diff(&outer("a", 1), &outer("a", 2)).unwrap();
+        assert_eq!(
+            patch.changes,
+            [(vec!["inner".into(), "id".into()], Value::Int(2))]
+        );
+        assert!(diff(&outer("a", 1), &outer("a", 1)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn apply_packed_patch() {
+        let old = outer("a", 1);
+        let mut new = outer("b", 1);
+        new.inner.tags.push(3);
+
+        let bytes = diff(&old, &new).unwrap().pack_to_vec().unwrap();
+        let patch = Patch::unpack_exact(&bytes).unwrap();
+        assert_eq!(patch.len(), 2);
+        assert_eq!(apply(&old, &patch).unwrap(), new);
+    }
+
+    #[test]
+    fn apply_unknown_field() {
+        let patch = Patch {
+            changes: vec![(vec!["missing".into()], Value::Null)],
+        };
+        let result = apply(&outer("a", 1), &patch);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,169 @@
+#[cfg(feature = "embedded-io-async")]
+use crate::decoder::Decoder;
+use crate::pack::{self, Pack};
+#[cfg(feature = "embedded-io-async")]
+use crate::unpack::Error;
+use crate::unpack::{Result, Unpack};
+use std::io;
+
+/// Adapter that exposes an `embedded_io` reader or writer as its `std::io`
+/// counterpart
+///
+/// Errors of the embedded stack are converted into an `io::Error` of the
+/// corresponding kind
+#[derive(Debug, Default)]
+pub struct FromEmbedded<T>(pub T);
+
+impl<T> FromEmbedded<T> {
+    /// Returns the wrapped reader or writer
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+fn convert_error(error: impl embedded_io::Error) -> io::Error {
+    io::Error::from(io::ErrorKind::from(error.kind()))
+}
+
+impl<T: embedded_io::Read> io::Read for FromEmbedded<T> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buffer).map_err(convert_error)
+    }
+}
+
+impl<T: embedded_io::Write> io::Write for FromEmbedded<T> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.0.write(buffer).map_err(convert_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush().map_err(convert_error)
+    }
+}
+
+/// Describes the ability to serialize this struct into an `embedded_io`
+/// writer
+///
+/// This trait is implemented for every type implementing [`Pack`]
+pub trait EmbeddedPack {
+    /// Tries to serialize this struct into an `embedded_io` writer
+    ///
+    /// Serialization may fail because of any error of the writer
+    fn pack_into_embedded(&self, writer: &mut impl embedded_io::Write) -> pack::Result<usize>;
+}
+
+impl<T: Pack + ?Sized> EmbeddedPack for T {
+    fn pack_into_embedded(&self, writer: &mut impl embedded_io::Write) -> pack::Result<usize> {
+        self.pack_into(&mut FromEmbedded(writer))
+    }
+}
+
+/// Describes the ability to deserialize a struct from an `embedded_io`
+/// reader
+///
+/// This trait is implemented for every type implementing [`Unpack`]
+pub trait EmbeddedUnpack {
+    /// Tries to deserialize this struct from an `embedded_io` reader
+    ///
+    /// Deserialization may fail for the same reasons as [`Unpack::unpack_from`]
+    fn unpack_from_embedded(reader: &mut impl embedded_io::Read) -> Result<Self>
+    where
+        Self: Sized;
+}
+
+impl<T: Unpack> EmbeddedUnpack for T {
+    fn unpack_from_embedded(reader: &mut impl embedded_io::Read) -> Result<Self> {
+        T::unpack_from(&mut FromEmbedded(reader))
+    }
+}
+
+/// Tries to serialize a value into an `embedded_io_async` writer
+///
+/// The value is packed into an intermediate buffer first, which is then
+/// written with a single call to `write_all`
+#[cfg(feature = "embedded-io-async")]
+pub async fn pack_into_embedded_async<T: Pack + ?Sized>(
+    value: &T,
+    writer: &mut impl embedded_io_async::Write,
+) -> pack::Result<usize> {
+    let buffer = value.pack_to_vec()?;
+    writer.write_all(&buffer).await.map_err(convert_error)?;
+    Ok(buffer.len())
+}
+
+/// Reads from an `embedded_io_async` reader until `decoder` yields a value
+///
+/// Bytes read behind the value stay in the decoder for the next call.
+/// Returns `Ok(None)` if the reader ends cleanly between two values and an
+/// `UnexpectedEof` error if it ends in the middle of a value
+#[cfg(feature = "embedded-io-async")]
+pub async fn unpack_next_embedded<T: Unpack>(
+    decoder: &mut Decoder<T>,
+    reader: &mut impl embedded_io_async::Read,
+) -> Result<Option<T>> {
+    let mut buffer = [0x00; 256];
+
+    loop {
+        if let Some(value) = decoder.poll_item()? {
+            return Ok(Some(value));
+        }
+
+        let read = reader.read(&mut buffer).await.map_err(convert_error)?;
+
+        if read == 0 {
+            return if decoder.is_empty() {
+                Ok(None)
+            } else {
+                Err(Error::IO(io::ErrorKind::UnexpectedEof.into()))
+            };
+        }
+
+        decoder.push(&buffer[..read]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unpack::Error;
+
+    #[test]
+    fn pack_embedded() {
+        let mut buffer = [0x00; 4];
+        let mut writer = buffer.as_mut_slice();
+        let written = 2u16.pack_into_embedded(&mut writer).unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(buffer, [0x00, 0x02, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn unpack_embedded() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x61, 0x62];
+        let mut reader = bytes.as_ref();
+        let value = String::unpack_from_embedded(&mut reader).unwrap();
+        assert_eq!(value, "ab");
+    }
+
+    #[test]
+    fn unpack_embedded_truncated() {
+        let bytes = [0x00];
+        let result = u16::unpack_from_embedded(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[cfg(feature = "embedded-io-async")]
+    #[test]
+    fn unpack_next_embedded_values() {
+        let bytes = [0x00, 0x01, 0x00, 0x02];
+        let mut reader = bytes.as_ref();
+        let mut decoder = Decoder::<u16>::new();
+        futures::executor::block_on(async {
+            let first = unpack_next_embedded(&mut decoder, &mut reader).await;
+            assert_eq!(first.unwrap(), Some(1));
+            let second = unpack_next_embedded(&mut decoder, &mut reader).await;
+            assert_eq!(second.unwrap(), Some(2));
+            let end = unpack_next_embedded(&mut decoder, &mut reader).await;
+            assert_eq!(end.unwrap(), None);
+        });
+    }
+}
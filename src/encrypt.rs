@@ -0,0 +1,136 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use chacha20poly1305::aead::{Aead, Generate, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::marker::PhantomData;
+
+pub use chacha20poly1305::Key;
+
+/// Generates a random key for [`Encrypted`] using the random number
+/// generator of the operating system
+pub fn generate_key() -> Key {
+    Key::generate()
+}
+
+/// Envelope holding a value encrypted with ChaCha20-Poly1305
+///
+/// The envelope is packed as the 12 byte nonce followed by the ciphertext
+/// including its authentication tag as a byte array. Packing and
+/// unpacking the envelope does not need the key, only [`Encrypted::seal`]
+/// and [`Encrypted::open`] do. Opening fails with
+/// `Error::AuthenticationFailed` if the envelope has been tampered with
+/// or the key is wrong
+pub struct Encrypted<T> {
+    nonce: Nonce,
+    ciphertext: Vec<u8>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Pack> Encrypted<T> {
+    /// Packs and encrypts a value with a fresh random nonce
+    pub fn seal(value: &T, key: &Key) -> pack::Result<Self> {
+        let nonce = Nonce::generate();
+        let plaintext = value.pack_to_vec()?;
+        let ciphertext = ChaCha20Poly1305::new(key)
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| io::Error::other("encryption failed"))?;
+
+        Ok(Self {
+            nonce,
+            ciphertext,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<T: Unpack> Encrypted<T> {
+    /// Authenticates and decrypts the envelope and unpacks its value
+    pub fn open(&self, key: &Key) -> Result<T> {
+        let plaintext = ChaCha20Poly1305::new(key)
+            .decrypt(&self.nonce, self.ciphertext.as_slice())
+            .map_err(|_| Error::AuthenticationFailed)?;
+        T::unpack_exact(&plaintext)
+    }
+}
+
+impl<T> Clone for Encrypted<T> {
+    fn clone(&self) -> Self {
+        Self {
+            nonce: self.nonce,
+            ciphertext: self.ciphertext.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for Encrypted<T> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_struct("Encrypted")
+            .field("len", &self.ciphertext.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Pack for Encrypted<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        writer.write_all(&self.nonce)?;
+        let written = self.ciphertext.as_slice().pack_into(writer)?;
+        Ok(self.nonce.len() + written)
+    }
+}
+
+impl<T> Unpack for Encrypted<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut nonce = Nonce::default();
+        reader.read_exact(&mut nonce).map_err(Error::IO)?;
+        let len = u32::unpack_from(reader)? as usize;
+        let ciphertext = read_bytes(reader, len)?;
+
+        Ok(Self {
+            nonce,
+            ciphertext,
+            marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_round_trip() {
+        let key = generate_key();
+        let envelope = Encrypted::seal(&"secret", &key).unwrap();
+        let bytes = envelope.pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 12 + 4 + 10 + 16);
+
+        let envelope = Encrypted::<String>::unpack_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(envelope.open(&key).unwrap(), "secret");
+    }
+
+    #[test]
+    fn open_with_wrong_key() {
+        let envelope = Encrypted::<u16>::seal(&2, &generate_key()).unwrap();
+        let result = envelope.open(&generate_key());
+        assert!(matches!(result, Err(Error::AuthenticationFailed)));
+    }
+
+    #[test]
+    fn open_tampered() {
+        let key = generate_key();
+        let mut bytes = Encrypted::<u16>::seal(&2, &key)
+            .unwrap()
+            .pack_to_vec()
+            .unwrap();
+        bytes[16] ^= 0x01;
+        let envelope = Encrypted::<u16>::unpack_from(&mut bytes.as_slice()).unwrap();
+        assert!(matches!(
+            envelope.open(&key),
+            Err(Error::AuthenticationFailed)
+        ));
+    }
+}
@@ -0,0 +1,109 @@
+//! Helpers for fuzzing packed formats, e.g. with `cargo fuzz`
+//!
+//! # Example
+//!
+//! A fuzz target checking a message type only needs a single line:
+//!
+//! ```
+//! use serial_container::fuzz::fuzz_roundtrip;
+//! use std::collections::BTreeMap;
+//!
+//! // fuzz_target!(|data: &[u8]| fuzz_roundtrip::<BTreeMap<u16, i64>>(data));
+//! fuzz_roundtrip::<BTreeMap<u16, i64>>(&[0x00, 0x00, 0x00, 0x01, 0x12, 0x34]);
+//! ```
+
+use crate::pack::Pack;
+use crate::unpack::Unpack;
+use arbitrary::{Arbitrary, Unstructured};
+use std::fmt::Debug;
+
+/// Fuzzing entry point checking the packed format of `T`
+///
+/// First unpacks `data` directly, which may fail but must never panic.
+/// Then derives an arbitrary value from `data` and checks it with
+/// [`assert_roundtrip`]
+///
+/// # Panics
+///
+/// Panics if any of the checks fails, which the fuzzer reports as a crash
+pub fn fuzz_roundtrip<T>(data: &[u8])
+where
+    T: Pack + Unpack + for<'a> Arbitrary<'a> + PartialEq + Debug,
+{
+    let _ = T::unpack_from_slice(data);
+
+    if let Ok(value) = T::arbitrary_take_rest(Unstructured::new(data)) {
+        assert_roundtrip(&value);
+    }
+}
+
+/// Checks that a value survives a round trip through its packed form
+///
+/// The unpacked copy has to be equal to the value or pack into the same
+/// bytes, so NaN floats pass the check as well as hash maps with a
+/// different iteration order
+///
+/// # Panics
+///
+/// Panics if the value cannot be packed, its packed form cannot be unpacked
+/// completely or the unpacked copy packs differently
+pub fn assert_roundtrip<T: Pack + Unpack + PartialEq + Debug>(value: &T) {
+    let bytes = value
+        .pack_to_vec()
+        .unwrap_or_else(|error| panic!("failed to pack {:?}: {}", value, error));
+    let copy = T::unpack_exact(&bytes)
+        .unwrap_or_else(|error| panic!("failed to unpack {:?}: {}", value, error));
+
+    if copy == *value {
+        return;
+    }
+
+    let copy_bytes = copy
+        .pack_to_vec()
+        .unwrap_or_else(|error| panic!("failed to pack {:?}: {}", copy, error));
+    assert_eq!(
+        bytes, copy_bytes,
+        "{:?} changed to {:?} in a round trip",
+        value, copy
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashMap};
+    use std::num::NonZeroU32;
+
+    #[test]
+    fn fuzz_primitives_and_collections() {
+        let inputs: [&[u8]; 4] = [
+            &[],
+            &[0xFF; 64],
+            &[0x00, 0x00, 0x00, 0x05, 0x61, 0x62],
+            &[0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0, 0x01, 0x02],
+        ];
+
+        for data in inputs {
+            fuzz_roundtrip::<[f32; 3]>(data);
+            fuzz_roundtrip::<BTreeSet<i16>>(data);
+            fuzz_roundtrip::<HashMap<u8, bool>>(data);
+            fuzz_roundtrip::<[Option<NonZeroU32>; 2]>(data);
+        }
+    }
+
+    #[test]
+    fn unpack_zero_as_non_zero() {
+        fuzz_roundtrip::<NonZeroU32>(&[0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn unpack_forged_length() {
+        fuzz_roundtrip::<[u64; 2]>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+        fuzz_roundtrip::<HashMap<u64, u64>>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+    }
+
+    #[test]
+    fn roundtrip_nan() {
+        assert_roundtrip(&f64::NAN);
+    }
+}
@@ -0,0 +1,186 @@
+//! Golden snapshot tests for packed formats
+//!
+//! A golden test packs a value and compares the bytes with a snapshot file
+//! committed to the repository, so an unintended change of the wire format
+//! fails the test suite instead of breaking downstream readers
+//!
+//! Missing snapshot files are created on the first run. Set the environment
+//! variable `SERIAL_CONTAINER_UPDATE_GOLDEN=1` to overwrite snapshots after
+//! an intended change of the wire format
+//!
+//! # Example
+//!
+//! ```no_run
+//! use serial_container::golden::assert_golden;
+//!
+//! assert_golden(&[1u16, 2, 3], "tests/golden/numbers.bin");
+//! ```
+
+use crate::pack::Pack;
+use std::fmt::Write;
+use std::path::Path;
+use std::{env, fs, io};
+
+/// Environment variable that makes golden tests overwrite their snapshots
+pub const UPDATE_VARIABLE: &str = "SERIAL_CONTAINER_UPDATE_GOLDEN";
+
+/// Packs the value and compares the bytes with the snapshot file at `path`
+///
+/// # Panics
+///
+/// Panics with a hex diff of the expected and actual bytes if they differ,
+/// or if the value cannot be packed or the snapshot cannot be accessed
+pub fn assert_golden<T: Pack + ?Sized>(value: &T, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+
+    if let Err(message) = check_golden(value, path) {
+        panic!("{}", message);
+    }
+}
+
+/// Packs the value and compares the bytes with the snapshot file at `path`
+///
+/// Returns a message with a hex diff of the expected and actual bytes if
+/// they differ
+pub fn check_golden<T: Pack + ?Sized>(value: &T, path: impl AsRef<Path>) -> Result<(), String> {
+    let path = path.as_ref();
+    let actual = value
+        .pack_to_vec()
+        .map_err(|error| format!("failed to pack value: {}", error))?;
+    let update = env::var_os(UPDATE_VARIABLE).is_some_and(|value| value != "0");
+
+    let expected = match fs::read(path) {
+        Ok(expected) if !update => expected,
+        Err(error) if !update && error.kind() != io::ErrorKind::NotFound => {
+            return Err(format!(
+                "failed to read golden snapshot {}: {}",
+                path.display(),
+                error
+            ));
+        }
+        _ => {
+            return write_snapshot(path, &actual).map_err(|error| {
+                format!(
+                    "failed to write golden snapshot {}: {}",
+                    path.display(),
+                    error
+                )
+            });
+        }
+    };
+
+    if expected == actual {
+        return Ok(());
+    }
+
+    Err(format!(
+        "golden snapshot {} does not match the packed value\n{}\nrerun with {}=1 to update the snapshot",
+        path.display(),
+        hex_diff(&expected, &actual),
+        UPDATE_VARIABLE
+    ))
+}
+
+/// Returns a readable hex diff of two byte sequences
+///
+/// Only rows of 16 bytes that differ are listed, the expected row prefixed
+/// with `-` and the actual row prefixed with `+`
+pub fn hex_diff(expected: &[u8], actual: &[u8]) -> String {
+    let mut output = String::new();
+    let first_difference = expected
+        .iter()
+        .zip(actual)
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or(expected.len().min(actual.len()));
+    let _ = writeln!(
+        output,
+        "expected {} bytes, found {} bytes, first difference at offset {:#x}",
+        expected.len(),
+        actual.len(),
+        first_difference
+    );
+
+    let rows = expected.len().max(actual.len()).div_ceil(16);
+
+    for row in 0..rows {
+        let range = row * 16..(row + 1) * 16;
+        let expected_row = row_bytes(expected, range.clone());
+        let actual_row = row_bytes(actual, range);
+
+        if expected_row == actual_row {
+            continue;
+        }
+
+        let _ = writeln!(output, "{:08x} - {}", row * 16, hex_row(expected_row));
+        let _ = writeln!(output, "{:08x} + {}", row * 16, hex_row(actual_row));
+    }
+
+    output
+}
+
+fn row_bytes(bytes: &[u8], range: std::ops::Range<usize>) -> &[u8] {
+    let start = range.start.min(bytes.len());
+    let end = range.end.min(bytes.len());
+    &bytes[start..end]
+}
+
+fn hex_row(bytes: &[u8]) -> String {
+    let mut output = String::new();
+
+    for byte in bytes {
+        let _ = write!(output, "{:02x} ", byte);
+    }
+
+    output.trim_end().to_string()
+}
+
+fn write_snapshot(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot_path(name: &str) -> std::path::PathBuf {
+        let path = env::temp_dir()
+            .join(format!("serial_container_golden_{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn create_and_match_snapshot() {
+        let path = snapshot_path("numbers.bin");
+        assert_golden(&[1u16, 2], &path);
+        assert_eq!(fs::read(&path).unwrap(), [1u16, 2].pack_to_vec().unwrap());
+        assert_golden(&[1u16, 2], &path);
+    }
+
+    #[test]
+    fn report_mismatch() {
+        let path = snapshot_path("mismatch.bin");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, [0x00, 0x00, 0x00, 0x01, 0x00, 0x01]).unwrap();
+
+        let message = check_golden(&[1u16, 3], &path).unwrap_err();
+        assert!(message.contains("expected 6 bytes, found 8 bytes, first difference at offset 0x3"));
+        assert!(message.contains("00000000 - 00 00 00 01 00 01\n"));
+        assert!(message.contains("00000000 + 00 00 00 02 00 01 00 03\n"));
+    }
+
+    #[test]
+    fn hex_diff_skips_equal_rows() {
+        let expected = [0u8; 32];
+        let mut actual = expected;
+        actual[20] = 0xFF;
+        let diff = hex_diff(&expected, &actual);
+        assert!(!diff.contains("00000000 -"));
+        assert!(diff.contains("00000010 + 00 00 00 00 ff"));
+    }
+}
@@ -0,0 +1,241 @@
+//! Negotiation of format version, schemas and features between two peers
+//!
+//! Both peers send a [`Hello`] right after connecting and read the hello
+//! of the other side. [`negotiate`] compares them and returns the common
+//! [`Config`], or fails if both peers know a schema under the same name but
+//! with a different fingerprint, which would otherwise silently corrupt
+//! every value exchanged later
+//!
+//! ```
+//! use serial_container::handshake::{negotiate, Hello};
+//! use serial_container::schema::Schema;
+//!
+//! let local = Hello::new()
+//!     .with_schema("Point", &Schema::structure("Point").field("x", Schema::I32))
+//!     .with_feature("compression");
+//! let remote = local.clone().with_feature("encryption");
+//!
+//! let config = negotiate(&local, &remote).unwrap();
+//! assert!(config.has_schema("Point"));
+//! assert!(config.has_feature("compression"));
+//! assert!(!config.has_feature("encryption"));
+//! ```
+
+use crate::pack::{self, checked_len, Pack};
+use crate::schema::Schema;
+use crate::unpack::{Error, Result, Unpack};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+
+/// Version of the wire format of this crate
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Description of a peer sent at the start of a connection
+///
+/// Packed as the format version (`u16`), the schemas as a length-prefixed
+/// sequence of names and fingerprints (`u64`) and the features as a
+/// length-prefixed sequence of names, both sorted by name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hello {
+    pub format_version: u16,
+    pub schemas: BTreeMap<String, u64>,
+    pub features: BTreeSet<String>,
+}
+
+impl Hello {
+    /// Creates a hello of the current format version without any schemas
+    /// or features
+    pub fn new() -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            schemas: BTreeMap::new(),
+            features: BTreeSet::new(),
+        }
+    }
+
+    /// Adds the fingerprint of a schema under the given name
+    pub fn with_schema(mut self, name: impl Into<String>, schema: &Schema) -> Self {
+        self.schemas.insert(name.into(), schema.fingerprint());
+        self
+    }
+
+    /// Adds a supported feature
+    pub fn with_feature(mut self, name: impl Into<String>) -> Self {
+        self.features.insert(name.into());
+        self
+    }
+}
+
+impl Default for Hello {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pack for Hello {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = self.format_version.pack_into(writer)?;
+        written += checked_len(self.schemas.len())?.pack_into(writer)?;
+
+        for (name, fingerprint) in &self.schemas {
+            written += name.pack_into(writer)?;
+            written += fingerprint.pack_into(writer)?;
+        }
+
+        written += checked_len(self.features.len())?.pack_into(writer)?;
+
+        for name in &self.features {
+            written += name.pack_into(writer)?;
+        }
+
+        Ok(written)
+    }
+}
+
+impl Unpack for Hello {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let format_version = u16::unpack_from(reader)?;
+        let mut schemas = BTreeMap::new();
+
+        for _i in 0..u32::unpack_from(reader)? {
+            let name = String::unpack_from(reader)?;
+            schemas.insert(name, u64::unpack_from(reader)?);
+        }
+
+        let mut features = BTreeSet::new();
+
+        for _i in 0..u32::unpack_from(reader)? {
+            features.insert(String::unpack_from(reader)?);
+        }
+
+        Ok(Hello {
+            format_version,
+            schemas,
+            features,
+        })
+    }
+}
+
+/// Result of a successful negotiation
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Config {
+    /// Lower of the format versions of both peers
+    pub format_version: u16,
+    /// Names of the schemas known to both peers with equal fingerprints
+    pub schemas: BTreeSet<String>,
+    /// Features supported by both peers
+    pub features: BTreeSet<String>,
+}
+
+impl Config {
+    /// Returns whether both peers agree on the schema with the given name
+    pub fn has_schema(&self, name: &str) -> bool {
+        self.schemas.contains(name)
+    }
+
+    /// Returns whether both peers support the feature with the given name
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.features.contains(name)
+    }
+}
+
+/// Compares the hellos of both peers and returns the common configuration
+///
+/// A peer of a newer format version is expected to fall back to the lower
+/// version. Fails with `Error::UnsupportedVersion` if the remote peer
+/// announces the invalid format version zero and with `Error::InvalidValue`
+/// if both peers know a schema under the same name but with different
+/// fingerprints
+pub fn negotiate(local: &Hello, remote: &Hello) -> Result<Config> {
+    if remote.format_version == 0 {
+        return Err(Error::UnsupportedVersion(remote.format_version));
+    }
+
+    let mut schemas = BTreeSet::new();
+
+    for (name, fingerprint) in &local.schemas {
+        match remote.schemas.get(name) {
+            Some(remote) if remote == fingerprint => {
+                schemas.insert(name.clone());
+            }
+            Some(_) => {
+                return Err(Error::InvalidValue(format!(
+                    "schema {} differs between the peers",
+                    name
+                )))
+            }
+            None => {}
+        }
+    }
+
+    Ok(Config {
+        format_version: local.format_version.min(remote.format_version),
+        schemas,
+        features: local
+            .features
+            .intersection(&remote.features)
+            .cloned()
+            .collect(),
+    })
+}
+
+/// Sends the local hello as a frame, reads the hello of the remote peer and
+/// negotiates the common configuration
+pub fn handshake(stream: &mut (impl io::Read + io::Write), local: &Hello) -> Result<Config> {
+    local.pack_framed(stream)?;
+    stream.flush()?;
+    let remote = Hello::unpack_framed(stream)?;
+    negotiate(local, &remote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    fn hello(x: &str) -> Hello {
+        Hello::new()
+            .with_schema("Point", &Schema::structure("Point").field(x, Schema::I32))
+            .with_schema("Id", &Schema::U64)
+            .with_feature("a")
+    }
+
+    #[test]
+    fn hello_round_trip() {
+        let hello = hello("x");
+        let bytes = hello.pack_to_vec().unwrap();
+        assert_eq!(bytes[..6], [0x00, 0x01, 0x00, 0x00, 0x00, 0x02]);
+        assert_eq!(Hello::unpack_exact(&bytes).unwrap(), hello);
+    }
+
+    #[test]
+    fn schema_mismatch() {
+        let result = negotiate(&hello("x"), &hello("y"));
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+
+        let remote = Hello::new().with_schema("Id", &Schema::U64);
+        let config = negotiate(&hello("x"), &remote).unwrap();
+        assert!(config.has_schema("Id"));
+        assert!(!config.has_schema("Point"));
+        assert!(config.features.is_empty());
+    }
+
+    #[test]
+    fn handshake_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut connection, _) = listener.accept().unwrap();
+            handshake(&mut connection, &hello("x").with_feature("b")).unwrap()
+        });
+
+        let mut connection = TcpStream::connect(address).unwrap();
+        let config = handshake(&mut connection, &hello("x")).unwrap();
+        assert_eq!(config, server.join().unwrap());
+        assert_eq!(config.format_version, FORMAT_VERSION);
+        assert_eq!(config.schemas.len(), 2);
+        assert!(config.has_feature("a") && !config.has_feature("b"));
+    }
+}
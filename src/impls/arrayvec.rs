@@ -0,0 +1,93 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use ::arrayvec::{ArrayString, ArrayVec};
+use std::io;
+
+impl<T: Pack, const N: usize> Pack for ArrayVec<T, N> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_slice().pack_into(writer)
+    }
+}
+
+impl<const N: usize> Pack for ArrayString<N> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_str().pack_into(writer)
+    }
+}
+
+impl<T: Unpack, const N: usize> Unpack for ArrayVec<T, N> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+
+        if len > N {
+            return Err(Error::CapacityExceeded { len, capacity: N });
+        }
+
+        let mut result = ArrayVec::new();
+
+        for _i in 0..len {
+            result.push(T::unpack_from(reader)?);
+        }
+
+        Ok(result)
+    }
+}
+
+impl<const N: usize> Unpack for ArrayString<N> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+
+        if len > N {
+            return Err(Error::CapacityExceeded { len, capacity: N });
+        }
+
+        let mut bytes = [0x00; N];
+        reader.read_exact(&mut bytes[..len])?;
+        let value = std::str::from_utf8(&bytes[..len]).map_err(Error::UTF8Ref)?;
+        Ok(ArrayString::from(value).expect("length has been checked against the capacity"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrayvec_round_trip() {
+        let value = ArrayVec::<u8, 4>::from_iter([1, 2]);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x02, 0x01, 0x02]);
+        assert_eq!(ArrayVec::<u8, 4>::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn unpack_arrayvec_over_capacity() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03];
+        let result = ArrayVec::<u8, 2>::unpack_exact(&bytes);
+        assert!(matches!(
+            result,
+            Err(Error::CapacityExceeded {
+                len: 3,
+                capacity: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn arraystring_round_trip() {
+        let value = ArrayString::<8>::from("abc").unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63]);
+        assert_eq!(ArrayString::<8>::unpack_exact(&bytes).unwrap(), value);
+
+        let result = ArrayString::<2>::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::CapacityExceeded { .. })));
+    }
+
+    #[test]
+    fn unpack_arraystring_invalid_utf8() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0xC3, 0x28];
+        let result = ArrayString::<4>::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::UTF8Ref(_))));
+    }
+}
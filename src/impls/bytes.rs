@@ -0,0 +1,51 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{read_bytes, Result, Unpack};
+use ::bytes::{Bytes, BytesMut};
+use std::io;
+
+impl Pack for Bytes {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_ref().pack_into(writer)
+    }
+}
+
+impl Pack for BytesMut {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_ref().pack_into(writer)
+    }
+}
+
+impl Unpack for Bytes {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        read_bytes(reader, len).map(Bytes::from)
+    }
+}
+
+impl Unpack for BytesMut {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        read_bytes(reader, len).map(|bytes| BytesMut::from(bytes.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        let value = Bytes::from_static(&[1, 2, 3]);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03]);
+        assert_eq!(Bytes::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn bytes_mut_round_trip() {
+        let value = BytesMut::from(&b"abc"[..]);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, "abc".pack_to_vec().unwrap());
+        assert_eq!(BytesMut::unpack_exact(&bytes).unwrap(), value);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use ::chrono::{
+    DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc,
+};
+use std::io;
+
+/// Days between the first day of the common era and the Unix epoch
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+fn from_timestamp(seconds: i64, nanoseconds: u32) -> Result<DateTime<Utc>> {
+    DateTime::from_timestamp(seconds, nanoseconds).ok_or_else(|| {
+        Error::InvalidValue(format!(
+            "timestamp {}.{:09} is out of range",
+            seconds, nanoseconds
+        ))
+    })
+}
+
+/// Packed as the seconds since the Unix epoch (`i64`) followed by the
+/// nanoseconds within the second (`u32`)
+impl Pack for DateTime<Utc> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = self.timestamp().pack_into(writer)?;
+        Ok(written + self.timestamp_subsec_nanos().pack_into(writer)?)
+    }
+}
+
+/// Packed as the UTC timestamp followed by the offset from UTC in seconds
+/// (`i32`)
+impl Pack for DateTime<FixedOffset> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = self.to_utc().pack_into(writer)?;
+        Ok(written + self.offset().local_minus_utc().pack_into(writer)?)
+    }
+}
+
+/// Packed like `DateTime<Utc>`, as if the date and time were in UTC
+impl Pack for NaiveDateTime {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.and_utc().pack_into(writer)
+    }
+}
+
+/// Packed as the days since the Unix epoch (`i32`)
+impl Pack for NaiveDate {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        (self.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE).pack_into(writer)
+    }
+}
+
+/// Packed as the seconds since midnight (`u32`) followed by the nanoseconds
+/// within the second (`u32`)
+impl Pack for NaiveTime {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = self.num_seconds_from_midnight().pack_into(writer)?;
+        Ok(written + self.nanosecond().pack_into(writer)?)
+    }
+}
+
+impl Unpack for DateTime<Utc> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let seconds = i64::unpack_from(reader)?;
+        let nanoseconds = u32::unpack_from(reader)?;
+        from_timestamp(seconds, nanoseconds)
+    }
+}
+
+impl Unpack for DateTime<FixedOffset> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let utc = DateTime::<Utc>::unpack_from(reader)?;
+        let seconds = i32::unpack_from(reader)?;
+        let offset = FixedOffset::east_opt(seconds).ok_or_else(|| {
+            Error::InvalidValue(format!("offset of {} seconds is out of range", seconds))
+        })?;
+        Ok(utc.with_timezone(&offset))
+    }
+}
+
+impl Unpack for NaiveDateTime {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        DateTime::<Utc>::unpack_from(reader).map(|value| value.naive_utc())
+    }
+}
+
+impl Unpack for NaiveDate {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let days = i32::unpack_from(reader)?;
+        days.checked_add(UNIX_EPOCH_DAYS_FROM_CE)
+            .and_then(NaiveDate::from_num_days_from_ce_opt)
+            .ok_or_else(|| Error::InvalidValue(format!("{} days are out of range", days)))
+    }
+}
+
+impl Unpack for NaiveTime {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let seconds = u32::unpack_from(reader)?;
+        let nanoseconds = u32::unpack_from(reader)?;
+        NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanoseconds).ok_or_else(|| {
+            Error::InvalidValue(format!(
+                "time {}.{:09} is out of range",
+                seconds, nanoseconds
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utc_round_trip() {
+        let value = DateTime::from_timestamp(1_700_000_000, 5).unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(
+            bytes,
+            [0x00, 0x00, 0x00, 0x00, 0x65, 0x53, 0xF1, 0x00, 0x00, 0x00, 0x00, 0x05]
+        );
+        assert_eq!(DateTime::<Utc>::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn fixed_offset_round_trip() {
+        let value = DateTime::parse_from_rfc3339("2024-02-29T12:30:00.25+02:00").unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        let result = DateTime::<FixedOffset>::unpack_exact(&bytes).unwrap();
+        assert_eq!(result, value);
+        assert_eq!(result.offset(), value.offset());
+    }
+
+    #[test]
+    fn naive_round_trip() {
+        let date = NaiveDate::from_ymd_opt(1969, 12, 31).unwrap();
+        assert_eq!(date.pack_to_vec().unwrap(), (-1i32).to_be_bytes());
+
+        let time = NaiveTime::from_hms_nano_opt(23, 59, 59, 1).unwrap();
+        let value = date.and_time(time);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(NaiveDateTime::unpack_exact(&bytes).unwrap(), value);
+
+        let bytes = time.pack_to_vec().unwrap();
+        assert_eq!(NaiveTime::unpack_exact(&bytes).unwrap(), time);
+    }
+
+    #[test]
+    fn unpack_out_of_range() {
+        let mut bytes = i64::MAX.pack_to_vec().unwrap();
+        0u32.pack_into(&mut bytes).unwrap();
+        let result = DateTime::<Utc>::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
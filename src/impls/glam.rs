@@ -0,0 +1,104 @@
+use crate::pack::{self, pack_fixed, Pack};
+use crate::unpack::{Result, Unpack};
+use ::glam::*;
+use std::io;
+
+/// Implements the traits for glam types packing their components one
+/// after another without a length prefix: vectors and quaternions in the
+/// order x, y, z, w and matrices in column-major order
+macro_rules! impl_components {
+    ($($ty:ty: [$scalar:ty; $n:expr], $to:ident, $from:ident;)*) => {$(
+        impl Pack for $ty {
+            const PACKED_SIZE: Option<usize> = Some($n * std::mem::size_of::<$scalar>());
+
+            fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+                let mut written = 0;
+
+                for component in self.$to() {
+                    written += component.pack_into(writer)?;
+                }
+
+                Ok(written)
+            }
+        }
+
+        pack_fixed!($ty);
+
+        impl Unpack for $ty {
+            const PACKED_SIZE: Option<usize> = Some($n * std::mem::size_of::<$scalar>());
+
+            fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+                let mut components = [<$scalar>::default(); $n];
+
+                for component in components.iter_mut() {
+                    *component = <$scalar>::unpack_from(reader)?;
+                }
+
+                Ok(<$ty>::$from(&components))
+            }
+        }
+    )*};
+}
+
+impl_components! {
+    Vec2: [f32; 2], to_array, from_slice;
+    Vec3: [f32; 3], to_array, from_slice;
+    Vec3A: [f32; 3], to_array, from_slice;
+    Vec4: [f32; 4], to_array, from_slice;
+    Quat: [f32; 4], to_array, from_slice;
+    Mat2: [f32; 4], to_cols_array, from_cols_slice;
+    Mat3: [f32; 9], to_cols_array, from_cols_slice;
+    Mat4: [f32; 16], to_cols_array, from_cols_slice;
+    DVec2: [f64; 2], to_array, from_slice;
+    DVec3: [f64; 3], to_array, from_slice;
+    DVec4: [f64; 4], to_array, from_slice;
+    DQuat: [f64; 4], to_array, from_slice;
+    DMat2: [f64; 4], to_cols_array, from_cols_slice;
+    DMat3: [f64; 9], to_cols_array, from_cols_slice;
+    DMat4: [f64; 16], to_cols_array, from_cols_slice;
+    IVec2: [i32; 2], to_array, from_slice;
+    IVec3: [i32; 3], to_array, from_slice;
+    IVec4: [i32; 4], to_array, from_slice;
+    UVec2: [u32; 2], to_array, from_slice;
+    UVec3: [u32; 3], to_array, from_slice;
+    UVec4: [u32; 4], to_array, from_slice;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::PackFixed;
+
+    #[test]
+    fn vec3_round_trip() {
+        let value = Vec3::new(1.0, 2.0, 3.0);
+        let bytes = value.pack_to_vec().unwrap();
+        let mut expected = Vec::new();
+        [1.0f32, 2.0, 3.0].iter().for_each(|x| {
+            x.pack_into(&mut expected).unwrap();
+        });
+        assert_eq!(bytes, expected);
+        assert_eq!(Vec3::unpack_exact(&bytes).unwrap(), value);
+        assert_eq!(Vec3::SIZE, bytes.len());
+    }
+
+    #[test]
+    fn mat4_is_column_major() {
+        let value = Mat4::from_translation(Vec3::new(5.0, 6.0, 7.0));
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 64);
+        assert_eq!(f32::unpack_exact(&bytes[48..52]).unwrap(), 5.0);
+        assert_eq!(Mat4::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn quat_and_integer_vectors_round_trip() {
+        let value = (DQuat::from_rotation_z(0.5), IVec2::new(-1, 2));
+        let mut bytes = value.0.pack_to_vec().unwrap();
+        value.1.pack_into(&mut bytes).unwrap();
+
+        let mut reader = bytes.as_slice();
+        assert_eq!(DQuat::unpack_from(&mut reader).unwrap(), value.0);
+        assert_eq!(IVec2::unpack_from(&mut reader).unwrap(), value.1);
+    }
+}
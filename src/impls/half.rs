@@ -0,0 +1,73 @@
+use crate::pack::{self, pack_fixed, write_bytes, Pack};
+use crate::unpack::{unpack_words, Result, Unpack};
+use ::half::{bf16, f16};
+use std::io;
+
+impl Pack for f16 {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        write_bytes(writer, &self.to_be_bytes())
+    }
+}
+
+impl Pack for bf16 {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        write_bytes(writer, &self.to_be_bytes())
+    }
+}
+
+impl Unpack for f16 {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut bytes = [0x00; 2];
+        reader.read_exact(&mut bytes)?;
+        Ok(f16::from_be_bytes(bytes))
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, f16::from_be_bytes)
+    }
+}
+
+impl Unpack for bf16 {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut bytes = [0x00; 2];
+        reader.read_exact(&mut bytes)?;
+        Ok(bf16::from_be_bytes(bytes))
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, bf16::from_be_bytes)
+    }
+}
+
+pack_fixed!(f16, bf16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::PackFixed;
+
+    #[test]
+    fn f16_round_trip() {
+        let value = f16::from_f32(1.5);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x3E, 0x00]);
+        assert_eq!(f16::unpack_exact(&bytes).unwrap(), value);
+        assert_eq!((f16::SIZE, bf16::SIZE), (2, 2));
+    }
+
+    #[test]
+    fn bf16_slice_round_trip() {
+        let values = vec![bf16::from_f32(1.0), bf16::from_f32(-2.0)];
+        let bytes = values.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x02, 0x3F, 0x80, 0xC0, 0x00]);
+        assert_eq!(Vec::<bf16>::unpack_exact(&bytes).unwrap(), values);
+    }
+}
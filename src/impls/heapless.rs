@@ -0,0 +1,117 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use std::io;
+
+impl<T: Pack, const N: usize> Pack for ::heapless::Vec<T, N> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_slice().pack_into(writer)
+    }
+}
+
+impl<const N: usize> Pack for ::heapless::String<N> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_str().pack_into(writer)
+    }
+}
+
+impl<T: Unpack, const N: usize> Unpack for ::heapless::Vec<T, N> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+
+        if len > N {
+            return Err(Error::CapacityExceeded { len, capacity: N });
+        }
+
+        let mut result = ::heapless::Vec::new();
+
+        for _i in 0..len {
+            if result.push(T::unpack_from(reader)?).is_err() {
+                unreachable!("length has been checked against the capacity");
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl<const N: usize> Unpack for ::heapless::String<N> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+
+        if len > N {
+            return Err(Error::CapacityExceeded { len, capacity: N });
+        }
+
+        let mut bytes = [0x00; N];
+        reader.read_exact(&mut bytes[..len])?;
+        let value = std::str::from_utf8(&bytes[..len]).map_err(Error::UTF8Ref)?;
+        let mut result = ::heapless::String::new();
+        result
+            .push_str(value)
+            .expect("length has been checked against the capacity");
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_heapless_vec() {
+        let value = ::heapless::Vec::<u16, 4>::from_slice(&[1, 2]).unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn unpack_heapless_vec() {
+        type Array = ::heapless::Vec<u8, 3>;
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03];
+        let value = Array::unpack_from(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, [1, 2, 3]);
+    }
+
+    #[test]
+    fn unpack_heapless_vec_over_capacity() {
+        type Array = ::heapless::Vec<u8, 2>;
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03];
+        let result = Array::unpack_from(&mut bytes.as_ref());
+        assert!(matches!(
+            result,
+            Err(Error::CapacityExceeded {
+                len: 3,
+                capacity: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn heapless_string_round_trip() {
+        type Text = ::heapless::String<8>;
+        let value = Text::try_from("abc").unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63]);
+        assert_eq!(Text::unpack_from(&mut bytes.as_slice()).unwrap(), value);
+    }
+
+    #[test]
+    fn unpack_heapless_string_over_capacity() {
+        type Text = ::heapless::String<2>;
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63];
+        let error = Text::unpack_from(&mut bytes.as_ref()).unwrap_err();
+        assert!(matches!(error, Error::CapacityExceeded { .. }));
+        assert_eq!(error.to_string(), "length 3 exceeds the capacity of 2");
+    }
+
+    #[test]
+    fn unpack_heapless_string_invalid_utf8() {
+        type Text = ::heapless::String<4>;
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0xC3, 0x28];
+        let result = Text::unpack_from(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::UTF8Ref(_))));
+
+        let result = Text::unpack_from(&mut [0x00, 0x00, 0x00, 0x02, 0x61].as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+}
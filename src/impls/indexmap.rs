@@ -0,0 +1,90 @@
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{initial_capacity, Result, Unpack};
+use ::indexmap::{IndexMap, IndexSet};
+use std::hash::{BuildHasher, Hash};
+use std::io;
+
+/// Packed like a `HashMap`, with the entries in insertion order
+impl<K: Pack, V: Pack, S> Pack for IndexMap<K, V, S> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let len = checked_len(self.len())?;
+        let mut written = len.pack_into(writer)?;
+
+        for (key, value) in self.iter() {
+            written += key.pack_into(writer)?;
+            written += value.pack_into(writer)?;
+        }
+
+        Ok(written)
+    }
+}
+
+/// Packed like a `HashSet`, with the values in insertion order
+impl<T: Pack, S> Pack for IndexSet<T, S> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let len = checked_len(self.len())?;
+        let mut written = len.pack_into(writer)?;
+
+        for value in self.iter() {
+            written += value.pack_into(writer)?;
+        }
+
+        Ok(written)
+    }
+}
+
+impl<K: Unpack + Eq + Hash, V: Unpack, S: BuildHasher + Default> Unpack for IndexMap<K, V, S> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        let mut result =
+            IndexMap::with_capacity_and_hasher(initial_capacity::<(K, V)>(len), S::default());
+
+        for _i in 0..len {
+            let key = K::unpack_from(reader)?;
+            let value = V::unpack_from(reader)?;
+            result.insert(key, value);
+        }
+
+        Ok(result)
+    }
+}
+
+impl<T: Unpack + Eq + Hash, S: BuildHasher + Default> Unpack for IndexSet<T, S> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        let mut result =
+            IndexSet::with_capacity_and_hasher(initial_capacity::<T>(len), S::default());
+
+        for _i in 0..len {
+            result.insert(T::unpack_from(reader)?);
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexmap_keeps_order() {
+        let value: IndexMap<u8, bool> = [(3, true), (1, false), (2, true)].into_iter().collect();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(
+            bytes,
+            [0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x01, 0xFF, 0x02, 0x00]
+        );
+
+        let result = IndexMap::<u8, bool>::unpack_exact(&bytes).unwrap();
+        assert!(result.keys().eq([3, 1, 2].iter()));
+    }
+
+    #[test]
+    fn indexset_keeps_order() {
+        let value: IndexSet<u16> = [5, 1, 3].into_iter().collect();
+        let bytes = value.pack_to_vec().unwrap();
+        let result = IndexSet::<u16>::unpack_exact(&bytes).unwrap();
+        assert!(result.iter().eq(value.iter()));
+    }
+}
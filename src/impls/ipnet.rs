@@ -0,0 +1,114 @@
+use crate::pack::{self, pack_fixed, write_bytes, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use ::ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use std::io;
+
+/// Packed as the 4 octets of the address followed by the prefix length
+/// (`u8`)
+impl Pack for Ipv4Net {
+    const PACKED_SIZE: Option<usize> = Some(5);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = write_bytes(writer, &self.addr().octets())?;
+        Ok(written + self.prefix_len().pack_into(writer)?)
+    }
+}
+
+/// Packed as the 16 octets of the address followed by the prefix length
+/// (`u8`)
+impl Pack for Ipv6Net {
+    const PACKED_SIZE: Option<usize> = Some(17);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = write_bytes(writer, &self.addr().octets())?;
+        Ok(written + self.prefix_len().pack_into(writer)?)
+    }
+}
+
+/// Packed as the IP version (`u8`: 4 or 6) followed by the network packed
+/// like an `Ipv4Net` or `Ipv6Net`
+impl Pack for IpNet {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        match self {
+            IpNet::V4(net) => Ok(4u8.pack_into(writer)? + net.pack_into(writer)?),
+            IpNet::V6(net) => Ok(6u8.pack_into(writer)? + net.pack_into(writer)?),
+        }
+    }
+}
+
+/// Fails with `Error::Custom` containing the `ipnet::PrefixLenError` if the
+/// prefix length exceeds 32
+impl Unpack for Ipv4Net {
+    const PACKED_SIZE: Option<usize> = Some(5);
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let octets = read_octets::<4>(reader)?;
+        let prefix = u8::unpack_from(reader)?;
+        Ipv4Net::new(octets.into(), prefix).map_err(|error| Error::Custom(Box::new(error)))
+    }
+}
+
+/// Fails with `Error::Custom` containing the `ipnet::PrefixLenError` if the
+/// prefix length exceeds 128
+impl Unpack for Ipv6Net {
+    const PACKED_SIZE: Option<usize> = Some(17);
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let octets = read_octets::<16>(reader)?;
+        let prefix = u8::unpack_from(reader)?;
+        Ipv6Net::new(octets.into(), prefix).map_err(|error| Error::Custom(Box::new(error)))
+    }
+}
+
+impl Unpack for IpNet {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        match u8::unpack_from(reader)? {
+            4 => Ipv4Net::unpack_from(reader).map(IpNet::V4),
+            6 => Ipv6Net::unpack_from(reader).map(IpNet::V6),
+            version => Err(Error::InvalidValue(format!(
+                "unknown IP version {}",
+                version
+            ))),
+        }
+    }
+}
+
+pack_fixed!(Ipv4Net, Ipv6Net);
+
+fn read_octets<const N: usize>(reader: &mut impl io::Read) -> Result<[u8; N]> {
+    let mut octets = [0; N];
+    reader.read_exact(&mut octets)?;
+    Ok(octets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::PackFixed;
+
+    #[test]
+    fn ipv4_round_trip() {
+        let value: Ipv4Net = "10.1.0.0/16".parse().unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [10, 1, 0, 0, 16]);
+        assert_eq!(Ipv4Net::unpack_exact(&bytes).unwrap(), value);
+        assert_eq!((Ipv4Net::SIZE, Ipv6Net::SIZE), (5, 17));
+    }
+
+    #[test]
+    fn ipnet_round_trip() {
+        let value: IpNet = "2001:db8::/32".parse().unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 18);
+        assert_eq!(bytes[0], 6);
+        assert_eq!(IpNet::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn unpack_invalid_prefix() {
+        match Ipv4Net::unpack_exact(&[10, 0, 0, 0, 33]) {
+            Err(Error::Custom(error)) => assert!(error.is::<::ipnet::PrefixLenError>()),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
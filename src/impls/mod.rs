@@ -0,0 +1,38 @@
+//! Implementations of `Pack` and `Unpack` for types of third-party crates
+//!
+//! Every crate is supported behind a feature of the same name
+
+#[cfg(feature = "arrayvec")]
+mod arrayvec;
+#[cfg(feature = "bytes")]
+mod bytes;
+#[cfg(feature = "chrono")]
+mod chrono;
+#[cfg(feature = "glam")]
+mod glam;
+#[cfg(feature = "half")]
+mod half;
+#[cfg(feature = "heapless")]
+mod heapless;
+#[cfg(feature = "indexmap")]
+mod indexmap;
+#[cfg(feature = "ipnet")]
+mod ipnet;
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
+#[cfg(feature = "num-bigint")]
+mod num_bigint;
+#[cfg(feature = "ordered-float")]
+mod ordered_float;
+#[cfg(feature = "rust_decimal")]
+mod rust_decimal;
+#[cfg(feature = "semver")]
+mod semver;
+#[cfg(feature = "smallvec")]
+mod smallvec;
+#[cfg(feature = "time")]
+mod time;
+#[cfg(feature = "url")]
+mod url;
+#[cfg(feature = "uuid")]
+mod uuid;
@@ -0,0 +1,127 @@
+use crate::pack::{self, Pack, PackFixed};
+use crate::unpack::{Result, Unpack};
+use ::nalgebra::{Quaternion, RealField, SMatrix, Scalar, UnitQuaternion, Vector4};
+use std::io;
+
+/// Packed as its components in column-major order without a length prefix,
+/// which covers `SVector` and the other statically sized aliases as well
+impl<T: Scalar + Pack, const R: usize, const C: usize> Pack for SMatrix<T, R, C> {
+    const PACKED_SIZE: Option<usize> = match T::PACKED_SIZE {
+        Some(size) => Some(size * R * C),
+        None => None,
+    };
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = 0;
+
+        for component in self.iter() {
+            written += component.pack_into(writer)?;
+        }
+
+        Ok(written)
+    }
+}
+
+/// Packed as its components in the order x (i), y (j), z (k), w like the
+/// quaternions of glam
+impl<T: Scalar + Pack> Pack for Quaternion<T> {
+    const PACKED_SIZE: Option<usize> = <SMatrix<T, 4, 1> as Pack>::PACKED_SIZE;
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.coords.pack_into(writer)
+    }
+}
+
+/// Packed like a `Quaternion`
+impl<T: Scalar + Pack> Pack for UnitQuaternion<T> {
+    const PACKED_SIZE: Option<usize> = <Quaternion<T> as Pack>::PACKED_SIZE;
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_ref().pack_into(writer)
+    }
+}
+
+impl<T: Scalar + Unpack, const R: usize, const C: usize> Unpack for SMatrix<T, R, C> {
+    const PACKED_SIZE: Option<usize> = match T::PACKED_SIZE {
+        Some(size) => Some(size * R * C),
+        None => None,
+    };
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut components = Vec::with_capacity(R * C);
+
+        for _i in 0..R * C {
+            components.push(T::unpack_from(reader)?);
+        }
+
+        Ok(SMatrix::from_iterator(components))
+    }
+}
+
+impl<T: Scalar + Unpack> Unpack for Quaternion<T> {
+    const PACKED_SIZE: Option<usize> = <SMatrix<T, 4, 1> as Unpack>::PACKED_SIZE;
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        Vector4::unpack_from(reader).map(Quaternion::from)
+    }
+}
+
+/// The unpacked quaternion is normalized to guard against rounding errors
+/// and tampered input
+impl<T: Scalar + Unpack + RealField> Unpack for UnitQuaternion<T> {
+    const PACKED_SIZE: Option<usize> = <Quaternion<T> as Unpack>::PACKED_SIZE;
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        Quaternion::unpack_from(reader).map(UnitQuaternion::new_normalize)
+    }
+}
+
+impl<T: Scalar + PackFixed, const R: usize, const C: usize> PackFixed for SMatrix<T, R, C> {
+    const SIZE: usize = T::SIZE * R * C;
+}
+
+impl<T: Scalar + PackFixed> PackFixed for Quaternion<T> {
+    const SIZE: usize = <SMatrix<T, 4, 1> as PackFixed>::SIZE;
+}
+
+impl<T: Scalar + PackFixed> PackFixed for UnitQuaternion<T> {
+    const SIZE: usize = <Quaternion<T> as PackFixed>::SIZE;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::nalgebra::{Matrix2, Vector3};
+
+    #[test]
+    fn vector_round_trip() {
+        let value = Vector3::new(1.0f32, 2.0, 3.0);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 12);
+        assert_eq!(f32::unpack_exact(&bytes[8..]).unwrap(), 3.0);
+        assert_eq!(Vector3::<f32>::unpack_exact(&bytes).unwrap(), value);
+        assert_eq!(Vector3::<f32>::SIZE, 12);
+    }
+
+    #[test]
+    fn matrix_is_column_major() {
+        let value = Matrix2::new(1u16, 2, 3, 4);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x01, 0x00, 0x03, 0x00, 0x02, 0x00, 0x04]);
+        assert_eq!(Matrix2::<u16>::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn quaternion_round_trip() {
+        let value = Quaternion::new(4.0f64, 1.0, 2.0, 3.0);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(f64::unpack_exact(&bytes[..8]).unwrap(), 1.0);
+        assert_eq!(f64::unpack_exact(&bytes[24..]).unwrap(), 4.0);
+        assert_eq!(Quaternion::<f64>::unpack_exact(&bytes).unwrap(), value);
+
+        let unit = UnitQuaternion::from_euler_angles(0.1f64, 0.2, 0.3);
+        let bytes = unit.pack_to_vec().unwrap();
+        let result = UnitQuaternion::<f64>::unpack_exact(&bytes).unwrap();
+        assert!((result.angle_to(&unit)).abs() < 1e-9);
+    }
+}
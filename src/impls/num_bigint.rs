@@ -0,0 +1,93 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use ::num_bigint::{BigInt, BigUint, Sign};
+use std::io;
+
+/// Packed as the big endian bytes of the value without leading zeros,
+/// prefixed with their length like a byte slice
+impl Pack for BigUint {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let bytes = if *self == BigUint::ZERO {
+            Vec::new()
+        } else {
+            self.to_bytes_be()
+        };
+        bytes.pack_into(writer)
+    }
+}
+
+/// Packed as the sign (`u8`: 0x00 for zero, 0x01 for positive and 0xFF for
+/// negative values) followed by the magnitude packed like a `BigUint`
+impl Pack for BigInt {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let sign: u8 = match self.sign() {
+            Sign::NoSign => 0x00,
+            Sign::Plus => 0x01,
+            Sign::Minus => 0xFF,
+        };
+        let written = sign.pack_into(writer)?;
+        Ok(written + self.magnitude().pack_into(writer)?)
+    }
+}
+
+impl Unpack for BigUint {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        Ok(BigUint::from_bytes_be(&read_bytes(reader, len)?))
+    }
+}
+
+impl Unpack for BigInt {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let sign = match u8::unpack_from(reader)? {
+            0x00 => Sign::NoSign,
+            0x01 => Sign::Plus,
+            0xFF => Sign::Minus,
+            sign => return Err(Error::InvalidValue(format!("invalid sign {:#04x}", sign))),
+        };
+        let magnitude = BigUint::unpack_from(reader)?;
+
+        if (sign == Sign::NoSign) != (magnitude == BigUint::ZERO) {
+            return Err(Error::InvalidValue(
+                "sign does not match the magnitude".into(),
+            ));
+        }
+
+        Ok(BigInt::from_biguint(sign, magnitude))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn biguint_round_trip() {
+        let value: BigUint = BigUint::from(1u32) << 130usize;
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes[..5], [0x00, 0x00, 0x00, 0x11, 0x04]);
+        assert_eq!(BigUint::unpack_exact(&bytes).unwrap(), value);
+
+        let bytes = BigUint::ZERO.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(BigUint::unpack_exact(&bytes).unwrap(), BigUint::ZERO);
+    }
+
+    #[test]
+    fn bigint_round_trip() {
+        let value = BigInt::from(-258);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0xFF, 0x00, 0x00, 0x00, 0x02, 0x01, 0x02]);
+        assert_eq!(BigInt::unpack_exact(&bytes).unwrap(), value);
+
+        let bytes = BigInt::ZERO.pack_to_vec().unwrap();
+        assert_eq!(BigInt::unpack_exact(&bytes).unwrap(), BigInt::ZERO);
+    }
+
+    #[test]
+    fn unpack_inconsistent_sign() {
+        let bytes = [0x00, 0x00, 0x00, 0x00, 0x01, 0x01];
+        let result = BigInt::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::pack::{self, Pack, PackFixed};
+use crate::unpack::{Error, Result, Unpack};
+use ::ordered_float::{FloatCore, NotNan, OrderedFloat};
+use std::io;
+
+impl<T: Pack> Pack for OrderedFloat<T> {
+    const PACKED_SIZE: Option<usize> = T::PACKED_SIZE;
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.0.pack_into(writer)
+    }
+}
+
+impl<T: Pack + FloatCore> Pack for NotNan<T> {
+    const PACKED_SIZE: Option<usize> = T::PACKED_SIZE;
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.into_inner().pack_into(writer)
+    }
+}
+
+impl<T: Unpack> Unpack for OrderedFloat<T> {
+    const PACKED_SIZE: Option<usize> = T::PACKED_SIZE;
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        T::unpack_from(reader).map(OrderedFloat)
+    }
+}
+
+/// Fails with `Error::Custom` containing the `ordered_float::FloatIsNan`
+/// error if the packed value is NaN
+impl<T: Unpack + FloatCore> Unpack for NotNan<T> {
+    const PACKED_SIZE: Option<usize> = T::PACKED_SIZE;
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        NotNan::new(T::unpack_from(reader)?).map_err(|error| Error::Custom(Box::new(error)))
+    }
+}
+
+impl<T: PackFixed> PackFixed for OrderedFloat<T> {
+    const SIZE: usize = T::SIZE;
+}
+
+impl<T: PackFixed + FloatCore> PackFixed for NotNan<T> {
+    const SIZE: usize = T::SIZE;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_float_round_trip() {
+        let value = OrderedFloat(1.5f32);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, 1.5f32.to_be_bytes());
+        assert_eq!(OrderedFloat::<f32>::unpack_exact(&bytes).unwrap(), value);
+        assert_eq!(OrderedFloat::<f32>::SIZE, 4);
+    }
+
+    #[test]
+    fn not_nan_round_trip() {
+        let value = NotNan::new(-2.25f64).unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(NotNan::<f64>::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn unpack_nan_as_not_nan() {
+        let bytes = f64::NAN.to_be_bytes();
+        match NotNan::<f64>::unpack_exact(&bytes) {
+            Err(Error::Custom(error)) => assert!(error.is::<::ordered_float::FloatIsNan>()),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
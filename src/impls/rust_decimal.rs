@@ -0,0 +1,60 @@
+use crate::pack::{self, pack_fixed, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use ::rust_decimal::Decimal;
+use std::io;
+
+/// Packed as the scale (`u8`) followed by the mantissa (`i128`), so the
+/// value is `mantissa / 10^scale` without any rounding
+impl Pack for Decimal {
+    const PACKED_SIZE: Option<usize> = Some(17);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = (self.scale() as u8).pack_into(writer)?;
+        Ok(written + self.mantissa().pack_into(writer)?)
+    }
+}
+
+impl Unpack for Decimal {
+    const PACKED_SIZE: Option<usize> = Some(17);
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let scale = u8::unpack_from(reader)?;
+        let mantissa = i128::unpack_from(reader)?;
+        Decimal::try_from_i128_with_scale(mantissa, scale.into())
+            .map_err(|error| Error::InvalidValue(error.to_string()))
+    }
+}
+
+pack_fixed!(Decimal);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::PackFixed;
+
+    #[test]
+    fn decimal_round_trip() {
+        let value = Decimal::new(-12345, 2);
+        let bytes = value.pack_to_vec().unwrap();
+        let mut expected = vec![0x02];
+        expected.extend_from_slice(&(-12345i128).to_be_bytes());
+        assert_eq!(bytes, expected);
+        assert_eq!(Decimal::unpack_exact(&bytes).unwrap(), value);
+        assert_eq!(Decimal::SIZE, bytes.len());
+    }
+
+    #[test]
+    fn decimal_keeps_scale() {
+        let value = Decimal::new(1000, 3);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(Decimal::unpack_exact(&bytes).unwrap().to_string(), "1.000");
+    }
+
+    #[test]
+    fn unpack_invalid_scale() {
+        let mut bytes = vec![29];
+        1i128.pack_into(&mut bytes).unwrap();
+        let result = Decimal::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use ::semver::{Version, VersionReq};
+use std::io;
+
+/// Packed as its string representation like a `str`
+impl Pack for Version {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.to_string().pack_into(writer)
+    }
+}
+
+/// Packed as its string representation like a `str`
+impl Pack for VersionReq {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.to_string().pack_into(writer)
+    }
+}
+
+/// Fails with `Error::Custom` containing the `semver::Error` if the packed
+/// string is not a valid version
+impl Unpack for Version {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let value = String::unpack_from(reader)?;
+        Version::parse(&value).map_err(|error| Error::Custom(Box::new(error)))
+    }
+}
+
+/// Fails with `Error::Custom` containing the `semver::Error` if the packed
+/// string is not a valid version requirement
+impl Unpack for VersionReq {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let value = String::unpack_from(reader)?;
+        VersionReq::parse(&value).map_err(|error| Error::Custom(Box::new(error)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_round_trip() {
+        let value = Version::parse("1.2.3-beta.1+build").unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, "1.2.3-beta.1+build".pack_to_vec().unwrap());
+        assert_eq!(Version::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn version_req_round_trip() {
+        let value = VersionReq::parse(">=1.2, <2").unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(VersionReq::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn unpack_invalid_version() {
+        let bytes = "1.x".pack_to_vec().unwrap();
+        match Version::unpack_exact(&bytes) {
+            Err(Error::Custom(error)) => assert!(error.is::<::semver::Error>()),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
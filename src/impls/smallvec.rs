@@ -0,0 +1,45 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Result, Unpack};
+use ::smallvec::{Array, SmallVec};
+use std::io;
+
+impl<A: Array> Pack for SmallVec<A>
+where
+    A::Item: Pack,
+{
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_slice().pack_into(writer)
+    }
+}
+
+impl<A: Array> Unpack for SmallVec<A>
+where
+    A::Item: Unpack,
+{
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        let mut result = SmallVec::new();
+
+        for _i in 0..len {
+            result.push(A::Item::unpack_from(reader)?);
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smallvec_round_trip() {
+        let value: SmallVec<[u16; 2]> = SmallVec::from_slice(&[1, 2, 3]);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(
+            bytes,
+            [0x00, 0x00, 0x00, 0x03, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03]
+        );
+        assert_eq!(SmallVec::<[u16; 2]>::unpack_exact(&bytes).unwrap(), value);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use ::time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use std::io;
+
+/// Julian day number of the Unix epoch
+const UNIX_EPOCH_JULIAN_DAY: i32 = 2_440_588;
+
+fn invalid(error: impl std::fmt::Display) -> Error {
+    Error::InvalidValue(error.to_string())
+}
+
+fn from_timestamp(seconds: i64, nanoseconds: u32) -> Result<OffsetDateTime> {
+    OffsetDateTime::from_unix_timestamp(seconds)
+        .and_then(|value| value.replace_nanosecond(nanoseconds))
+        .map_err(invalid)
+}
+
+/// Packed as the seconds since the Unix epoch (`i64`), the nanoseconds
+/// within the second (`u32`) and the offset from UTC in seconds (`i32`)
+impl Pack for OffsetDateTime {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = self.unix_timestamp().pack_into(writer)?;
+        written += self.nanosecond().pack_into(writer)?;
+        Ok(written + self.offset().whole_seconds().pack_into(writer)?)
+    }
+}
+
+/// Packed as the seconds since the Unix epoch (`i64`) followed by the
+/// nanoseconds within the second (`u32`), as if the date and time were in
+/// UTC
+impl Pack for PrimitiveDateTime {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let value = self.assume_utc();
+        let written = value.unix_timestamp().pack_into(writer)?;
+        Ok(written + value.nanosecond().pack_into(writer)?)
+    }
+}
+
+/// Packed as the days since the Unix epoch (`i32`)
+impl Pack for Date {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        (self.to_julian_day() - UNIX_EPOCH_JULIAN_DAY).pack_into(writer)
+    }
+}
+
+/// Packed as the seconds since midnight (`u32`) followed by the nanoseconds
+/// within the second (`u32`)
+impl Pack for Time {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let (hour, minute, second, nanosecond) = self.as_hms_nano();
+        let seconds = u32::from(hour) * 3600 + u32::from(minute) * 60 + u32::from(second);
+        let written = seconds.pack_into(writer)?;
+        Ok(written + nanosecond.pack_into(writer)?)
+    }
+}
+
+/// Packed as the whole seconds (`i64`) followed by the remaining
+/// nanoseconds (`i32`) with the same sign
+impl Pack for Duration {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = self.whole_seconds().pack_into(writer)?;
+        Ok(written + self.subsec_nanoseconds().pack_into(writer)?)
+    }
+}
+
+impl Unpack for OffsetDateTime {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let seconds = i64::unpack_from(reader)?;
+        let nanoseconds = u32::unpack_from(reader)?;
+        let offset = UtcOffset::from_whole_seconds(i32::unpack_from(reader)?).map_err(invalid)?;
+        from_timestamp(seconds, nanoseconds)?
+            .checked_to_offset(offset)
+            .ok_or_else(|| Error::InvalidValue("date is out of range for the offset".into()))
+    }
+}
+
+impl Unpack for PrimitiveDateTime {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let seconds = i64::unpack_from(reader)?;
+        let nanoseconds = u32::unpack_from(reader)?;
+        let value = from_timestamp(seconds, nanoseconds)?;
+        Ok(PrimitiveDateTime::new(value.date(), value.time()))
+    }
+}
+
+impl Unpack for Date {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let days = i32::unpack_from(reader)?;
+        let julian_day = days
+            .checked_add(UNIX_EPOCH_JULIAN_DAY)
+            .ok_or_else(|| Error::InvalidValue(format!("{} days are out of range", days)))?;
+        Date::from_julian_day(julian_day).map_err(invalid)
+    }
+}
+
+impl Unpack for Time {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let seconds = u32::unpack_from(reader)?;
+        let nanoseconds = u32::unpack_from(reader)?;
+
+        if seconds >= 86_400 {
+            return Err(Error::InvalidValue(format!(
+                "{} seconds exceed a day",
+                seconds
+            )));
+        }
+
+        let hour = (seconds / 3600) as u8;
+        let minute = (seconds / 60 % 60) as u8;
+        let second = (seconds % 60) as u8;
+        Time::from_hms_nano(hour, minute, second, nanoseconds).map_err(invalid)
+    }
+}
+
+impl Unpack for Duration {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let seconds = i64::unpack_from(reader)?;
+        let nanoseconds = i32::unpack_from(reader)?;
+
+        if nanoseconds.unsigned_abs() >= 1_000_000_000
+            || (seconds > 0 && nanoseconds < 0)
+            || (seconds < 0 && nanoseconds > 0)
+        {
+            return Err(Error::InvalidValue(format!(
+                "duration {}s {}ns is not normalized",
+                seconds, nanoseconds
+            )));
+        }
+
+        Ok(Duration::new(seconds, nanoseconds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::time::macros::{date, datetime, time};
+
+    #[test]
+    fn offset_date_time_round_trip() {
+        let value = datetime!(2024-02-29 12:30:00.25 +02:00);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 16);
+        let result = OffsetDateTime::unpack_exact(&bytes).unwrap();
+        assert_eq!(result, value);
+        assert_eq!(result.offset(), value.offset());
+    }
+
+    #[test]
+    fn primitive_round_trip() {
+        let value = datetime!(1970-01-01 00:00:01.5);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(
+            bytes,
+            [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x1D, 0xCD, 0x65, 0x00]
+        );
+        assert_eq!(PrimitiveDateTime::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn date_and_time_round_trip() {
+        let value = date!(1969 - 12 - 31);
+        assert_eq!(value.pack_to_vec().unwrap(), (-1i32).to_be_bytes());
+        assert_eq!(Date::unpack_exact(&(-1i32).to_be_bytes()).unwrap(), value);
+
+        let value = time!(23:59:59.000000001);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(Time::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn duration_round_trip() {
+        let value = Duration::new(-3, -500);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(Duration::unpack_exact(&bytes).unwrap(), value);
+
+        let mut bytes = 1i64.pack_to_vec().unwrap();
+        (-1i32).pack_into(&mut bytes).unwrap();
+        assert!(matches!(
+            Duration::unpack_exact(&bytes),
+            Err(Error::InvalidValue(_))
+        ));
+    }
+}
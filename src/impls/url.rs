@@ -0,0 +1,42 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use ::url::Url;
+use std::io;
+
+/// Packed as its serialization like a `str`
+impl Pack for Url {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.as_str().pack_into(writer)
+    }
+}
+
+/// Fails with `Error::Custom` containing the `url::ParseError` if the
+/// packed string is not a valid URL
+impl Unpack for Url {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let value = String::unpack_from(reader)?;
+        Url::parse(&value).map_err(|error| Error::Custom(Box::new(error)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_round_trip() {
+        let value = Url::parse("https://example.com/a?b=c").unwrap();
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(bytes, "https://example.com/a?b=c".pack_to_vec().unwrap());
+        assert_eq!(Url::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn unpack_invalid_url() {
+        let bytes = "not a url".pack_to_vec().unwrap();
+        match Url::unpack_exact(&bytes) {
+            Err(Error::Custom(error)) => assert!(error.is::<::url::ParseError>()),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::pack::{self, pack_fixed, Pack};
+use crate::unpack::{Result, Unpack};
+use std::io;
+
+impl Pack for ::uuid::Uuid {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        writer.write_all(self.as_bytes())?;
+        Ok(16)
+    }
+}
+
+impl Unpack for ::uuid::Uuid {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut bytes = [0; 16];
+        reader.read_exact(&mut bytes)?;
+        Ok(::uuid::Uuid::from_bytes(bytes))
+    }
+}
+
+pack_fixed!(::uuid::Uuid);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::PackFixed;
+
+    #[test]
+    fn uuid_round_trip() {
+        let value = ::uuid::Uuid::from_u128(0x0011_2233_4455_6677_8899_AABB_CCDD_EEFF);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(
+            bytes,
+            0x0011_2233_4455_6677_8899_AABB_CCDD_EEFFu128.to_be_bytes()
+        );
+        assert_eq!(::uuid::Uuid::unpack_exact(&bytes).unwrap(), value);
+        assert_eq!(::uuid::Uuid::SIZE, bytes.len());
+    }
+
+    #[test]
+    fn pack_uuid_slice() {
+        let values = [::uuid::Uuid::nil(), ::uuid::Uuid::max()];
+        let bytes = values.pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 4 + 32);
+        assert_eq!(<[::uuid::Uuid; 2]>::unpack_exact(&bytes).unwrap(), values);
+    }
+}
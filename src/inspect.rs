@@ -0,0 +1,289 @@
+//! Tools for explaining how a packed byte stream is laid out
+//!
+//! # Example
+//!
+//! ```
+//! use serial_container::inspect::inspect;
+//! use serial_container::schema::Schema;
+//!
+//! let schema = Schema::structure("Point")
+//!     .field("x", Schema::U16)
+//!     .field("y", Schema::U16);
+//! let report = inspect(&schema, &[0x00, 0x01, 0x00]);
+//!
+//! assert_eq!(report.fields.len(), 1);
+//! assert_eq!(report.failure.unwrap().path, "y");
+//! ```
+
+use crate::pack::{self, Pack};
+use crate::schema::{Describe, Schema};
+use crate::unpack::{initial_capacity, Error};
+use crate::value::{check_elements, Value};
+use std::fmt;
+
+/// Single decoded field of an inspected byte stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// Path of the field, e.g. `points[2].x`, or `.len` for length prefixes
+    pub path: String,
+    /// Offset of the first byte of the field in the stream
+    pub offset: usize,
+    /// Raw bytes of the field
+    pub bytes: Vec<u8>,
+    /// Decoded value of the field
+    pub value: Value,
+}
+
+/// Position at which decoding of an inspected byte stream failed
+#[derive(Debug)]
+pub struct Failure {
+    /// Path of the field that could not be decoded
+    pub path: String,
+    /// Offset at which the field starts
+    pub offset: usize,
+    /// Cause of the failure
+    pub error: Error,
+}
+
+/// Structured report of an inspected byte stream
+///
+/// Lists every decoded field in stream order, up to the first field that
+/// could not be decoded
+#[derive(Debug)]
+pub struct Report {
+    /// Successfully decoded fields
+    pub fields: Vec<Field>,
+    /// Number of bytes consumed by the decoded fields
+    pub consumed: usize,
+    /// Failure that stopped the inspection, if any
+    pub failure: Option<Failure>,
+    /// Number of bytes left after the end of a successfully decoded value
+    pub trailing: usize,
+}
+
+impl Report {
+    /// Returns true if the whole stream was decoded without failure or
+    /// trailing bytes
+    pub fn is_ok(&self) -> bool {
+        self.failure.is_none() && self.trailing == 0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in &self.fields {
+            write!(f, "{:08x}  ", field.offset)?;
+
+            for byte in field.bytes.iter().take(16) {
+                write!(f, "{:02x} ", byte)?;
+            }
+
+            if field.bytes.len() > 16 {
+                write!(f, "... ")?;
+            }
+
+            writeln!(f, " {} = {:?}", display_path(&field.path), field.value)?;
+        }
+
+        if let Some(failure) = &self.failure {
+            writeln!(
+                f,
+                "{:08x}  failed to decode {}: {}",
+                failure.offset,
+                display_path(&failure.path),
+                failure.error
+            )?;
+        } else if self.trailing > 0 {
+            writeln!(f, "{:08x}  {} trailing bytes", self.consumed, self.trailing)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Walks the given bytes according to the schema and reports the offset,
+/// raw bytes and decoded value of every field
+pub fn inspect(schema: &Schema, bytes: &[u8]) -> Report {
+    let mut inspector = Inspector {
+        bytes,
+        offset: 0,
+        fields: Vec::new(),
+    };
+    let failure = inspector.walk(schema, String::new()).err();
+    Report {
+        fields: inspector.fields,
+        consumed: inspector.offset,
+        trailing: if failure.is_none() {
+            bytes.len() - inspector.offset
+        } else {
+            0
+        },
+        failure,
+    }
+}
+
+/// Inspects the given bytes according to the schema of the given type
+pub fn inspect_as<T: Describe>(bytes: &[u8]) -> Report {
+    inspect(&T::schema(), bytes)
+}
+
+/// Packs the given value and renders an annotated hex view of the bytes
+///
+/// Every line shows the offset, raw bytes, path and decoded value of a
+/// field, as located by the schema of the value. This helps to find the
+/// first field at which another implementation disagrees about the wire
+/// format
+pub fn dump<T: Pack + Describe + ?Sized>(value: &T) -> pack::Result<String> {
+    let bytes = value.pack_to_vec()?;
+    Ok(inspect(&T::schema(), &bytes).to_string())
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "<root>"
+    } else {
+        path
+    }
+}
+
+struct Inspector<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    fields: Vec<Field>,
+}
+
+impl Inspector<'_> {
+    fn walk(&mut self, schema: &Schema, path: String) -> Result<(), Failure> {
+        match schema {
+            Schema::Sequence(element) if **element != Schema::U8 => {
+                let len = self.length(&path)?;
+                self.check_elements(&path, len, element.is_zero_sized())?;
+                self.fields.reserve(initial_capacity::<Field>(len));
+
+                for index in 0..len {
+                    self.walk(element, format!("{}[{}]", path, index))?;
+                }
+            }
+            Schema::Map(key, value) => {
+                let len = self.length(&path)?;
+                self.check_elements(&path, len, key.is_zero_sized() && value.is_zero_sized())?;
+                self.fields.reserve(initial_capacity::<Field>(len));
+
+                for index in 0..len {
+                    self.walk(key, format!("{}[{}].key", path, index))?;
+                    self.walk(value, format!("{}[{}].value", path, index))?;
+                }
+            }
+            Schema::Struct { fields, .. } => {
+                for (name, schema) in fields {
+                    let path = if path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{}.{}", path, name)
+                    };
+                    self.walk(schema, path)?;
+                }
+            }
+            _ => {
+                self.leaf(schema, path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn length(&mut self, path: &str) -> Result<usize, Failure> {
+        match self.leaf(&Schema::U32, format!("{}.len", path))? {
+            Value::Int(len) => Ok(len as usize),
+            _ => unreachable!("u32 always decodes into an integer"),
+        }
+    }
+
+    fn check_elements(&self, path: &str, len: usize, zero_sized: bool) -> Result<(), Failure> {
+        check_elements(len, zero_sized).map_err(|error| Failure {
+            path: path.to_string(),
+            offset: self.offset,
+            error,
+        })
+    }
+
+    fn leaf(&mut self, schema: &Schema, path: String) -> Result<Value, Failure> {
+        let mut remaining = &self.bytes[self.offset..];
+
+        match Value::unpack_with_schema(schema, &mut remaining) {
+            Ok(value) => {
+                let len = self.bytes.len() - self.offset - remaining.len();
+                self.fields.push(Field {
+                    path,
+                    offset: self.offset,
+                    bytes: self.bytes[self.offset..self.offset + len].to_vec(),
+                    value: value.clone(),
+                });
+                self.offset += len;
+                Ok(value)
+            }
+            Err(error) => Err(Failure {
+                path,
+                offset: self.offset,
+                error,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inspect_zero_sized_elements() {
+        let schema = Schema::sequence(Schema::structure("Empty"));
+        let report = inspect(&schema, &u32::MAX.to_be_bytes());
+        let failure = report.failure.unwrap();
+        assert_eq!(failure.path, "");
+        assert!(matches!(failure.error, Error::InvalidValue(_)));
+    }
+
+    #[test]
+    fn inspect_nested() {
+        let schema = Schema::structure("Polygon")
+            .field("name", Schema::String)
+            .field("xs", Schema::sequence(Schema::I16));
+        let bytes = [
+            0x00, 0x00, 0x00, 0x01, 0x61, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0xFF, 0xFF,
+        ];
+        let report = inspect(&schema, &bytes);
+        assert!(report.is_ok());
+
+        let paths: Vec<_> = report.fields.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["name", "xs.len", "xs[0]", "xs[1]"]);
+        assert_eq!(report.fields[3].offset, 11);
+        assert_eq!(report.fields[3].bytes, [0xFF, 0xFF]);
+        assert_eq!(report.fields[3].value, Value::Int(-1));
+    }
+
+    #[test]
+    fn inspect_trailing_bytes() {
+        let report = inspect_as::<u8>(&[0x01, 0x02]);
+        assert!(!report.is_ok());
+        assert_eq!(report.trailing, 1);
+        assert!(report.to_string().contains("1 trailing bytes"));
+    }
+
+    #[test]
+    fn inspect_failure() {
+        let report = inspect_as::<Vec<u16>>(&[0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00]);
+        let failure = report.failure.as_ref().unwrap();
+        assert_eq!(failure.path, "[1]");
+        assert_eq!(failure.offset, 6);
+        assert!(report.to_string().contains("failed to decode [1]"));
+    }
+
+    #[test]
+    fn dump_value() {
+        let text = dump(&[0x0102u16]).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], "00000000  00 00 00 01  .len = Int(1)");
+        assert_eq!(lines[1], "00000004  01 02  [0] = Int(258)");
+    }
+}
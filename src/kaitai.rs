@@ -0,0 +1,275 @@
+//! Export of schemas as [Kaitai Struct](https://kaitai.io) descriptions
+//!
+//! The generated `.ksy` files allow parsing packed data with the Kaitai
+//! tooling, e.g. from Python, Java or the Kaitai Web IDE
+//!
+//! # Example
+//!
+//! ```
+//! use serial_container::kaitai::to_ksy;
+//! use serial_container::schema::Schema;
+//!
+//! let schema = Schema::structure("Point")
+//!     .field("x", Schema::U16)
+//!     .field("y", Schema::U16);
+//! let ksy = to_ksy(&schema, "point");
+//!
+//! assert!(ksy.contains("  - id: x\n    type: u2\n"));
+//! ```
+
+use crate::schema::Schema;
+use std::fmt::Write;
+
+/// Returns a Kaitai Struct description of the given schema
+///
+/// The fields of a struct become the top-level sequence of the description,
+/// any other schema is described as a single field named `value`. Types
+/// with a custom wire format cannot be described and consume the remaining
+/// bytes of the stream instead
+pub fn to_ksy(schema: &Schema, id: &str) -> String {
+    let mut generator = Generator::default();
+    let seq = generator.type_body(schema);
+    let mut output = String::new();
+
+    output.push_str("meta:\n");
+    let _ = writeln!(output, "  id: {}", id);
+    output.push_str("  endian: be\n");
+    output.push_str(&seq);
+
+    if !generator.types.is_empty() {
+        output.push_str("types:\n");
+
+        for (name, body) in &generator.types {
+            let _ = writeln!(output, "  {}:", name);
+
+            for line in body.lines() {
+                let _ = writeln!(output, "    {}", line);
+            }
+        }
+    }
+
+    output
+}
+
+#[derive(Default)]
+struct Generator {
+    types: Vec<(String, String)>,
+    generated: usize,
+}
+
+impl Generator {
+    fn type_body(&mut self, schema: &Schema) -> String {
+        let mut body = String::from("seq:\n");
+
+        match schema {
+            Schema::Struct { fields, .. } => {
+                for (name, schema) in fields {
+                    self.field(&mut body, name, schema);
+                }
+            }
+            schema => self.field(&mut body, "value", schema),
+        }
+
+        body
+    }
+
+    fn field(&mut self, output: &mut String, id: &str, schema: &Schema) {
+        match schema {
+            Schema::String => {
+                length(output, id);
+                let _ = writeln!(output, "  - id: {}", id);
+                output.push_str("    type: str\n");
+                let _ = writeln!(output, "    size: {}_len", id);
+                output.push_str("    encoding: UTF-8\n");
+            }
+            Schema::Sequence(element) if **element == Schema::U8 => {
+                length(output, id);
+                let _ = writeln!(output, "  - id: {}", id);
+                let _ = writeln!(output, "    size: {}_len", id);
+            }
+            Schema::Sequence(element) => {
+                length(output, id);
+                let _ = writeln!(output, "  - id: {}", id);
+                self.element(output, element);
+                output.push_str("    repeat: expr\n");
+                let _ = writeln!(output, "    repeat-expr: {}_len", id);
+            }
+            Schema::Map(key, value) => {
+                length(output, id);
+                let _ = writeln!(output, "  - id: {}", id);
+                let name = self.entry_type(key, value);
+                let _ = writeln!(output, "    type: {}", name);
+                output.push_str("    repeat: expr\n");
+                let _ = writeln!(output, "    repeat-expr: {}_len", id);
+            }
+            Schema::Custom(name) => {
+                let _ = writeln!(output, "  - id: {}", id);
+                output.push_str("    size-eos: true\n");
+                let _ = writeln!(output, "    doc: custom type {} with unknown layout", name);
+            }
+            schema => {
+                let _ = writeln!(output, "  - id: {}", id);
+                self.element(output, schema);
+            }
+        }
+    }
+
+    fn element(&mut self, output: &mut String, schema: &Schema) {
+        match primitive(schema) {
+            Some(kind) => output.push_str(kind),
+            None => {
+                let name = self.named_type(schema);
+                let _ = writeln!(output, "    type: {}", name);
+            }
+        }
+    }
+
+    fn named_type(&mut self, schema: &Schema) -> String {
+        let name = match schema {
+            Schema::Struct { name, .. } => snake_case(name),
+            _ => {
+                self.generated += 1;
+                format!("value_{}", self.generated)
+            }
+        };
+
+        if !self.types.iter().any(|(existing, _)| *existing == name) {
+            // reserve the name before recursing to terminate on repeated structs
+            self.types.push((name.clone(), String::new()));
+            let body = self.type_body(schema);
+            let index = self
+                .types
+                .iter()
+                .position(|(existing, _)| *existing == name);
+            self.types[index.expect("type was reserved")].1 = body;
+        }
+
+        name
+    }
+
+    fn entry_type(&mut self, key: &Schema, value: &Schema) -> String {
+        self.generated += 1;
+        let name = format!("entry_{}", self.generated);
+        self.types.push((name.clone(), String::new()));
+
+        let mut body = String::from("seq:\n");
+        self.field(&mut body, "key", key);
+        self.field(&mut body, "value", value);
+
+        let index = self
+            .types
+            .iter()
+            .position(|(existing, _)| *existing == name);
+        self.types[index.expect("type was reserved")].1 = body;
+        name
+    }
+}
+
+fn length(output: &mut String, id: &str) {
+    let _ = writeln!(output, "  - id: {}_len", id);
+    output.push_str("    type: u4\n");
+}
+
+fn primitive(schema: &Schema) -> Option<&'static str> {
+    let kind = match schema {
+        Schema::Bool => "    type: u1\n    doc: 0x00 is true, 0xFF is false\n",
+        Schema::U8 => "    type: u1\n",
+        Schema::U16 => "    type: u2\n",
+        Schema::U32 => "    type: u4\n",
+        Schema::U64 => "    type: u8\n",
+        Schema::U128 => "    size: 16\n    doc: unsigned 128 bit integer\n",
+        Schema::I16 => "    type: s2\n",
+        Schema::I32 => "    type: s4\n",
+        Schema::I64 => "    type: s8\n",
+        Schema::I128 => "    size: 16\n    doc: signed 128 bit integer\n",
+        Schema::F32 => "    type: f4\n",
+        Schema::F64 => "    type: f8\n",
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn snake_case(name: &str) -> String {
+    let mut output = String::new();
+
+    for (index, char) in name.chars().enumerate() {
+        if char.is_uppercase() {
+            if index > 0 {
+                output.push('_');
+            }
+
+            output.extend(char.to_lowercase());
+        } else if char.is_alphanumeric() {
+            output.push(char);
+        } else {
+            output.push('_');
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_primitive() {
+        let ksy = to_ksy(&Schema::I32, "number");
+        assert_eq!(
+            ksy,
+            "meta:\n  id: number\n  endian: be\nseq:\n  - id: value\n    type: s4\n"
+        );
+    }
+
+    #[test]
+    fn export_nested_struct() {
+        let point = Schema::structure("Point")
+            .field("x", Schema::F32)
+            .field("y", Schema::F32);
+        let schema = Schema::structure("Polygon")
+            .field("name", Schema::String)
+            .field("points", Schema::sequence(point));
+        let ksy = to_ksy(&schema, "polygon");
+
+        let expected = "\
+meta:
+  id: polygon
+  endian: be
+seq:
+  - id: name_len
+    type: u4
+  - id: name
+    type: str
+    size: name_len
+    encoding: UTF-8
+  - id: points_len
+    type: u4
+  - id: points
+    type: point
+    repeat: expr
+    repeat-expr: points_len
+types:
+  point:
+    seq:
+      - id: x
+        type: f4
+      - id: y
+        type: f4
+";
+        assert_eq!(ksy, expected);
+    }
+
+    #[test]
+    fn export_map() {
+        let ksy = to_ksy(&Schema::map(Schema::U8, Schema::String), "lookup");
+        assert!(ksy.contains("    type: entry_1\n"));
+        assert!(ksy.contains("  entry_1:\n    seq:\n      - id: key\n        type: u1\n"));
+    }
+
+    #[test]
+    fn snake_case_names() {
+        assert_eq!(snake_case("HttpRequest"), "http_request");
+        assert_eq!(snake_case("a-b"), "a_b");
+    }
+}
@@ -1,2 +1,69 @@
+pub mod adapters;
+pub mod archive;
+#[cfg(feature = "futures-io")]
+pub mod async_futures;
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+mod async_impl;
+#[cfg(feature = "tokio")]
+pub mod async_tokio;
+pub mod borrowed;
+pub mod cbor;
+pub mod checksum;
+pub mod chunk;
+#[cfg(feature = "tokio-util")]
+pub mod codec;
+pub mod columnar;
+#[cfg(any(feature = "flate2", feature = "lz4_flex", feature = "zstd"))]
+pub mod compress;
+pub mod contextual;
+pub mod datagram;
+pub mod decoder;
+pub mod delta;
+pub mod deterministic;
+pub mod diff;
+#[cfg(feature = "embedded-io")]
+pub mod embedded;
+#[cfg(feature = "chacha20poly1305")]
+pub mod encrypt;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod golden;
+pub mod handshake;
+mod impls;
+pub mod inspect;
+pub mod kaitai;
+pub mod metered;
+#[cfg(feature = "memmap2")]
+pub mod mmap;
+pub mod msgpack;
+pub mod open_enum;
 pub mod pack;
+pub mod packed_index;
+pub mod padding;
+#[cfg(feature = "bytemuck")]
+pub mod pod;
+pub mod pool;
+pub mod project;
+pub mod protobuf;
+pub mod pubsub;
+pub mod raw;
+pub mod record_log;
+pub mod rpc;
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde_format;
+pub mod shared;
+#[cfg(feature = "ed25519-dalek")]
+pub mod sign;
+pub mod skip;
+pub mod stream;
+pub mod stringly;
+pub mod text;
+pub mod tlv;
+mod trace;
+pub mod transport;
 pub mod unpack;
+pub mod value;
+pub mod version;
+#[cfg(feature = "tungstenite")]
+pub mod websocket;
@@ -0,0 +1,241 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{Result, Unpack};
+use std::io;
+
+/// Counters collected by [`MeteredWriter`] and [`MeteredReader`]
+///
+/// `bytes` counts every byte passing through the adapter, while `values`
+/// and `errors` only count values packed with `put` or unpacked with `take`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub bytes: u64,
+    pub values: u64,
+    pub errors: u64,
+}
+
+/// Writer counting the bytes and values packed into it
+///
+/// With the `metrics` feature enabled, values packed with
+/// [`MeteredWriter::put`] are additionally reported to the global recorder
+/// of the `metrics` crate as the counters `serial_container_packed_bytes`,
+/// `serial_container_packed_values` and `serial_container_pack_errors`,
+/// labeled with the type name of the value
+pub struct MeteredWriter<W: io::Write> {
+    inner: W,
+    counters: Counters,
+}
+
+impl<W: io::Write> MeteredWriter<W> {
+    /// Creates a writer with all counters set to zero
+    pub fn new(writer: W) -> Self {
+        Self {
+            inner: writer,
+            counters: Counters::default(),
+        }
+    }
+
+    /// Packs a value into the bytestream and counts it
+    pub fn put<T: Pack + ?Sized>(&mut self, value: &T) -> pack::Result<usize> {
+        let result = value.pack_into(self);
+
+        match &result {
+            Ok(written) => {
+                self.counters.values += 1;
+                report::packed::<T>(*written);
+            }
+            Err(_) => {
+                self.counters.errors += 1;
+                report::pack_failed::<T>();
+            }
+        }
+
+        result
+    }
+
+    /// Returns the counters collected so far
+    pub fn counters(&self) -> Counters {
+        self.counters
+    }
+
+    /// Returns the counters collected so far and sets them to zero
+    pub fn reset(&mut self) -> Counters {
+        std::mem::take(&mut self.counters)
+    }
+
+    /// Returns a reference to the underlying bytestream
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the underlying bytestream
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> io::Write for MeteredWriter<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buffer)?;
+        self.counters.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader counting the bytes and values unpacked from it
+///
+/// With the `metrics` feature enabled, values unpacked with
+/// [`MeteredReader::take`] are additionally reported to the global recorder
+/// of the `metrics` crate as the counters `serial_container_unpacked_bytes`,
+/// `serial_container_unpacked_values` and `serial_container_unpack_errors`,
+/// labeled with the type name of the value
+pub struct MeteredReader<R: io::Read> {
+    inner: R,
+    counters: Counters,
+}
+
+impl<R: io::Read> MeteredReader<R> {
+    /// Creates a reader with all counters set to zero
+    pub fn new(reader: R) -> Self {
+        Self {
+            inner: reader,
+            counters: Counters::default(),
+        }
+    }
+
+    /// Unpacks the next value from the bytesource and counts it
+    pub fn take<T: Unpack>(&mut self) -> Result<T> {
+        let before = self.counters.bytes;
+        let result = T::unpack_from(self);
+
+        match &result {
+            Ok(_) => {
+                self.counters.values += 1;
+                report::unpacked::<T>((self.counters.bytes - before) as usize);
+            }
+            Err(_) => {
+                self.counters.errors += 1;
+                report::unpack_failed::<T>();
+            }
+        }
+
+        result
+    }
+
+    /// Returns the counters collected so far
+    pub fn counters(&self) -> Counters {
+        self.counters
+    }
+
+    /// Returns the counters collected so far and sets them to zero
+    pub fn reset(&mut self) -> Counters {
+        std::mem::take(&mut self.counters)
+    }
+
+    /// Returns a reference to the underlying bytesource
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the underlying bytesource
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> io::Read for MeteredReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buffer)?;
+        self.counters.bytes += len as u64;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "metrics")]
+mod report {
+    use std::any::type_name;
+
+    pub(super) fn packed<T: ?Sized>(bytes: usize) {
+        let labels = [("type", type_name::<T>())];
+        metrics::counter!("serial_container_packed_bytes", &labels).increment(bytes as u64);
+        metrics::counter!("serial_container_packed_values", &labels).increment(1);
+    }
+
+    pub(super) fn pack_failed<T: ?Sized>() {
+        let labels = [("type", type_name::<T>())];
+        metrics::counter!("serial_container_pack_errors", &labels).increment(1);
+    }
+
+    pub(super) fn unpacked<T>(bytes: usize) {
+        let labels = [("type", type_name::<T>())];
+        metrics::counter!("serial_container_unpacked_bytes", &labels).increment(bytes as u64);
+        metrics::counter!("serial_container_unpacked_values", &labels).increment(1);
+    }
+
+    pub(super) fn unpack_failed<T>() {
+        let labels = [("type", type_name::<T>())];
+        metrics::counter!("serial_container_unpack_errors", &labels).increment(1);
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+#[allow(clippy::extra_unused_type_parameters)]
+mod report {
+    pub(super) fn packed<T: ?Sized>(_bytes: usize) {}
+
+    pub(super) fn pack_failed<T: ?Sized>() {}
+
+    pub(super) fn unpacked<T>(_bytes: usize) {}
+
+    pub(super) fn unpack_failed<T>() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn count_packed_values() {
+        let mut writer = MeteredWriter::new(Vec::new());
+        writer.put(&2u16).unwrap();
+        writer.put("ab").unwrap();
+        writer.write_all(&[0xFF]).unwrap();
+
+        let counters = Counters {
+            bytes: 9,
+            values: 2,
+            errors: 0,
+        };
+        assert_eq!(writer.counters(), counters);
+        assert_eq!(writer.reset(), counters);
+        assert_eq!(writer.counters(), Counters::default());
+        assert_eq!(writer.into_inner().len(), 9);
+    }
+
+    #[test]
+    fn count_pack_errors() {
+        let mut buffer = [0u8; 3];
+        let mut writer = MeteredWriter::new(buffer.as_mut());
+        assert!(writer.put(&1u32).is_err());
+        assert_eq!(writer.counters().errors, 1);
+        assert_eq!(writer.counters().values, 0);
+    }
+
+    #[test]
+    fn count_unpacked_values() {
+        let bytes = [0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x61, 0x01];
+        let mut reader = MeteredReader::new(bytes.as_ref());
+        assert_eq!(reader.take::<u16>().unwrap(), 2);
+        assert_eq!(reader.take::<String>().unwrap(), "a");
+        assert!(reader.take::<u16>().is_err());
+
+        let counters = reader.counters();
+        assert_eq!(counters.values, 2);
+        assert_eq!(counters.errors, 1);
+        assert_eq!(counters.bytes, 8);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::unpack::{Error, Result, Unpack};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Read-only memory map of a file containing packed values
+///
+/// The content of the file is not read up front. Only the pages touched by
+/// unpacking are loaded by the operating system, so single regions of huge
+/// datasets can be decoded without streaming the whole file through a reader
+///
+/// The file must not be modified by other processes while it is mapped,
+/// otherwise unpacking may observe inconsistent bytes
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    /// Maps the file at the given path into memory
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read-only and the type documents that the file
+        // must not be modified concurrently
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self { map })
+    }
+
+    /// Returns the mapped content of the file
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Returns the length of the mapped file in bytes
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether the mapped file is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Tries to deserialize a value starting at the given byte offset
+    ///
+    /// Returns the value together with the offset directly behind it, so
+    /// consecutive values can be unpacked by chaining calls
+    pub fn unpack_at<T: Unpack>(&self, offset: usize) -> Result<(T, usize)> {
+        let mut remaining = self
+            .map
+            .get(offset..)
+            .ok_or_else(|| Error::IO(io::ErrorKind::UnexpectedEof.into()))?;
+        let len = remaining.len();
+        let value = T::unpack_from_buffered(&mut remaining)?;
+        Ok((value, offset + len - remaining.len()))
+    }
+
+    /// Tries to deserialize the whole file as a single value
+    ///
+    /// Deserialization fails if bytes are left over behind the value
+    pub fn unpack<T: Unpack>(&self) -> Result<T> {
+        let (value, end) = self.unpack_at(0)?;
+
+        if end < self.map.len() {
+            return Err(Error::TrailingBytes(self.map.len() - end));
+        }
+
+        Ok(value)
+    }
+}
+
+/// Tries to deserialize the file at the given path as a single value by
+/// mapping it into memory
+///
+/// See [`MappedFile`] for unpacking only parts of a file
+pub fn unpack_from_mmap<T: Unpack>(path: impl AsRef<Path>) -> Result<T> {
+    MappedFile::open(path)?.unpack()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, content: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("stacker-{}-{}", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn unpack_mapped_file() {
+        let path = temp_file("mmap", &[0x00, 0x00, 0x00, 0x02, 0x61, 0x62]);
+        let value: String = unpack_from_mmap(&path).unwrap();
+        assert_eq!(value, "ab");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unpack_mapped_region() {
+        let path = temp_file("mmap-region", &[0x00, 0x01, 0x00, 0x02, 0x00, 0x03]);
+        let file = MappedFile::open(&path).unwrap();
+        let (value, next) = file.unpack_at::<u16>(2).unwrap();
+        assert_eq!((value, next), (2, 4));
+        assert!(file.unpack_at::<u32>(4).is_err());
+        assert!(matches!(file.unpack::<u16>(), Err(Error::TrailingBytes(4))));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+//! Interoperability with [MessagePack](https://msgpack.org)
+//!
+//! Values are transcoded through their [`Schema`]: a packed value is read
+//! as a [`Value`] and written as MessagePack, and vice versa. Structs become
+//! MessagePack maps with their field names as keys, which is what most
+//! MessagePack libraries in other languages produce for objects
+//!
+//! # Example
+//!
+//! ```
+//! use serial_container::msgpack::{from_msgpack, to_msgpack};
+//!
+//! let bytes = to_msgpack(&[1u32, 300]).unwrap();
+//! assert_eq!(bytes, [0x92, 0x01, 0xCD, 0x01, 0x2C]);
+//!
+//! let value: Vec<u32> = from_msgpack(&bytes).unwrap();
+//! assert_eq!(value, [1, 300]);
+//! ```
+
+use crate::pack::{checked_len, Pack};
+use crate::schema::{Describe, Schema};
+use crate::unpack::{check_depth, read_bytes, Error, Result, Unpack};
+use crate::value::Value;
+use std::io;
+
+/// Converts a packable value into MessagePack
+pub fn to_msgpack<T: Pack + Describe + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let packed = value.pack_to_vec()?;
+    let value = Value::unpack_with_schema(&T::schema(), &mut packed.as_slice())?;
+    let mut bytes = Vec::new();
+    write_value(&value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Converts MessagePack into an unpackable value
+///
+/// Fails with `Error::TrailingBytes` if the bytes are not consumed entirely
+pub fn from_msgpack<T: Unpack + Describe>(bytes: &[u8]) -> Result<T> {
+    from_msgpack_with_schema(&T::schema(), bytes).and_then(|packed| T::unpack_exact(&packed))
+}
+
+/// Converts MessagePack into the packed format described by the schema
+pub fn from_msgpack_with_schema(schema: &Schema, mut bytes: &[u8]) -> Result<Vec<u8>> {
+    let value = read_value(&mut bytes)?;
+
+    if !bytes.is_empty() {
+        return Err(Error::TrailingBytes(bytes.len()));
+    }
+
+    let mut packed = Vec::new();
+    value.pack_with_schema(schema, &mut packed)?;
+    Ok(packed)
+}
+
+/// Writes a value as MessagePack and returns the number of bytes written
+///
+/// Integers outside of the `i64`/`u64` range cannot be represented and fail
+/// with `Error::InvalidValue`
+pub fn write_value(value: &Value, writer: &mut impl io::Write) -> Result<usize> {
+    let written = match value {
+        Value::Null => 0xC0u8.pack_into(writer)?,
+        Value::Bool(true) => 0xC3u8.pack_into(writer)?,
+        Value::Bool(false) => 0xC2u8.pack_into(writer)?,
+        Value::Int(value) => write_int(*value, writer)?,
+        Value::Float(value) => 0xCBu8.pack_into(writer)? + value.pack_into(writer)?,
+        Value::Str(value) => {
+            let len = value.len();
+            let written = if len < 32 {
+                (0xA0 | len as u8).pack_into(writer)?
+            } else {
+                write_len(len, [0xD9, 0xDA, 0xDB], writer)?
+            };
+            writer.write_all(value.as_bytes())?;
+            written + len
+        }
+        Value::Bytes(value) => {
+            let written = write_len(value.len(), [0xC4, 0xC5, 0xC6], writer)?;
+            writer.write_all(value)?;
+            written + value.len()
+        }
+        Value::List(items) => {
+            let mut written = if items.len() < 16 {
+                (0x90 | items.len() as u8).pack_into(writer)?
+            } else {
+                write_len(items.len(), [0x00, 0xDC, 0xDD], writer)?
+            };
+
+            for item in items {
+                written += write_value(item, writer)?;
+            }
+
+            written
+        }
+        Value::Map(entries) => {
+            let mut written = if entries.len() < 16 {
+                (0x80 | entries.len() as u8).pack_into(writer)?
+            } else {
+                write_len(entries.len(), [0x00, 0xDE, 0xDF], writer)?
+            };
+
+            for (key, value) in entries {
+                written += write_value(key, writer)?;
+                written += write_value(value, writer)?;
+            }
+
+            written
+        }
+    };
+    Ok(written)
+}
+
+/// Reads a single MessagePack value
+///
+/// Extension types are not supported and fail with `Error::InvalidValue`,
+/// as do arrays and maps nested too deeply
+pub fn read_value(reader: &mut impl io::Read) -> Result<Value> {
+    read_nested(reader, 0)
+}
+
+fn read_nested(reader: &mut impl io::Read, depth: usize) -> Result<Value> {
+    check_depth(depth)?;
+    let marker = u8::unpack_from(reader)?;
+    let value = match marker {
+        0x00..=0x7F => Value::Int(marker.into()),
+        0x80..=0x8F => read_map(reader, (marker & 0x0F).into(), depth)?,
+        0x90..=0x9F => read_list(reader, (marker & 0x0F).into(), depth)?,
+        0xA0..=0xBF => read_str(reader, (marker & 0x1F).into())?,
+        0xC0 => Value::Null,
+        0xC2 => Value::Bool(false),
+        0xC3 => Value::Bool(true),
+        0xC4 => {
+            let len = u8::unpack_from(reader)?.into();
+            Value::Bytes(read_bytes(reader, len)?)
+        }
+        0xC5 => {
+            let len = u16::unpack_from(reader)?.into();
+            Value::Bytes(read_bytes(reader, len)?)
+        }
+        0xC6 => {
+            let len = u32::unpack_from(reader)? as usize;
+            Value::Bytes(read_bytes(reader, len)?)
+        }
+        0xCA => Value::Float(f32::unpack_from(reader)?.into()),
+        0xCB => Value::Float(f64::unpack_from(reader)?),
+        0xCC => Value::Int(u8::unpack_from(reader)?.into()),
+        0xCD => Value::Int(u16::unpack_from(reader)?.into()),
+        0xCE => Value::Int(u32::unpack_from(reader)?.into()),
+        0xCF => Value::Int(u64::unpack_from(reader)?.into()),
+        0xD0 => Value::Int((u8::unpack_from(reader)? as i8).into()),
+        0xD1 => Value::Int(i16::unpack_from(reader)?.into()),
+        0xD2 => Value::Int(i32::unpack_from(reader)?.into()),
+        0xD3 => Value::Int(i64::unpack_from(reader)?.into()),
+        0xD9 => {
+            let len = u8::unpack_from(reader)?.into();
+            read_str(reader, len)?
+        }
+        0xDA => {
+            let len = u16::unpack_from(reader)?.into();
+            read_str(reader, len)?
+        }
+        0xDB => {
+            let len = u32::unpack_from(reader)? as usize;
+            read_str(reader, len)?
+        }
+        0xDC => {
+            let len = u16::unpack_from(reader)?.into();
+            read_list(reader, len, depth)?
+        }
+        0xDD => {
+            let len = u32::unpack_from(reader)? as usize;
+            read_list(reader, len, depth)?
+        }
+        0xDE => {
+            let len = u16::unpack_from(reader)?.into();
+            read_map(reader, len, depth)?
+        }
+        0xDF => {
+            let len = u32::unpack_from(reader)? as usize;
+            read_map(reader, len, depth)?
+        }
+        0xE0..=0xFF => Value::Int((marker as i8).into()),
+        marker => {
+            return Err(Error::InvalidValue(format!(
+                "unsupported MessagePack marker {:#04x}",
+                marker
+            )))
+        }
+    };
+    Ok(value)
+}
+
+fn write_int(value: i128, writer: &mut impl io::Write) -> Result<usize> {
+    let written = match value {
+        0..=0x7F => (value as u8).pack_into(writer)?,
+        -32..=-1 => (value as u8).pack_into(writer)?,
+        0x80..=0xFF => 0xCCu8.pack_into(writer)? + (value as u8).pack_into(writer)?,
+        0x100..=0xFFFF => 0xCDu8.pack_into(writer)? + (value as u16).pack_into(writer)?,
+        0x1_0000..=0xFFFF_FFFF => 0xCEu8.pack_into(writer)? + (value as u32).pack_into(writer)?,
+        0x1_0000_0000..=0xFFFF_FFFF_FFFF_FFFF => {
+            0xCFu8.pack_into(writer)? + (value as u64).pack_into(writer)?
+        }
+        -0x80..=-33 => 0xD0u8.pack_into(writer)? + (value as u8).pack_into(writer)?,
+        -0x8000..=-0x81 => 0xD1u8.pack_into(writer)? + (value as i16).pack_into(writer)?,
+        -0x8000_0000..=-0x8001 => 0xD2u8.pack_into(writer)? + (value as i32).pack_into(writer)?,
+        -0x8000_0000_0000_0000..=-0x8000_0001 => {
+            0xD3u8.pack_into(writer)? + (value as i64).pack_into(writer)?
+        }
+        value => {
+            return Err(Error::InvalidValue(format!(
+                "{} cannot be represented in MessagePack",
+                value
+            )))
+        }
+    };
+    Ok(written)
+}
+
+fn write_len(len: usize, markers: [u8; 3], writer: &mut impl io::Write) -> Result<usize> {
+    let written = if len <= 0xFF && markers[0] != 0x00 {
+        markers[0].pack_into(writer)? + (len as u8).pack_into(writer)?
+    } else if len <= 0xFFFF {
+        markers[1].pack_into(writer)? + (len as u16).pack_into(writer)?
+    } else {
+        let len = checked_len(len)?;
+        markers[2].pack_into(writer)? + len.pack_into(writer)?
+    };
+    Ok(written)
+}
+
+fn read_str(reader: &mut impl io::Read, len: usize) -> Result<Value> {
+    Ok(Value::Str(
+        String::from_utf8(read_bytes(reader, len)?).map_err(Error::UTF8)?,
+    ))
+}
+
+fn read_list(reader: &mut impl io::Read, len: usize, depth: usize) -> Result<Value> {
+    let mut items = Vec::new();
+
+    for _i in 0..len {
+        items.push(read_nested(reader, depth + 1)?);
+    }
+
+    Ok(Value::List(items))
+}
+
+fn read_map(reader: &mut impl io::Read, len: usize, depth: usize) -> Result<Value> {
+    let mut entries = Vec::new();
+
+    for _i in 0..len {
+        let key = read_nested(reader, depth + 1)?;
+        let value = read_nested(reader, depth + 1)?;
+        entries.push((key, value));
+    }
+
+    Ok(Value::Map(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: Value) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let written = write_value(&value, &mut bytes).unwrap();
+        assert_eq!(written, bytes.len());
+        assert_eq!(read_value(&mut bytes.as_slice()).unwrap(), value);
+        bytes
+    }
+
+    #[test]
+    fn encode_integers() {
+        assert_eq!(round_trip(Value::Int(5)), [0x05]);
+        assert_eq!(round_trip(Value::Int(-3)), [0xFD]);
+        assert_eq!(round_trip(Value::Int(200)), [0xCC, 0xC8]);
+        assert_eq!(round_trip(Value::Int(-200)), [0xD1, 0xFF, 0x38]);
+        assert_eq!(round_trip(Value::Int(u64::MAX.into())).len(), 9);
+        assert_eq!(round_trip(Value::Int(i64::MIN.into())).len(), 9);
+    }
+
+    #[test]
+    fn encode_containers() {
+        let value = Value::Map(vec![(
+            Value::Str("a".into()),
+            Value::List(vec![Value::Null, Value::Bool(true), Value::Bytes(vec![1])]),
+        )]);
+        assert_eq!(
+            round_trip(value),
+            [0x81, 0xA1, 0x61, 0x93, 0xC0, 0xC3, 0xC4, 0x01, 0x01]
+        );
+
+        let long = Value::Str("x".repeat(40));
+        assert_eq!(round_trip(long)[..2], [0xD9, 40]);
+    }
+
+    #[test]
+    fn encode_out_of_range() {
+        let result = write_value(&Value::Int(i128::MAX), &mut Vec::new());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn struct_from_map() {
+        let schema = Schema::structure("Point")
+            .field("x", Schema::I16)
+            .field("y", Schema::I16);
+        let bytes = [0x82, 0xA1, 0x79, 0x02, 0xA1, 0x78, 0xFF];
+        let packed = from_msgpack_with_schema(&schema, &bytes).unwrap();
+        assert_eq!(packed, [0xFF, 0xFF, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn decode_extension_type() {
+        let result = read_value(&mut [0xD4, 0x01, 0x00].as_ref());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn read_deeply_nested_array() {
+        let bytes = vec![0x91; 100_000];
+        let result = read_value(&mut bytes.as_slice());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,225 @@
+//! Forward-compatible enums keeping unknown variants
+//!
+//! A gateway forwarding messages should not fail on variants added by newer
+//! peers. [`OpenEnum`] packs every variant as its tag (`u32`), the length of
+//! its payload (`u32`) and the packed payload. Thanks to the length, the
+//! payload of an unknown tag can be captured as raw bytes and is packed again
+//! unchanged:
+//!
+//! ```
+//! use serial_container::open_enum::{OpenEnum, Variant};
+//! use serial_container::pack::{self, Pack};
+//! use serial_container::unpack::{Result, Unpack};
+//! use std::io;
+//!
+//! enum Command {
+//!     Stop,
+//!     Move(u16),
+//! }
+//!
+//! impl Variant for Command {
+//!     fn tag(&self) -> u32 {
+//!         match self {
+//!             Command::Stop => 0,
+//!             Command::Move(_) => 1,
+//!         }
+//!     }
+//!
+//!     fn pack_payload(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+//!         match self {
+//!             Command::Stop => Ok(0),
+//!             Command::Move(distance) => distance.pack_into(writer),
+//!         }
+//!     }
+//!
+//!     fn unpack_payload(tag: u32, reader: &mut impl io::Read) -> Result<Option<Self>> {
+//!         match tag {
+//!             0 => Ok(Some(Command::Stop)),
+//!             1 => Ok(Some(Command::Move(u16::unpack_from(reader)?))),
+//!             _ => Ok(None),
+//!         }
+//!     }
+//! }
+//!
+//! let bytes = [0, 0, 0, 7, 0, 0, 0, 1, 0xAB];
+//! let command = OpenEnum::<Command>::unpack_exact(&bytes).unwrap();
+//! assert!(!command.is_known());
+//! assert_eq!(command.pack_to_vec().unwrap(), bytes);
+//! ```
+
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::io;
+
+/// Describes the variants of an enum packed within an [`OpenEnum`]
+pub trait Variant: Sized {
+    /// Returns the tag identifying the variant of this value
+    fn tag(&self) -> u32;
+
+    /// Packs the fields of the variant without its tag
+    fn pack_payload(&self, writer: &mut impl io::Write) -> pack::Result<usize>;
+
+    /// Unpacks the fields of the variant with the given tag
+    ///
+    /// Returns `None` for unknown tags, without reading from the bytesource
+    fn unpack_payload(tag: u32, reader: &mut impl io::Read) -> Result<Option<Self>>;
+}
+
+/// Enum value that is either a known variant or an unknown variant captured
+/// with its raw payload
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OpenEnum<T> {
+    /// Variant known to this implementation
+    Known(T),
+    /// Variant with a tag unknown to this implementation
+    Other {
+        /// Tag of the variant
+        tag: u32,
+        /// Packed payload of the variant
+        payload: Vec<u8>,
+    },
+}
+
+impl<T: Variant> OpenEnum<T> {
+    /// Returns the tag of the variant
+    pub fn tag(&self) -> u32 {
+        match self {
+            OpenEnum::Known(value) => value.tag(),
+            OpenEnum::Other { tag, .. } => *tag,
+        }
+    }
+}
+
+impl<T> OpenEnum<T> {
+    /// Returns whether the variant is known to this implementation
+    pub fn is_known(&self) -> bool {
+        matches!(self, OpenEnum::Known(_))
+    }
+
+    /// Returns the known variant, if any
+    pub fn known(&self) -> Option<&T> {
+        match self {
+            OpenEnum::Known(value) => Some(value),
+            OpenEnum::Other { .. } => None,
+        }
+    }
+
+    /// Returns the known variant, if any, consuming this value
+    pub fn into_known(self) -> Option<T> {
+        match self {
+            OpenEnum::Known(value) => Some(value),
+            OpenEnum::Other { .. } => None,
+        }
+    }
+}
+
+impl<T> From<T> for OpenEnum<T> {
+    fn from(value: T) -> Self {
+        OpenEnum::Known(value)
+    }
+}
+
+impl<T: Variant> Pack for OpenEnum<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut buffer = Vec::new();
+        let payload = match self {
+            OpenEnum::Known(value) => {
+                value.pack_payload(&mut buffer)?;
+                &buffer
+            }
+            OpenEnum::Other { payload, .. } => payload,
+        };
+
+        let mut written = self.tag().pack_into(writer)?;
+        written += checked_len(payload.len())?.pack_into(writer)?;
+        writer.write_all(payload)?;
+        Ok(written + payload.len())
+    }
+}
+
+/// Fails with `Error::TrailingBytes` if a known variant does not consume its
+/// whole payload
+impl<T: Variant> Unpack for OpenEnum<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let tag = u32::unpack_from(reader)?;
+        let len = u32::unpack_from(reader)? as usize;
+        let payload = read_bytes(reader, len)?;
+        let mut remaining = payload.as_slice();
+
+        match T::unpack_payload(tag, &mut remaining)? {
+            Some(_) if !remaining.is_empty() => Err(Error::TrailingBytes(remaining.len())),
+            Some(value) => Ok(OpenEnum::Known(value)),
+            None => Ok(OpenEnum::Other { tag, payload }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Ping,
+        Text(String),
+    }
+
+    impl Variant for Event {
+        fn tag(&self) -> u32 {
+            match self {
+                Event::Ping => 1,
+                Event::Text(_) => 2,
+            }
+        }
+
+        fn pack_payload(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+            match self {
+                Event::Ping => Ok(0),
+                Event::Text(text) => text.as_str().pack_into(writer),
+            }
+        }
+
+        fn unpack_payload(tag: u32, reader: &mut impl io::Read) -> Result<Option<Self>> {
+            match tag {
+                1 => Ok(Some(Event::Ping)),
+                2 => Ok(Some(Event::Text(String::unpack_from(reader)?))),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    #[test]
+    fn known_variants() {
+        let bytes = OpenEnum::from(Event::Text("a".into()))
+            .pack_to_vec()
+            .unwrap();
+        assert_eq!(bytes, [0, 0, 0, 2, 0, 0, 0, 5, 0, 0, 0, 1, b'a']);
+        let value = OpenEnum::<Event>::unpack_exact(&bytes).unwrap();
+        assert_eq!(value.into_known(), Some(Event::Text("a".into())));
+
+        let bytes = OpenEnum::from(Event::Ping).pack_to_vec().unwrap();
+        assert_eq!(bytes, [0, 0, 0, 1, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn unknown_variant_survives() {
+        let bytes = [0, 0, 0, 9, 0, 0, 0, 2, 0xCA, 0xFE];
+        let value = OpenEnum::<Event>::unpack_exact(&bytes).unwrap();
+        assert_eq!(
+            value,
+            OpenEnum::Other {
+                tag: 9,
+                payload: vec![0xCA, 0xFE]
+            }
+        );
+        assert_eq!(value.tag(), 9);
+        assert_eq!(value.pack_to_vec().unwrap(), bytes);
+    }
+
+    #[test]
+    fn payload_not_consumed() {
+        let bytes = [0, 0, 0, 1, 0, 0, 0, 1, 0x00];
+        let result = OpenEnum::<Event>::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::TrailingBytes(1))));
+    }
+}
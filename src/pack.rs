@@ -1,10 +1,17 @@
+use crate::pool::{PackPool, PooledBuffer};
+use crate::trace;
 use std::collections::binary_heap::*;
 use std::collections::btree_map::*;
 use std::collections::btree_set::*;
 use std::collections::hash_map::*;
 use std::collections::hash_set::*;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
 use std::io;
 use std::num::*;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Describes the ability to serialize this struct into a sequential
 /// bytestream
@@ -16,288 +23,702 @@ use std::num::*;
 /// A derived trait would make assumptions
 /// about their order and this would break compatibility with
 /// deserializing those structs later
+///
+/// With the `tracing` feature enabled, the methods packing a whole struct
+/// (all but `pack_into`) emit a span and an event with the type name, the
+/// number of bytes and the elapsed time
 pub trait Pack {
+    /// Number of bytes every packed value of this type occupies, if that
+    /// number is fixed
+    ///
+    /// Sequences of such values are packed into a single buffer and written
+    /// at once instead of element by element
+    #[doc(hidden)]
+    const PACKED_SIZE: Option<usize> = None;
+
     /// Tries to serialize this struct into a bytestream
     ///
     /// Serialization may fail because of any IO-Error
     /// (except of the ErrorKind::Interrupted which are ignored)
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize>;
+    /// or because the value cannot be represented in its encoding
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize>;
+
+    /// Packs consecutive values of a fixed packed size with a single write
+    ///
+    /// The values are collected into one buffer by default, bytes override
+    /// this to write the slice as it is
+    #[doc(hidden)]
+    fn pack_packed(values: &[Self], writer: &mut impl io::Write) -> Result<usize>
+    where
+        Self: Sized,
+    {
+        let mut buffer = Vec::with_capacity(values.len() * Self::PACKED_SIZE.unwrap_or(0));
+
+        for value in values {
+            value.pack_into(&mut buffer)?;
+        }
+
+        write_bytes(writer, &buffer)
+    }
 
     /// Tries to serialize this struct into a byte-vector
     ///
     /// Serialization may fail because of any IO-Error
     /// (except of the ErrorKind::Interrupted which are ignored)
-    fn pack_to_vec(&self) -> io::Result<Vec<u8>> {
+    /// or because the value cannot be represented in its encoding
+    fn pack_to_vec(&self) -> Result<Vec<u8>> {
         let mut buffer = Vec::new();
-        self.pack_into(&mut buffer)?;
+        trace::pack::<Self>(|| self.pack_into(&mut buffer))?;
         Ok(buffer)
     }
+
+    /// Tries to serialize this struct into a buffer borrowed from a pool
+    ///
+    /// The buffer returns to the pool when it is dropped, so hot paths
+    /// packing many values do not allocate a new buffer per value
+    fn pack_to_pooled<'a>(&self, pool: &'a PackPool) -> Result<PooledBuffer<'a>> {
+        let mut buffer = pool.take();
+        trace::pack::<Self>(|| self.pack_into(buffer.as_mut_vec()))?;
+        Ok(buffer)
+    }
+
+    /// Tries to serialize this struct into a bytestream as a frame
+    ///
+    /// The frame starts with the length of the packed struct as `u32`,
+    /// followed by the packed struct itself, so that it can be read back
+    /// exactly with `unpack_framed`
+    fn pack_framed(&self, writer: &mut impl io::Write) -> Result<usize> {
+        let buffer = self.pack_to_vec()?;
+        let len = checked_len(buffer.len())?;
+        let written = len.pack_into(writer)?;
+        writer.write_all(&buffer)?;
+        Ok(written + buffer.len())
+    }
+
+    /// Tries to serialize this struct into a bytestream with a single write
+    ///
+    /// Packing a struct of many small fields directly into an unbuffered
+    /// writer (e.g. a socket) issues one call to `write` per field. This
+    /// method packs the struct into an intermediate buffer first, which is
+    /// then handed to the writer with a single call to `write_all`
+    fn pack_coalesced(&self, writer: &mut impl io::Write) -> Result<usize> {
+        let buffer = self.pack_to_vec()?;
+        writer.write_all(&buffer)?;
+        Ok(buffer.len())
+    }
+
+    /// Tries to serialize this struct into a caller-provided buffer
+    ///
+    /// Returns the number of bytes written to the start of the buffer.
+    /// Serialization fails with `ErrorKind::WriteZero` if the buffer is
+    /// too small, in which case its content is unspecified
+    fn pack_into_slice(&self, buffer: &mut [u8]) -> Result<usize> {
+        let mut remaining = buffer;
+        trace::pack::<Self>(|| self.pack_into(&mut remaining))
+    }
+
+    /// Tries to serialize this struct into the file at the given path
+    ///
+    /// The struct is packed into a temporary file next to the target first,
+    /// which then replaces the target. A crash in the middle of writing
+    /// therefore never leaves a truncated file behind, the target either
+    /// contains the old or the new content
+    fn pack_to_file(&self, path: impl AsRef<Path>) -> Result<usize> {
+        write_atomic(path.as_ref(), false, |file| {
+            trace::pack::<Self>(|| self.pack_into(file))
+        })
+    }
+
+    /// Tries to serialize this struct into the file at the given path and
+    /// waits until the content has reached the disk
+    ///
+    /// Works like `pack_to_file`, but additionally syncs the file before it
+    /// replaces the target and, on Unix, the directory after the rename, so
+    /// the new content survives a power loss
+    fn pack_to_file_synced(&self, path: impl AsRef<Path>) -> Result<usize> {
+        write_atomic(path.as_ref(), true, |file| {
+            trace::pack::<Self>(|| self.pack_into(file))
+        })
+    }
+
+    /// Tries to serialize this struct into a buffer of the `bytes` crate
+    ///
+    /// The bytes are appended directly to the buffer, so no intermediate
+    /// `Vec` is required when writing into e.g. a `BytesMut`
+    #[cfg(feature = "bytes")]
+    fn pack_into_buf(&self, buffer: &mut impl bytes::BufMut) -> Result<usize> {
+        self.pack_into(&mut bytes::BufMut::writer(buffer))
+    }
+}
+
+/// Describes types whose packed values always occupy the same number of
+/// bytes
+///
+/// The size is known at compile time, so callers can allocate exact stack
+/// buffers and compute the offsets of records in random-access files as
+/// constants. Structs of fixed-size fields implement it with the sum of the
+/// sizes of their fields
+///
+/// ```
+/// use serial_container::pack::{self, Pack, PackFixed};
+/// # use std::io;
+///
+/// struct Record {
+///     id: u32,
+///     value: f64,
+/// }
+///
+/// impl Pack for Record {
+///     fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+///         Ok(self.id.pack_into(writer)? + self.value.pack_into(writer)?)
+///     }
+/// }
+///
+/// impl PackFixed for Record {
+///     const SIZE: usize = u32::SIZE + f64::SIZE;
+/// }
+///
+/// const THIRD_RECORD: usize = 2 * Record::SIZE;
+///
+/// let mut buffer = [0x00; Record::SIZE];
+/// let record = Record { id: 1, value: 0.5 };
+/// assert_eq!(record.pack_into_slice(&mut buffer).unwrap(), Record::SIZE);
+/// assert_eq!(THIRD_RECORD, 24);
+/// ```
+pub trait PackFixed: Pack {
+    /// Number of bytes every packed value of this type occupies
+    const SIZE: usize;
+}
+
+/// Implements `PackFixed` with the size taken from `Pack::PACKED_SIZE`, so
+/// both constants can not disagree
+macro_rules! pack_fixed {
+    ($($type:ty),+ $(,)?) => {
+        $(
+            impl $crate::pack::PackFixed for $type {
+                const SIZE: usize =
+                    $crate::pack::fixed_size(<$type as $crate::pack::Pack>::PACKED_SIZE);
+            }
+        )+
+    };
 }
 
+pub(crate) use pack_fixed;
+
+/// Unwraps a `PACKED_SIZE` at compile time
+pub(crate) const fn fixed_size(size: Option<usize>) -> usize {
+    match size {
+        Some(size) => size,
+        None => panic!("type has no fixed packed size"),
+    }
+}
+
+self::pack_fixed! {
+    bool, u8, NonZeroU8, Option<NonZeroU8>,
+    u16, NonZeroU16, Option<NonZeroU16>, i16, NonZeroI16, Option<NonZeroI16>,
+    u32, NonZeroU32, Option<NonZeroU32>, i32, NonZeroI32, Option<NonZeroI32>, f32,
+    u64, NonZeroU64, Option<NonZeroU64>, i64, NonZeroI64, Option<NonZeroI64>, f64,
+    u128, NonZeroU128, Option<NonZeroU128>, i128, NonZeroI128, Option<NonZeroI128>,
+}
+
+/// Arrays are packed with their length prefix like slices
+impl<T: PackFixed, const N: usize> PackFixed for [T; N] {
+    const SIZE: usize = 4 + N * T::SIZE;
+}
+
+/// Error that may occur during serialization
+///
+/// These are the possible reasons serialization may fail:
+/// - any IO-Error ocurred (ErrorKind::Interrupted is ignored)
+/// - a custom error previously defined occurred
+/// - a value cannot be represented in its encoding (e.g. it is out of range)
+/// - a string or collection is too long for its `u32` length prefix
+/// - a custom error carrying a numeric code for the wire occurred
+#[derive(Debug)]
+pub enum Error {
+    IO(io::Error),
+    Custom(Box<dyn error::Error + Send + Sync>),
+    InvalidValue(String),
+    LengthOverflow(usize),
+    Coded {
+        code: u16,
+        error: Box<dyn error::Error + Send + Sync>,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        use Error::*;
+        match self {
+            IO(error) => error.fmt(destination),
+            Custom(error) => error.fmt(destination),
+            InvalidValue(reason) => write!(destination, "invalid value: {}", reason),
+            LengthOverflow(len) => write!(
+                destination,
+                "length {} exceeds the maximum of {}",
+                len,
+                u32::MAX
+            ),
+            Coded { code, error } => write!(destination, "error {}: {}", code, error),
+        }
+    }
+}
+
+impl Error {
+    /// Creates a custom error carrying a numeric code, e.g. the code of the
+    /// error response a protocol sends for this failure
+    pub fn coded(code: u16, error: impl Into<Box<dyn error::Error + Send + Sync>>) -> Self {
+        Error::Coded {
+            code,
+            error: error.into(),
+        }
+    }
+
+    /// Returns the numeric code of a coded error
+    pub fn code(&self) -> Option<u16> {
+        match self {
+            Error::Coded { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::IO(error)
+    }
+}
+
+/// Converts the error for callers working with `io::Result`, e.g. inside
+/// implementations of `io::Write`
+///
+/// IO errors are returned unchanged, all other errors are wrapped into an
+/// error of the kind `InvalidInput`
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::IO(error) => error,
+            error => io::Error::new(io::ErrorKind::InvalidInput, error),
+        }
+    }
+}
+
+/// Wrapper for a serialization result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Converts the length of a string or collection into its `u32` prefix
+///
+/// Fails with `Error::LengthOverflow` instead of silently truncating the
+/// length, which would corrupt the rest of the stream
+pub fn checked_len(len: usize) -> Result<u32> {
+    u32::try_from(len).map_err(|_| Error::LengthOverflow(len))
+}
+
+/// Writes all bytes to the writer and returns their number
+pub(crate) fn write_bytes(writer: &mut impl io::Write, bytes: &[u8]) -> Result<usize> {
+    writer.write_all(bytes)?;
+    Ok(bytes.len())
+}
+
+fn write_atomic(
+    path: &Path,
+    sync: bool,
+    pack: impl FnOnce(&mut io::BufWriter<File>) -> Result<usize>,
+) -> Result<usize> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = File::options()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .map_err(Error::from)
+        .and_then(|file| {
+            let mut writer = io::BufWriter::new(file);
+            let written = pack(&mut writer)?;
+            let file = writer
+                .into_inner()
+                .map_err(io::IntoInnerError::into_error)?;
+
+            if sync {
+                file.sync_all()?;
+            }
+
+            Ok(written)
+        });
+
+    let written = match result.and_then(|written| {
+        fs::rename(&temp_path, path)
+            .map(|_| written)
+            .map_err(Error::from)
+    }) {
+        Ok(written) => written,
+        Err(error) => {
+            let _ = fs::remove_file(&temp_path);
+            return Err(error);
+        }
+    };
+
+    // the rename itself only survives a power loss once the directory
+    // entry has reached the disk
+    #[cfg(unix)]
+    if sync {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(parent)?.sync_all()?;
+    }
+
+    Ok(written)
+}
+
+/// Counter making the names of temporary files unique within the process
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
 impl Pack for bool {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(1);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             true => 0x00,
             false => 0xFF,
         };
         let buffer = [value];
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for u8 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(1);
+
+    fn pack_packed(values: &[Self], writer: &mut impl io::Write) -> Result<usize> {
+        write_bytes(writer, values)
+    }
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = [*self];
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroU8 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(1);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = [self.get()];
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroU8> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(1);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = [value];
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for u16 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroU16 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.get().to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroU16> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = value.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for u32 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroU32 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.get().to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroU32> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = value.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for u64 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroU64 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.get().to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroU64> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = value.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for u128 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroU128 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.get().to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroU128> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = value.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for i16 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroI16 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.get().to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroI16> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = value.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for i32 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroI32 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.get().to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroI32> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = value.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for i64 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroI64 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.get().to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroI64> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = value.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for i128 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for NonZeroI128 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.get().to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for Option<NonZeroI128> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = match self {
             Some(value) => value.get(),
             None => 0,
         };
         let buffer = value.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for f32 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for f64 {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.to_be_bytes();
-        writer.write(&buffer)
+        write_bytes(writer, &buffer)
     }
 }
 
 impl Pack for str {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let buffer = self.as_bytes();
-        let len = buffer.len() as u32;
+        let len = checked_len(buffer.len())?;
         let written = len.pack_into(writer)?;
-        writer.write(buffer).map(|x| written + x)
+        write_bytes(writer, buffer).map(|x| written + x)
     }
 }
 
 impl<T: Pack> Pack for [T] {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
-        let len = self.len() as u32;
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+        let len = checked_len(self.len())?;
         let mut written = len.pack_into(writer)?;
 
+        if T::PACKED_SIZE.is_some() {
+            return Ok(written + T::pack_packed(self, writer)?);
+        }
+
         for item in self.iter() {
             written += item.pack_into(writer)?;
         }
@@ -306,16 +727,33 @@ impl<T: Pack> Pack for [T] {
     }
 }
 
+impl<T: Pack, const N: usize> Pack for [T; N] {
+    const PACKED_SIZE: Option<usize> = match T::PACKED_SIZE {
+        Some(size) => Some(4 + N * size),
+        None => None,
+    };
+
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+        self.as_slice().pack_into(writer)
+    }
+}
+
+impl<T: Pack + ?Sized> Pack for &T {
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+        (**self).pack_into(writer)
+    }
+}
+
 impl<T: Pack> Pack for dyn AsRef<T> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
         let value = self.as_ref();
         value.pack_into(writer)
     }
 }
 
 impl<K: Pack, V: Pack> Pack for HashMap<K, V> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
-        let len = self.len() as u32;
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+        let len = checked_len(self.len())?;
         let mut written = len.pack_into(writer)?;
 
         for (key, value) in self.iter() {
@@ -328,8 +766,8 @@ impl<K: Pack, V: Pack> Pack for HashMap<K, V> {
 }
 
 impl<T: Pack> Pack for HashSet<T> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
-        let len = self.len() as u32;
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+        let len = checked_len(self.len())?;
         let mut written = len.pack_into(writer)?;
 
         for value in self.iter() {
@@ -341,8 +779,8 @@ impl<T: Pack> Pack for HashSet<T> {
 }
 
 impl<K: Pack, V: Pack> Pack for BTreeMap<K, V> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
-        let len = self.len() as u32;
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+        let len = checked_len(self.len())?;
         let mut written = len.pack_into(writer)?;
 
         for (key, value) in self.iter() {
@@ -355,8 +793,8 @@ impl<K: Pack, V: Pack> Pack for BTreeMap<K, V> {
 }
 
 impl<T: Pack> Pack for BTreeSet<T> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
-        let len = self.len() as u32;
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+        let len = checked_len(self.len())?;
         let mut written = len.pack_into(writer)?;
 
         for value in self.iter() {
@@ -368,8 +806,8 @@ impl<T: Pack> Pack for BTreeSet<T> {
 }
 
 impl<T: Pack> Pack for BinaryHeap<T> {
-    fn pack_into(&self, writer: &mut impl io::Write) -> io::Result<usize> {
-        let len = self.len() as u32;
+    fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+        let len = checked_len(self.len())?;
         let mut written = len.pack_into(writer)?;
 
         for value in self.iter() {
@@ -383,6 +821,7 @@ impl<T: Pack> Pack for BinaryHeap<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::unpack::Unpack;
     use std::rc::Rc;
 
     #[test]
@@ -651,4 +1090,212 @@ mod tests {
         let bytes = value.pack_to_vec().unwrap();
         assert_eq!(bytes, [0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03]);
     }
+
+    #[test]
+    fn pack_framed() {
+        let mut bytes = Vec::new();
+        let written = "ab".pack_framed(&mut bytes).unwrap();
+        assert_eq!(written, 10);
+        assert_eq!(
+            bytes,
+            [0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x02, 0x61, 0x62]
+        );
+    }
+
+    #[test]
+    fn pack_reference() {
+        let value: &u16 = &2;
+        let bytes = Pack::pack_to_vec(&value).unwrap();
+        assert_eq!(bytes, [0x00, 0x02]);
+    }
+
+    struct CountingWriter(Vec<u8>, usize);
+
+    impl io::Write for CountingWriter {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            self.1 += 1;
+            self.0.write(buffer)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn pack_coalesced() {
+        let value: [u16; 3] = [1, 2, 3];
+        let mut writer = CountingWriter(Vec::new(), 0);
+        let written = value.pack_coalesced(&mut writer).unwrap();
+        assert_eq!(written, 10);
+        assert_eq!(writer.1, 1);
+        assert_eq!(writer.0, value.pack_to_vec().unwrap());
+    }
+
+    #[test]
+    fn pack_slices_in_bulk() {
+        let bytes = vec![0xABu8; 1000];
+        let mut writer = CountingWriter(Vec::new(), 0);
+        assert_eq!(bytes.pack_into(&mut writer).unwrap(), 1004);
+        assert_eq!(writer.1, 2);
+        assert_eq!(writer.0[..5], [0x00, 0x00, 0x03, 0xE8, 0xAB]);
+
+        let mut writer = CountingWriter(Vec::new(), 0);
+        [1u32, 2].as_slice().pack_into(&mut writer).unwrap();
+        assert_eq!(writer.1, 2);
+        assert_eq!(writer.0, [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn pack_to_file() {
+        let path = std::env::temp_dir().join(format!("stacker-pack-{}", std::process::id()));
+        let written = "abc".pack_to_file(&path).unwrap();
+        assert_eq!(written, 7);
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63]
+        );
+
+        2u16.pack_to_file_synced(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), [0x00, 0x02]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn pack_to_file_concurrently() {
+        let path = std::env::temp_dir().join(format!("stacker-threads-{}", std::process::id()));
+
+        std::thread::scope(|scope| {
+            for value in 0..8u32 {
+                let path = &path;
+                scope.spawn(move || value.pack_to_file(path).unwrap());
+            }
+        });
+
+        assert!(u32::unpack_exact(&fs::read(&path).unwrap()).unwrap() < 8);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn pack_into_buf() {
+        let mut buffer = bytes::BytesMut::new();
+        let written = "ab".pack_into_buf(&mut buffer).unwrap();
+        assert_eq!(written, 6);
+        assert_eq!(buffer.as_ref(), [0x00, 0x00, 0x00, 0x02, 0x61, 0x62]);
+    }
+
+    #[test]
+    fn pack_domain_error() {
+        struct Percent(u8);
+
+        impl Pack for Percent {
+            fn pack_into(&self, writer: &mut impl io::Write) -> Result<usize> {
+                if self.0 > 100 {
+                    return Err(Error::InvalidValue(format!("{} exceeds 100", self.0)));
+                }
+
+                self.0.pack_into(writer)
+            }
+        }
+
+        assert_eq!(Percent(7).pack_to_vec().unwrap(), [0x07]);
+        let result = Percent(101).pack_to_vec();
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+
+        let error = io::Error::from(result.unwrap_err());
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let error = crate::unpack::Error::from(Percent(101).pack_to_vec().unwrap_err());
+        assert!(matches!(error, crate::unpack::Error::InvalidValue(_)));
+    }
+
+    #[test]
+    fn checked_len_boundary() {
+        assert_eq!(checked_len(u32::MAX as usize).unwrap(), u32::MAX);
+
+        if let Some(len) = (u32::MAX as usize).checked_add(1) {
+            assert!(matches!(checked_len(len), Err(Error::LengthOverflow(x)) if x == len));
+        }
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn pack_slice_length_overflow() {
+        #[derive(Clone, Copy)]
+        struct Empty;
+
+        impl Pack for Empty {
+            fn pack_into(&self, _writer: &mut impl io::Write) -> Result<usize> {
+                Ok(0)
+            }
+        }
+
+        const LEN: usize = u32::MAX as usize + 1;
+        let values = [Empty; LEN];
+        let mut bytes = Vec::new();
+        let result = values.as_slice().pack_into(&mut bytes);
+        assert!(matches!(result, Err(Error::LengthOverflow(LEN))));
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn pack_fixed_sizes() {
+        fn check<T: PackFixed>(value: T) {
+            assert_eq!(value.pack_to_vec().unwrap().len(), T::SIZE);
+            assert_eq!(T::PACKED_SIZE, Some(T::SIZE));
+        }
+
+        check(true);
+        check(NonZeroU16::new(3));
+        check(-4i32);
+        check(0.5f64);
+        check(u128::MAX);
+        check([[1u16; 2]; 3]);
+        assert_eq!(<[[u16; 2]; 3]>::SIZE, 4 + 3 * (4 + 2 * 2));
+    }
+
+    #[test]
+    fn pack_into_slice() {
+        let mut buffer = [0x00; 8];
+        let written = "ab".pack_into_slice(&mut buffer).unwrap();
+        assert_eq!(written, 6);
+        assert_eq!(buffer[..written], [0x00, 0x00, 0x00, 0x02, 0x61, 0x62]);
+    }
+
+    #[test]
+    fn pack_into_slice_too_small() {
+        let mut buffer = [0x00; 5];
+        let result = "ab".pack_into_slice(&mut buffer);
+        assert!(
+            matches!(result, Err(Error::IO(error)) if error.kind() == io::ErrorKind::WriteZero)
+        );
+
+        let result = 2u32.pack_into_slice(&mut buffer[..3]);
+        assert!(
+            matches!(result, Err(Error::IO(error)) if error.kind() == io::ErrorKind::WriteZero)
+        );
+    }
+
+    #[test]
+    fn pack_fixed_size_array() {
+        let value: [i16; 3] = [1, -1, 0x0203];
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(
+            bytes,
+            [0x00, 0x00, 0x00, 0x03, 0x00, 0x01, 0xFF, 0xFF, 0x02, 0x03]
+        );
+    }
+
+    #[test]
+    fn pack_nested_fixed_array() {
+        let value: [[u8; 2]; 2] = [[1, 2], [3, 4]];
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x01, 0x02, 0x00, 0x00, 0x00, 0x02,
+                0x03, 0x04
+            ]
+        );
+    }
 }
@@ -0,0 +1,255 @@
+//! File of values with a key to offset index for random access
+//!
+//! The file starts with the magic `PIDX`, followed by the packed values
+//! back to back. The index is appended at the end as the number of entries
+//! (`u32`) and every key with the offset and length of its value (`u64`
+//! each), sorted by key. The last 8 bytes hold the offset of the index as
+//! `u64`. Once the index is loaded, every lookup costs a single seek
+//!
+//! ```
+//! use serial_container::packed_index::{PackedIndex, PackedIndexWriter};
+//! use std::io::Cursor;
+//!
+//! let mut writer = PackedIndexWriter::<u32, [u8], _>::new(Cursor::new(Vec::new())).unwrap();
+//! writer.insert(7, &[1, 2]).unwrap();
+//! writer.insert(3, &[3]).unwrap();
+//! let file = writer.finish().unwrap();
+//!
+//! let mut index = PackedIndex::<u32, Vec<u8>, _>::open(file).unwrap();
+//! assert_eq!(index.get(&3).unwrap(), Some(vec![3]));
+//! assert_eq!(index.get(&5).unwrap(), None);
+//! ```
+
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::io::{self, Seek, SeekFrom};
+use std::marker::PhantomData;
+
+const MAGIC: [u8; 4] = *b"PIDX";
+
+/// Writer storing values sequentially and their index at the end
+///
+/// The index is only written by [`PackedIndexWriter::finish`], a file that
+/// was not finished cannot be opened
+pub struct PackedIndexWriter<K, V: ?Sized, W: io::Write> {
+    writer: W,
+    offset: u64,
+    index: BTreeMap<K, (u64, u64)>,
+    marker: PhantomData<fn(&V)>,
+}
+
+impl<K: Pack + Ord, V: Pack + ?Sized, W: io::Write> PackedIndexWriter<K, V, W> {
+    /// Starts a new file by writing its magic
+    pub fn new(mut writer: W) -> pack::Result<Self> {
+        writer.write_all(&MAGIC)?;
+
+        Ok(Self {
+            writer,
+            offset: MAGIC.len() as u64,
+            index: BTreeMap::new(),
+            marker: PhantomData,
+        })
+    }
+
+    /// Packs a value and records its offset under the given key
+    ///
+    /// Fails with `pack::Error::InvalidValue` if the key was already
+    /// inserted
+    pub fn insert(&mut self, key: K, value: &V) -> pack::Result<()> {
+        if self.index.contains_key(&key) {
+            return Err(pack::Error::InvalidValue(String::from(
+                "duplicate key in packed index",
+            )));
+        }
+
+        // pack into a buffer first, so a failing value leaves no partial
+        // bytes behind that would shift the offsets of later values
+        let bytes = value.pack_to_vec()?;
+        self.writer.write_all(&bytes)?;
+        let len = bytes.len() as u64;
+        self.index.insert(key, (self.offset, len));
+        self.offset += len;
+        Ok(())
+    }
+
+    /// Returns the number of values inserted so far
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns whether no value has been inserted yet
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Writes the index and returns the underlying bytestream
+    pub fn finish(mut self) -> pack::Result<W> {
+        checked_len(self.index.len())?.pack_into(&mut self.writer)?;
+
+        for (key, (offset, len)) in &self.index {
+            key.pack_into(&mut self.writer)?;
+            offset.pack_into(&mut self.writer)?;
+            len.pack_into(&mut self.writer)?;
+        }
+
+        self.offset.pack_into(&mut self.writer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// File opened for lookups of single values by their key
+pub struct PackedIndex<K, V, R: io::Read + Seek> {
+    reader: R,
+    index: BTreeMap<K, (u64, u64)>,
+    marker: PhantomData<fn() -> V>,
+}
+
+impl<K: Unpack + Ord, V: Unpack, R: io::Read + Seek> PackedIndex<K, V, R> {
+    /// Opens a file by reading its index
+    ///
+    /// Fails with `Error::InvalidValue` if the magic does not match
+    pub fn open(mut reader: R) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut magic = [0x00; 4];
+        reader.read_exact(&mut magic)?;
+
+        if magic != MAGIC {
+            return Err(Error::InvalidValue(String::from("not a packed index")));
+        }
+
+        reader.seek(SeekFrom::End(-8))?;
+        let offset = u64::unpack_from(&mut reader)?;
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let len = u32::unpack_from(&mut reader)?;
+        let mut index = BTreeMap::new();
+
+        for _i in 0..len {
+            let key = K::unpack_from(&mut reader)?;
+            let offset = u64::unpack_from(&mut reader)?;
+            index.insert(key, (offset, u64::unpack_from(&mut reader)?));
+        }
+
+        Ok(Self {
+            reader,
+            index,
+            marker: PhantomData,
+        })
+    }
+
+    /// Unpacks the value stored under the given key
+    ///
+    /// Fails with `Error::TrailingBytes` if the value does not consume all
+    /// bytes recorded for it
+    pub fn get<Q>(&mut self, key: &Q) -> Result<Option<V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (offset, len) = match self.index.get(key) {
+            Some(location) => *location,
+            None => return Ok(None),
+        };
+
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let bytes = read_bytes(&mut self.reader, len as usize)?;
+        V::unpack_exact(&bytes).map(Some)
+    }
+
+    /// Returns whether a value is stored under the given key
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.index.contains_key(key)
+    }
+
+    /// Returns all keys in ascending order
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.index.keys()
+    }
+
+    /// Returns the number of stored values
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns whether no value is stored
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Returns the underlying bytesource
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn file() -> Cursor<Vec<u8>> {
+        let mut writer = PackedIndexWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer.insert(20u16, "second").unwrap();
+        writer.insert(10u16, "first").unwrap();
+        assert!(writer.insert(10u16, "again").is_err());
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn lookup_by_key() {
+        let mut index = PackedIndex::<u16, String, _>::open(file()).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.keys().collect::<Vec<_>>(), [&10, &20]);
+        assert_eq!(index.get(&20).unwrap().unwrap(), "second");
+        assert_eq!(index.get(&10).unwrap().unwrap(), "first");
+        assert!(index.get(&30).unwrap().is_none());
+    }
+
+    #[test]
+    fn values_are_sequential() {
+        let bytes = file().into_inner();
+        assert_eq!(bytes[..4], MAGIC);
+        assert_eq!(bytes[4..14], *b"\x00\x00\x00\x06second");
+    }
+
+    #[test]
+    fn wrong_magic() {
+        let mut bytes = file().into_inner();
+        bytes[0] = 0x00;
+        let result = PackedIndex::<u16, String, _>::open(Cursor::new(bytes));
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn insert_after_failed_value() {
+        enum Entry {
+            Valid(u8),
+            Faulty,
+        }
+
+        impl Pack for Entry {
+            fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+                match self {
+                    Entry::Valid(value) => value.pack_into(writer),
+                    Entry::Faulty => {
+                        writer.write_all(&[1, 2, 3])?;
+                        Err(pack::Error::InvalidValue("faulty".into()))
+                    }
+                }
+            }
+        }
+
+        let mut writer = PackedIndexWriter::new(Cursor::new(Vec::new())).unwrap();
+        assert!(writer.insert(1u16, &Entry::Faulty).is_err());
+        writer.insert(2u16, &Entry::Valid(7)).unwrap();
+        let mut index = PackedIndex::<u16, u8, _>::open(writer.finish().unwrap()).unwrap();
+        assert_eq!(index.get(&2).unwrap(), Some(7));
+    }
+}
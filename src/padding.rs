@@ -0,0 +1,170 @@
+//! Alignment and padding of packed values
+//!
+//! Structs dumped from the memory of C programs contain padding bytes
+//! between and after their fields. The wrappers in this module insert such
+//! padding as zero bytes when packing and verify it when unpacking, so a
+//! struct can mirror the layout of its C counterpart field by field:
+//!
+//! ```
+//! use serial_container::pack::Pack;
+//! use serial_container::padding::{Aligned, Padded};
+//!
+//! // struct { uint8_t kind; uint32_t value; } with natural alignment
+//! let mut bytes = Aligned::<_, 4>(7u8).pack_to_vec().unwrap();
+//! 9u32.pack_into(&mut bytes).unwrap();
+//! assert_eq!(bytes, [7, 0, 0, 0, 0, 0, 0, 9]);
+//!
+//! // struct { uint16_t id; uint8_t reserved[6]; }
+//! let header = Padded::<_, 8>(3u16).pack_to_vec().unwrap();
+//! assert_eq!(header, [0, 3, 0, 0, 0, 0, 0, 0]);
+//! ```
+//!
+//! Padding is counted from the start of the wrapped value, so a field has to
+//! start at an aligned offset for `Aligned` to match the C layout
+
+use crate::pack::{self, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::io;
+
+/// Wrapper padding the packed value with zero bytes up to a multiple of `A`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Aligned<T, const A: usize>(pub T);
+
+/// Wrapper padding the packed value with zero bytes up to exactly `N` bytes
+///
+/// Packing fails with `pack::Error::InvalidValue` if the value takes more
+/// than `N` bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Padded<T, const N: usize>(pub T);
+
+impl<T: Pack, const A: usize> Pack for Aligned<T, A> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = self.0.pack_into(writer)?;
+        Ok(written + write_padding(writer, padding(written, A))?)
+    }
+}
+
+impl<T: Unpack, const A: usize> Unpack for Aligned<T, A> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut counting = Counting {
+            inner: reader,
+            count: 0,
+        };
+        let value = T::unpack_from(&mut counting)?;
+        let len = padding(counting.count, A);
+        read_padding(reader, len)?;
+        Ok(Aligned(value))
+    }
+}
+
+impl<T: Pack, const N: usize> Pack for Padded<T, N> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let bytes = self.0.pack_to_vec()?;
+
+        if bytes.len() > N {
+            return Err(pack::Error::InvalidValue(format!(
+                "packed value takes {} bytes, but is padded to {}",
+                bytes.len(),
+                N
+            )));
+        }
+
+        writer.write_all(&bytes)?;
+        Ok(bytes.len() + write_padding(writer, N - bytes.len())?)
+    }
+}
+
+impl<T: Unpack, const N: usize> Unpack for Padded<T, N> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let bytes = read_bytes(reader, N)?;
+        let mut remaining = bytes.as_slice();
+        let value = T::unpack_from(&mut remaining)?;
+        check_padding(remaining)?;
+        Ok(Padded(value))
+    }
+}
+
+fn padding(len: usize, alignment: usize) -> usize {
+    match alignment {
+        0 => 0,
+        _ => (alignment - len % alignment) % alignment,
+    }
+}
+
+fn write_padding(writer: &mut impl io::Write, len: usize) -> pack::Result<usize> {
+    writer.write_all(&vec![0; len])?;
+    Ok(len)
+}
+
+fn read_padding(reader: &mut impl io::Read, len: usize) -> Result<()> {
+    check_padding(&read_bytes(reader, len)?)
+}
+
+fn check_padding(bytes: &[u8]) -> Result<()> {
+    match bytes.iter().position(|byte| *byte != 0) {
+        Some(index) => Err(Error::InvalidValue(format!(
+            "padding byte {} is {:#04x} instead of zero",
+            index, bytes[index]
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Reader counting the bytes read through it
+struct Counting<'a, R> {
+    inner: &'a mut R,
+    count: usize,
+}
+
+impl<R: io::Read> io::Read for Counting<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_aligned() {
+        assert_eq!(Aligned::<_, 4>(1u8).pack_to_vec().unwrap(), [1, 0, 0, 0]);
+        assert_eq!(Aligned::<_, 4>(1u32).pack_to_vec().unwrap(), [0, 0, 0, 1]);
+        assert_eq!(
+            Aligned::<_, 4>("ab").pack_to_vec().unwrap(),
+            [0, 0, 0, 2, b'a', b'b', 0, 0]
+        );
+    }
+
+    #[test]
+    fn unpack_aligned() {
+        let bytes = [0, 0, 0, 2, b'a', b'b', 0, 0];
+        let value = Aligned::<String, 4>::unpack_exact(&bytes).unwrap();
+        assert_eq!(value.0, "ab");
+
+        let bytes = [0x01, 0x02, 0x00, 0x01];
+        let result = Aligned::<u16, 4>::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn pack_padded() {
+        let bytes = Padded::<_, 6>(0x0102u16).pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x01, 0x02, 0, 0, 0, 0]);
+        assert_eq!(Padded::<u16, 6>::unpack_exact(&bytes).unwrap().0, 0x0102);
+
+        let result = Padded::<_, 1>(0x0102u16).pack_to_vec();
+        assert!(matches!(result, Err(pack::Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn unpack_padded_mismatch() {
+        let result = Padded::<u8, 3>::unpack_exact(&[1, 0, 2]);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+
+        let result = Padded::<u8, 3>::unpack_exact(&[1, 0]);
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::pack::{self, checked_len, write_bytes, Pack};
+use crate::unpack::{initial_capacity, Error, Result, Unpack};
+use bytemuck::Pod;
+use std::io;
+
+/// Describes a plain-old-data struct that can be packed with a single copy
+///
+/// The packed form of such a struct is the in-memory representation of its
+/// fields in declaration order, with every field in big endian byte order.
+/// Because `Pod` guarantees the absence of padding, this is the same wire
+/// format as packing the fields one after another
+///
+/// Implementors have to swap the byte order of every multi-byte field,
+/// e.g. by calling `swap_bytes` on it. This is only done on little endian
+/// targets
+pub trait PodPack: Pod {
+    /// Reverses the byte order of every field of this struct
+    fn swap_bytes(&mut self);
+}
+
+/// Wrapper that packs a vector of plain-old-data structs in bulk
+///
+/// The wire format is the same as for a `Vec` of the structs packed field
+/// by field
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PodVec<T>(pub Vec<T>);
+
+impl<T: PodPack> Pack for PodVec<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = checked_len(self.0.len())?.pack_into(writer)?;
+
+        if cfg!(target_endian = "big") {
+            return Ok(written + write_bytes(writer, bytemuck::cast_slice(&self.0))?);
+        }
+
+        // the byte order can only be swapped on a copy of the values, which
+        // is made chunk by chunk in a scratch buffer of bounded size
+        let chunk_len = initial_capacity::<T>(self.0.len()).max(1);
+        let mut scratch = Vec::with_capacity(chunk_len);
+        let mut written = written;
+
+        for chunk in self.0.chunks(chunk_len) {
+            scratch.clear();
+            scratch.extend_from_slice(chunk);
+            scratch.iter_mut().for_each(PodPack::swap_bytes);
+            written += write_bytes(writer, bytemuck::cast_slice(&scratch))?;
+        }
+
+        Ok(written)
+    }
+}
+
+impl<T: PodPack> Unpack for PodVec<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        let step = initial_capacity::<T>(len).max(1);
+        let mut values = Vec::new();
+
+        // grow in bounded steps, so a forged length cannot allocate more
+        // than the bytesource actually provides
+        while values.len() < len {
+            let start = values.len();
+            values.resize(len.min(start + step), T::zeroed());
+            reader
+                .read_exact(bytemuck::cast_slice_mut(&mut values[start..]))
+                .map_err(Error::IO)?;
+        }
+
+        if cfg!(target_endian = "little") {
+            values.iter_mut().for_each(PodPack::swap_bytes);
+        }
+
+        Ok(PodVec(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Sample {
+        left: i16,
+        right: u16,
+        time: f32,
+    }
+
+    impl PodPack for Sample {
+        fn swap_bytes(&mut self) {
+            self.left = self.left.swap_bytes();
+            self.right = self.right.swap_bytes();
+            self.time = f32::from_bits(self.time.to_bits().swap_bytes());
+        }
+    }
+
+    impl Pack for Sample {
+        fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+            Ok(self.left.pack_into(writer)?
+                + self.right.pack_into(writer)?
+                + self.time.pack_into(writer)?)
+        }
+    }
+
+    fn samples() -> Vec<Sample> {
+        vec![
+            Sample {
+                left: -1,
+                right: 2,
+                time: 0.5,
+            },
+            Sample {
+                left: 3,
+                right: 0x0405,
+                time: -1.0,
+            },
+        ]
+    }
+
+    #[test]
+    fn pack_pod_vec_like_fields() {
+        let bytes = PodVec(samples()).pack_to_vec().unwrap();
+        assert_eq!(bytes, samples().as_slice().pack_to_vec().unwrap());
+    }
+
+    #[test]
+    fn pod_vec_round_trip() {
+        let bytes = PodVec(samples()).pack_to_vec().unwrap();
+        let value = PodVec::<Sample>::unpack_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(value.0, samples());
+    }
+
+    #[test]
+    fn unpack_pod_vec_truncated() {
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0x00, 0x01];
+        let result = PodVec::<Sample>::unpack_from(&mut bytes.as_ref());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn pod_vec_across_chunks() {
+        let values: Vec<_> = (0..20_000)
+            .map(|index| Sample {
+                left: index as i16,
+                right: index as u16 ^ 0x5A5A,
+                time: index as f32,
+            })
+            .collect();
+        let bytes = PodVec(values.clone()).pack_to_vec().unwrap();
+        assert_eq!(bytes, values.as_slice().pack_to_vec().unwrap());
+
+        let result = PodVec::<Sample>::unpack_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(result.0, values);
+    }
+
+    #[test]
+    fn unpack_pod_vec_forged_length() {
+        let bytes = [0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x01];
+        let result = PodVec::<Sample>::unpack_from(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::{self, Debug, Formatter};
+use std::mem;
+use std::ops::Deref;
+use std::sync::Mutex;
+
+/// Default limit for the number of idle buffers kept by a [`PackPool`]
+pub const DEFAULT_MAX_IDLE_BUFFERS: usize = 64;
+
+/// Pool of reusable byte buffers for packing values
+///
+/// Buffers handed out by the pool return to it when they are dropped, so
+/// packing many small values in a row reuses the same allocations instead
+/// of allocating a new `Vec` per value. The pool can be shared between
+/// threads
+pub struct PackPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    max_idle_buffers: usize,
+}
+
+impl PackPool {
+    /// Creates a pool keeping up to [`DEFAULT_MAX_IDLE_BUFFERS`] idle buffers
+    pub fn new() -> Self {
+        Self::with_max_idle_buffers(DEFAULT_MAX_IDLE_BUFFERS)
+    }
+
+    /// Creates a pool keeping up to the given number of idle buffers
+    ///
+    /// Buffers returned while the pool is full are deallocated
+    pub fn with_max_idle_buffers(max_idle_buffers: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::new()),
+            max_idle_buffers,
+        }
+    }
+
+    /// Returns an empty buffer, reusing an idle one if available
+    pub fn take(&self) -> PooledBuffer<'_> {
+        let buffer = self.lock().pop().unwrap_or_default();
+        PooledBuffer { buffer, pool: self }
+    }
+
+    /// Returns the number of idle buffers in the pool
+    pub fn idle_buffers(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Vec<u8>>> {
+        self.buffers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn give_back(&self, mut buffer: Vec<u8>) {
+        let mut buffers = self.lock();
+
+        if buffers.len() < self.max_idle_buffers {
+            buffer.clear();
+            buffers.push(buffer);
+        }
+    }
+}
+
+impl Default for PackPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for PackPool {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_struct("PackPool")
+            .field("idle_buffers", &self.idle_buffers())
+            .field("max_idle_buffers", &self.max_idle_buffers)
+            .finish()
+    }
+}
+
+/// Byte buffer borrowed from a [`PackPool`]
+///
+/// The buffer returns to its pool when it is dropped
+pub struct PooledBuffer<'a> {
+    buffer: Vec<u8>,
+    pool: &'a PackPool,
+}
+
+impl PooledBuffer<'_> {
+    /// Takes the bytes out of the pool, so the buffer does not return to it
+    pub fn into_vec(mut self) -> Vec<u8> {
+        mem::take(&mut self.buffer)
+    }
+
+    pub(crate) fn as_mut_vec(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl AsRef<[u8]> for PooledBuffer<'_> {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl Debug for PooledBuffer<'_> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        self.buffer.fmt(destination)
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        if self.buffer.capacity() > 0 {
+            self.pool.give_back(mem::take(&mut self.buffer));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::Pack;
+
+    #[test]
+    fn pack_to_pooled() {
+        let pool = PackPool::new();
+        let buffer = 2u16.pack_to_pooled(&pool).unwrap();
+        assert_eq!(*buffer, [0x00, 0x02]);
+        assert_eq!(pool.idle_buffers(), 0);
+        drop(buffer);
+        assert_eq!(pool.idle_buffers(), 1);
+    }
+
+    #[test]
+    fn pooled_buffers_are_reused() {
+        let pool = PackPool::new();
+        let pointer = "abc".pack_to_pooled(&pool).unwrap().as_ptr();
+        let buffer = 1u8.pack_to_pooled(&pool).unwrap();
+        assert_eq!(*buffer, [0x01]);
+        assert_eq!(buffer.as_ptr(), pointer);
+    }
+
+    #[test]
+    fn pool_limits_idle_buffers() {
+        let pool = PackPool::with_max_idle_buffers(1);
+        let first = 1u8.pack_to_pooled(&pool).unwrap();
+        let second = 2u8.pack_to_pooled(&pool).unwrap();
+        drop(first);
+        drop(second);
+        assert_eq!(pool.idle_buffers(), 1);
+    }
+
+    #[test]
+    fn pooled_buffer_into_vec() {
+        let pool = PackPool::new();
+        let bytes = 1u8.pack_to_pooled(&pool).unwrap().into_vec();
+        assert_eq!(bytes, [0x01]);
+        assert_eq!(pool.idle_buffers(), 0);
+    }
+}
@@ -0,0 +1,188 @@
+//! Projection of selected fields of a packed record
+//!
+//! Scans touching a few fields of wide records do not need to decode the
+//! whole record. The functions in this module walk the schema of the record
+//! type, decode only the requested fields and skip all others with
+//! [`skip_with_schema`], leaving the bytesource behind the record
+//!
+//! ```
+//! use serial_container::pack::Pack;
+//! use serial_container::project::unpack_field;
+//! use serial_container::schema::{Describe, Schema};
+//!
+//! struct Trade;
+//!
+//! impl Describe for Trade {
+//!     fn schema() -> Schema {
+//!         Schema::structure("Trade")
+//!             .field("symbol", Schema::String)
+//!             .field("notes", Schema::sequence(Schema::String))
+//!             .field("price", Schema::F64)
+//!     }
+//! }
+//!
+//! let mut bytes = "ACME".pack_to_vec().unwrap();
+//! ["a", "b"].pack_into(&mut bytes).unwrap();
+//! 12.5f64.pack_into(&mut bytes).unwrap();
+//!
+//! let price: f64 = unpack_field::<Trade, _>(&mut bytes.as_slice(), "price").unwrap();
+//! assert_eq!(price, 12.5);
+//! ```
+
+use crate::schema::{Describe, Schema};
+use crate::skip::{skip_with_schema, Reading};
+use crate::unpack::{Error, Result, Unpack};
+use crate::value::Value;
+use std::io;
+
+/// Unpacks a single field of a record of type `T` and skips all others
+///
+/// Fails with `Error::InvalidValue` if `T` is not described as a struct,
+/// has no field of the given name or the schema of the field is not
+/// compatible with the schema of `F`. Nothing is read in these cases
+pub fn unpack_field<T: Describe + ?Sized, F: Unpack + Describe>(
+    reader: &mut impl io::Read,
+    name: &str,
+) -> Result<F> {
+    let schema = T::schema();
+    let fields = fields(&schema)?;
+    let index = position(fields, name)?;
+
+    if !F::schema().compatible_with(&fields[index].1) {
+        return Err(Error::InvalidValue(format!(
+            "field {} can not be unpacked as {}",
+            name,
+            std::any::type_name::<F>()
+        )));
+    }
+
+    let mut value = None;
+
+    for (current, (_, schema)) in fields.iter().enumerate() {
+        if current == index {
+            value = Some(F::unpack_from(reader)?);
+        } else {
+            skip_with_schema(schema, &mut Reading(reader))?;
+        }
+    }
+
+    Ok(value.expect("field was found in the schema"))
+}
+
+/// Decodes the given fields of a record of type `T` and skips all others
+///
+/// Returns the values in the order of the requested names. Fails with
+/// `Error::InvalidValue` if `T` is not described as a struct or has no
+/// field of one of the names
+pub fn unpack_fields<T: Describe + ?Sized>(
+    reader: &mut impl io::Read,
+    names: &[&str],
+) -> Result<Vec<Value>> {
+    let schema = T::schema();
+    let fields = fields(&schema)?;
+    let indices = names
+        .iter()
+        .map(|name| position(fields, name))
+        .collect::<Result<Vec<_>>>()?;
+    let mut values = vec![Value::Null; names.len()];
+
+    for (current, (_, schema)) in fields.iter().enumerate() {
+        if !indices.contains(&current) {
+            skip_with_schema(schema, &mut Reading(reader))?;
+            continue;
+        }
+
+        let value = Value::unpack_with_schema(schema, reader)?;
+
+        for (requested, index) in indices.iter().enumerate() {
+            if *index == current {
+                values[requested] = value.clone();
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+fn fields(schema: &Schema) -> Result<&[(String, Schema)]> {
+    match schema {
+        Schema::Struct { fields, .. } => Ok(fields),
+        _ => Err(Error::InvalidValue(String::from(
+            "only fields of structs can be projected",
+        ))),
+    }
+}
+
+fn position(fields: &[(String, Schema)], name: &str) -> Result<usize> {
+    fields
+        .iter()
+        .position(|(field, _)| field == name)
+        .ok_or_else(|| Error::InvalidValue(format!("unknown field {}", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::Pack;
+
+    struct Row;
+
+    impl Describe for Row {
+        fn schema() -> Schema {
+            Schema::structure("Row")
+                .field("id", Schema::U32)
+                .field("tags", Schema::map(Schema::String, Schema::U8))
+                .field("name", Schema::String)
+                .field("score", Schema::I16)
+        }
+    }
+
+    fn row() -> Vec<u8> {
+        let mut tags = std::collections::BTreeMap::new();
+        tags.insert("a", 1u8);
+        let mut bytes = 7u32.pack_to_vec().unwrap();
+        tags.pack_into(&mut bytes).unwrap();
+        "bob".pack_into(&mut bytes).unwrap();
+        (-3i16).pack_into(&mut bytes).unwrap();
+        0xFFu8.pack_into(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn project_single_field() {
+        let bytes = row();
+        let mut reader = bytes.as_slice();
+        let name: String = unpack_field::<Row, _>(&mut reader, "name").unwrap();
+        assert_eq!(name, "bob");
+        assert_eq!(reader, [0xFF]);
+    }
+
+    #[test]
+    fn project_multiple_fields() {
+        let bytes = row();
+        let mut reader = bytes.as_slice();
+        let values = unpack_fields::<Row>(&mut reader, &["score", "id"]).unwrap();
+        assert_eq!(values, [Value::Int(-3), Value::Int(7)]);
+        assert_eq!(reader, [0xFF]);
+    }
+
+    #[test]
+    fn project_unknown_field() {
+        let result = unpack_field::<Row, u8>(&mut row().as_slice(), "missing");
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+        let result = unpack_field::<u32, u8>(&mut row().as_slice(), "id");
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn project_mismatching_type() {
+        let bytes = row();
+        let mut reader = bytes.as_slice();
+        let result = unpack_field::<Row, u64>(&mut reader, "id");
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+        assert_eq!(reader.len(), bytes.len());
+
+        let result = unpack_field::<Row, Vec<u8>>(&mut reader, "name");
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,761 @@
+//! Protobuf-compatible encoding of simple messages
+//!
+//! Messages are described by a struct [`Schema`]: by default the field at
+//! position `i` gets the field number `i + 1`. Other numbers, e.g. those of
+//! an existing `.proto` file, are assigned per struct and field name with
+//! [`FieldNumbers`]. Scalar types map to protobuf types as follows:
+//!
+//! - `bool`, `u8`, `u16`, `u32` and `u64` as `bool`, `uint32` or `uint64`
+//! - `i16`, `i32` and `i64` as zigzag encoded `sint32` or `sint64`
+//! - `f32` and `f64` as `float` and `double`
+//! - `String` and byte sequences as `string` and `bytes`
+//! - sequences as packed (scalars) or unpacked `repeated` fields
+//! - maps as `map` fields and nested structs as embedded messages
+//!
+//! Like in proto3, scalar fields with default values are omitted and
+//! missing fields are read as default values. 128 bit integers, sequences
+//! of sequences or maps and custom types have no protobuf counterpart and
+//! are rejected
+//!
+//! # Example
+//!
+//! ```
+//! use serial_container::protobuf::{decode_value, encode_value};
+//! use serial_container::schema::Schema;
+//! use serial_container::value::Value;
+//!
+//! let schema = Schema::structure("Test1").field("a", Schema::I32);
+//! let value = Value::Map(vec![(Value::Str("a".into()), Value::Int(-2))]);
+//! let bytes = encode_value(&schema, &value).unwrap();
+//! assert_eq!(bytes, [0x08, 0x03]);
+//!
+//! assert_eq!(decode_value(&schema, &bytes).unwrap(), value);
+//! ```
+//!
+//! Explicit field numbers:
+//!
+//! ```
+//! use serial_container::protobuf::{encode_value_with, FieldNumbers};
+//! use serial_container::schema::Schema;
+//! use serial_container::value::Value;
+//!
+//! let schema = Schema::structure("Test1").field("a", Schema::U32);
+//! let numbers = FieldNumbers::new().field("Test1", "a", 3);
+//! let value = Value::Map(vec![(Value::Str("a".into()), Value::Int(1))]);
+//! let bytes = encode_value_with(&schema, &value, &numbers).unwrap();
+//! assert_eq!(bytes, [0x18, 0x01]);
+//! ```
+
+use crate::pack::Pack;
+use crate::schema::{Describe, Schema};
+use crate::unpack::{Error, Result, Unpack};
+use crate::value::Value;
+use std::collections::HashMap;
+use std::io;
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LENGTH_DELIMITED: u8 = 2;
+const FIXED32: u8 = 5;
+
+/// Largest field number protobuf allows
+const MAX_FIELD_NUMBER: u32 = (1 << 29) - 1;
+
+/// Field numbers reserved for the protobuf implementation
+const RESERVED_FIELD_NUMBERS: std::ops::RangeInclusive<u32> = 19000..=19999;
+
+/// Field numbers that differ from the position of the fields in a struct
+///
+/// Numbers are assigned per struct name and field name, so they apply to
+/// every occurrence of a struct, including nested messages. Fields without
+/// an assigned number keep the number `i + 1` of their position `i`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldNumbers {
+    numbers: HashMap<(String, String), u32>,
+}
+
+impl FieldNumbers {
+    /// Creates a mapping without any assigned numbers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assigns a field number to a field of the struct with the given name
+    pub fn field(
+        mut self,
+        message: impl Into<String>,
+        field: impl Into<String>,
+        number: u32,
+    ) -> Self {
+        self.numbers.insert((message.into(), field.into()), number);
+        self
+    }
+
+    /// Returns the field numbers of all fields of a struct
+    ///
+    /// Fails with `Error::InvalidValue` for numbers protobuf does not allow
+    /// and for numbers used by more than one field
+    fn of(&self, message: &str, fields: &[(String, Schema)]) -> Result<Vec<u32>> {
+        let mut numbers = Vec::with_capacity(fields.len());
+
+        for (index, (name, _)) in fields.iter().enumerate() {
+            let number = match self.numbers.get(&(message.to_owned(), name.clone())) {
+                Some(number) => *number,
+                None => u32::try_from(index + 1).unwrap_or(u32::MAX),
+            };
+
+            if number == 0 || number > MAX_FIELD_NUMBER || RESERVED_FIELD_NUMBERS.contains(&number)
+            {
+                return Err(Error::InvalidValue(format!(
+                    "{} is not a valid field number for {}.{}",
+                    number, message, name
+                )));
+            }
+
+            if numbers.contains(&number) {
+                return Err(Error::InvalidValue(format!(
+                    "field number {} is used twice in {}",
+                    number, message
+                )));
+            }
+
+            numbers.push(number);
+        }
+
+        Ok(numbers)
+    }
+}
+
+/// Converts a packable struct into a protobuf message
+pub fn to_protobuf<T: Pack + Describe + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    to_protobuf_with(value, &FieldNumbers::default())
+}
+
+/// Converts a packable struct into a protobuf message with the given field
+/// numbers
+pub fn to_protobuf_with<T: Pack + Describe + ?Sized>(
+    value: &T,
+    numbers: &FieldNumbers,
+) -> Result<Vec<u8>> {
+    let schema = T::schema();
+    let packed = value.pack_to_vec()?;
+    let value = Value::unpack_with_schema(&schema, &mut packed.as_slice())?;
+    encode_value_with(&schema, &value, numbers)
+}
+
+/// Converts a protobuf message into an unpackable struct
+pub fn from_protobuf<T: Unpack + Describe>(bytes: &[u8]) -> Result<T> {
+    from_protobuf_with(bytes, &FieldNumbers::default())
+}
+
+/// Converts a protobuf message with the given field numbers into an
+/// unpackable struct
+pub fn from_protobuf_with<T: Unpack + Describe>(bytes: &[u8], numbers: &FieldNumbers) -> Result<T> {
+    let schema = T::schema();
+    let value = decode_value_with(&schema, bytes, numbers)?;
+    let mut packed = Vec::new();
+    value.pack_with_schema(&schema, &mut packed)?;
+    T::unpack_exact(&packed)
+}
+
+/// Encodes a struct value as a protobuf message described by the schema
+pub fn encode_value(schema: &Schema, value: &Value) -> Result<Vec<u8>> {
+    encode_value_with(schema, value, &FieldNumbers::default())
+}
+
+/// Encodes a struct value as a protobuf message described by the schema and
+/// the given field numbers
+pub fn encode_value_with(
+    schema: &Schema,
+    value: &Value,
+    numbers: &FieldNumbers,
+) -> Result<Vec<u8>> {
+    let (name, fields) = message(schema)?;
+    let mut bytes = Vec::new();
+    write_message(
+        fields,
+        &numbers.of(name, fields)?,
+        numbers,
+        value,
+        &mut bytes,
+    )?;
+    Ok(bytes)
+}
+
+/// Decodes a protobuf message described by the schema into a struct value
+///
+/// Unknown fields are skipped
+pub fn decode_value(schema: &Schema, bytes: &[u8]) -> Result<Value> {
+    decode_value_with(schema, bytes, &FieldNumbers::default())
+}
+
+/// Decodes a protobuf message described by the schema and the given field
+/// numbers into a struct value
+///
+/// Unknown fields are skipped
+pub fn decode_value_with(schema: &Schema, bytes: &[u8], numbers: &FieldNumbers) -> Result<Value> {
+    let (name, fields) = message(schema)?;
+    read_message(fields, &numbers.of(name, fields)?, numbers, bytes)
+}
+
+/// Writes an unsigned integer as varint and returns the number of bytes
+/// written
+pub fn write_varint(mut value: u64, writer: &mut impl io::Write) -> io::Result<usize> {
+    let mut written = 0;
+
+    while value >= 0x80 {
+        written += (value as u8 | 0x80).pack_into(writer)?;
+        value >>= 7;
+    }
+
+    Ok(written + (value as u8).pack_into(writer)?)
+}
+
+/// Reads an unsigned integer encoded as varint
+pub fn read_varint(reader: &mut impl io::Read) -> Result<u64> {
+    let mut value = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let byte = u8::unpack_from(reader)?;
+        value |= u64::from(byte & 0x7F) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(Error::InvalidValue("varint is longer than 10 bytes".into()))
+}
+
+/// Maps a signed integer to an unsigned integer with small absolute values
+/// staying small
+pub fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Reverses [`zigzag`]
+pub fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn message(schema: &Schema) -> Result<(&str, &[(String, Schema)])> {
+    match schema {
+        Schema::Struct { name, fields } => Ok((name, fields)),
+        schema => Err(Error::InvalidValue(format!(
+            "{:?} is not a protobuf message",
+            schema
+        ))),
+    }
+}
+
+fn unsupported(schema: &Schema) -> Error {
+    Error::InvalidValue(format!("{:?} has no protobuf counterpart", schema))
+}
+
+fn mismatch(schema: &Schema, value: &Value) -> Error {
+    Error::InvalidValue(format!("{:?} does not match schema {:?}", value, schema))
+}
+
+fn scalar_wire_type(schema: &Schema) -> Option<u8> {
+    match schema {
+        Schema::Bool
+        | Schema::U8
+        | Schema::U16
+        | Schema::U32
+        | Schema::U64
+        | Schema::I16
+        | Schema::I32
+        | Schema::I64 => Some(VARINT),
+        Schema::F32 => Some(FIXED32),
+        Schema::F64 => Some(FIXED64),
+        _ => None,
+    }
+}
+
+fn write_key(number: u32, wire_type: u8, output: &mut Vec<u8>) -> Result<()> {
+    write_varint((u64::from(number) << 3) | u64::from(wire_type), output)?;
+    Ok(())
+}
+
+fn write_delimited(number: u32, payload: &[u8], output: &mut Vec<u8>) -> Result<()> {
+    write_key(number, LENGTH_DELIMITED, output)?;
+    write_varint(payload.len() as u64, output)?;
+    output.extend_from_slice(payload);
+    Ok(())
+}
+
+fn write_message(
+    fields: &[(String, Schema)],
+    field_numbers: &[u32],
+    numbers: &FieldNumbers,
+    value: &Value,
+    output: &mut Vec<u8>,
+) -> Result<()> {
+    for ((name, schema), number) in fields.iter().zip(field_numbers) {
+        let field = value
+            .get(name)
+            .ok_or_else(|| Error::InvalidValue(format!("missing field {}", name)))?;
+        write_field(*number, schema, numbers, field, output)?;
+    }
+
+    Ok(())
+}
+
+fn write_field(
+    number: u32,
+    schema: &Schema,
+    numbers: &FieldNumbers,
+    value: &Value,
+    output: &mut Vec<u8>,
+) -> Result<()> {
+    match (schema, value) {
+        (Schema::String, Value::Str(value)) if value.is_empty() => {}
+        (Schema::String, Value::Str(value)) => write_delimited(number, value.as_bytes(), output)?,
+        (Schema::Sequence(_), Value::Bytes(bytes)) if bytes.is_empty() => {}
+        (Schema::Sequence(_), Value::Bytes(bytes)) => write_delimited(number, bytes, output)?,
+        (Schema::Sequence(_), Value::List(items)) if items.is_empty() => {}
+        (Schema::Sequence(element), Value::List(items)) if scalar_wire_type(element).is_some() => {
+            let mut payload = Vec::new();
+
+            for item in items {
+                write_scalar(element, item, &mut payload)?;
+            }
+
+            write_delimited(number, &payload, output)?;
+        }
+        (Schema::Sequence(element), Value::List(items)) => {
+            if let Schema::Sequence(_) | Schema::Map(_, _) = **element {
+                return Err(unsupported(schema));
+            }
+
+            for item in items {
+                match (&**element, item) {
+                    (Schema::String, Value::Str(value)) => {
+                        write_delimited(number, value.as_bytes(), output)?
+                    }
+                    _ => write_field(number, element, numbers, item, output)?,
+                }
+            }
+        }
+        (Schema::Map(key_schema, value_schema), Value::Map(entries)) => {
+            for (key, value) in entries {
+                let mut payload = Vec::new();
+                write_field(1, key_schema, numbers, key, &mut payload)?;
+                write_field(2, value_schema, numbers, value, &mut payload)?;
+                write_delimited(number, &payload, output)?;
+            }
+        }
+        (Schema::Struct { name, fields }, value) => {
+            let mut payload = Vec::new();
+            let field_numbers = numbers.of(name, fields)?;
+            write_message(fields, &field_numbers, numbers, value, &mut payload)?;
+            write_delimited(number, &payload, output)?;
+        }
+        (schema, value) => match scalar_wire_type(schema) {
+            Some(_) if is_default(value) => {}
+            Some(wire_type) => {
+                write_key(number, wire_type, output)?;
+                write_scalar(schema, value, output)?;
+            }
+            None => return Err(unsupported(schema)),
+        },
+    }
+
+    Ok(())
+}
+
+fn is_default(value: &Value) -> bool {
+    match value {
+        Value::Bool(value) => !value,
+        Value::Int(value) => *value == 0,
+        Value::Float(value) => *value == 0.0 && value.is_sign_positive(),
+        _ => false,
+    }
+}
+
+fn write_scalar(schema: &Schema, value: &Value, output: &mut Vec<u8>) -> Result<()> {
+    let int = |value: &Value| match value {
+        Value::Int(value) => Ok(*value),
+        value => Err(mismatch(schema, value)),
+    };
+
+    match (schema, value) {
+        (Schema::Bool, Value::Bool(value)) => {
+            write_varint(u64::from(*value), output)?;
+        }
+        (Schema::F32, Value::Float(value)) => {
+            output.extend_from_slice(&(*value as f32).to_le_bytes())
+        }
+        (Schema::F64, Value::Float(value)) => output.extend_from_slice(&value.to_le_bytes()),
+        (Schema::I16 | Schema::I32 | Schema::I64, value) => {
+            let value = i64::try_from(int(value)?).map_err(|_| mismatch(schema, value))?;
+            write_varint(zigzag(value), output)?;
+        }
+        (Schema::U8 | Schema::U16 | Schema::U32 | Schema::U64, value) => {
+            let value = u64::try_from(int(value)?).map_err(|_| mismatch(schema, value))?;
+            write_varint(value, output)?;
+        }
+        (schema, value) => return Err(mismatch(schema, value)),
+    }
+
+    Ok(())
+}
+
+fn read_message(
+    fields: &[(String, Schema)],
+    field_numbers: &[u32],
+    numbers: &FieldNumbers,
+    mut bytes: &[u8],
+) -> Result<Value> {
+    let mut values: Vec<Value> = fields
+        .iter()
+        .map(|(_, schema)| default_value(schema))
+        .collect::<Result<_>>()?;
+
+    while !bytes.is_empty() {
+        let key = read_varint(&mut bytes)?;
+        let wire_type = (key & 0x07) as u8;
+        let number = key >> 3;
+
+        match field_numbers
+            .iter()
+            .position(|candidate| u64::from(*candidate) == number)
+        {
+            Some(index) => {
+                let schema = &fields[index].1;
+                read_field(schema, numbers, wire_type, &mut bytes, &mut values[index])?;
+            }
+            None => skip_field(wire_type, &mut bytes)?,
+        }
+    }
+
+    Ok(Value::Map(
+        fields
+            .iter()
+            .zip(values)
+            .map(|((name, _), value)| (Value::Str(name.clone()), value))
+            .collect(),
+    ))
+}
+
+fn read_field(
+    schema: &Schema,
+    numbers: &FieldNumbers,
+    wire_type: u8,
+    bytes: &mut &[u8],
+    target: &mut Value,
+) -> Result<()> {
+    match (schema, target) {
+        (Schema::Sequence(element), Value::Bytes(target)) if **element == Schema::U8 => {
+            expect_wire_type(LENGTH_DELIMITED, wire_type)?;
+            *target = read_delimited(bytes)?.to_vec();
+        }
+        (Schema::Sequence(element), Value::List(items)) => match scalar_wire_type(element) {
+            Some(expected) if wire_type == LENGTH_DELIMITED => {
+                let mut payload = read_delimited(bytes)?;
+
+                while !payload.is_empty() {
+                    items.push(read_scalar(element, expected, &mut payload)?);
+                }
+            }
+            Some(expected) => {
+                expect_wire_type(expected, wire_type)?;
+                items.push(read_scalar(element, expected, bytes)?);
+            }
+            None if matches!(**element, Schema::Sequence(_) | Schema::Map(_, _)) => {
+                return Err(unsupported(schema))
+            }
+            None => {
+                let mut item = default_value(element)?;
+                read_field(element, numbers, wire_type, bytes, &mut item)?;
+                items.push(item);
+            }
+        },
+        (Schema::Map(key_schema, value_schema), Value::Map(entries)) => {
+            expect_wire_type(LENGTH_DELIMITED, wire_type)?;
+            let entry_fields = [
+                ("key".to_string(), (**key_schema).clone()),
+                ("value".to_string(), (**value_schema).clone()),
+            ];
+            let entry = read_message(&entry_fields, &[1, 2], numbers, read_delimited(bytes)?)?;
+
+            if let Value::Map(mut pair) = entry {
+                let (_, value) = pair.pop().expect("entry has a value");
+                let (_, key) = pair.pop().expect("entry has a key");
+                entries.push((key, value));
+            }
+        }
+        (Schema::Struct { name, fields }, target) => {
+            expect_wire_type(LENGTH_DELIMITED, wire_type)?;
+            let field_numbers = numbers.of(name, fields)?;
+            *target = read_message(fields, &field_numbers, numbers, read_delimited(bytes)?)?;
+        }
+        (Schema::String, target) => {
+            expect_wire_type(LENGTH_DELIMITED, wire_type)?;
+            let value = String::from_utf8(read_delimited(bytes)?.to_vec()).map_err(Error::UTF8)?;
+            *target = Value::Str(value);
+        }
+        (schema, target) => match scalar_wire_type(schema) {
+            Some(expected) => {
+                expect_wire_type(expected, wire_type)?;
+                *target = read_scalar(schema, expected, bytes)?;
+            }
+            None => return Err(unsupported(schema)),
+        },
+    }
+
+    Ok(())
+}
+
+fn read_scalar(schema: &Schema, wire_type: u8, bytes: &mut &[u8]) -> Result<Value> {
+    let value = match wire_type {
+        FIXED32 => Value::Float(f32::from_le_bytes(read_array(bytes)?).into()),
+        FIXED64 => Value::Float(f64::from_le_bytes(read_array(bytes)?)),
+        _ => {
+            let value = read_varint(bytes)?;
+            match schema {
+                Schema::Bool => Value::Bool(value != 0),
+                Schema::I16 | Schema::I32 | Schema::I64 => Value::Int(unzigzag(value).into()),
+                _ => Value::Int(value.into()),
+            }
+        }
+    };
+    Ok(value)
+}
+
+fn read_array<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N]> {
+    let mut array = [0; N];
+    io::Read::read_exact(bytes, &mut array)?;
+    Ok(array)
+}
+
+fn read_delimited<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let len = read_varint(bytes)? as usize;
+
+    if len > bytes.len() {
+        return Err(Error::IO(io::ErrorKind::UnexpectedEof.into()));
+    }
+
+    let (payload, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(payload)
+}
+
+fn skip_field(wire_type: u8, bytes: &mut &[u8]) -> Result<()> {
+    match wire_type {
+        VARINT => {
+            read_varint(bytes)?;
+        }
+        FIXED64 => {
+            read_array::<8>(bytes)?;
+        }
+        LENGTH_DELIMITED => {
+            read_delimited(bytes)?;
+        }
+        FIXED32 => {
+            read_array::<4>(bytes)?;
+        }
+        wire_type => {
+            return Err(Error::InvalidValue(format!(
+                "unsupported wire type {}",
+                wire_type
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+fn expect_wire_type(expected: u8, found: u8) -> Result<()> {
+    if expected != found {
+        return Err(Error::InvalidValue(format!(
+            "expected wire type {}, found {}",
+            expected, found
+        )));
+    }
+
+    Ok(())
+}
+
+fn default_value(schema: &Schema) -> Result<Value> {
+    let value = match schema {
+        Schema::Bool => Value::Bool(false),
+        Schema::U8
+        | Schema::U16
+        | Schema::U32
+        | Schema::U64
+        | Schema::I16
+        | Schema::I32
+        | Schema::I64 => Value::Int(0),
+        Schema::F32 | Schema::F64 => Value::Float(0.0),
+        Schema::String => Value::Str(String::new()),
+        Schema::Sequence(element) if **element == Schema::U8 => Value::Bytes(Vec::new()),
+        Schema::Sequence(_) => Value::List(Vec::new()),
+        Schema::Map(_, _) => Value::Map(Vec::new()),
+        Schema::Struct { fields, .. } => Value::Map(
+            fields
+                .iter()
+                .map(|(name, schema)| Ok((Value::Str(name.clone()), default_value(schema)?)))
+                .collect::<Result<_>>()?,
+        ),
+        schema => return Err(unsupported(schema)),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, value: Value) -> (Value, Value) {
+        (Value::Str(name.into()), value)
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        let mut bytes = Vec::new();
+        assert_eq!(write_varint(300, &mut bytes).unwrap(), 2);
+        assert_eq!(bytes, [0xAC, 0x02]);
+        assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), 300);
+    }
+
+    #[test]
+    fn zigzag_values() {
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+        assert_eq!(zigzag(i64::MIN), u64::MAX);
+        assert_eq!(unzigzag(u64::MAX), i64::MIN);
+    }
+
+    #[test]
+    fn encode_reference_messages() {
+        // examples of the protobuf encoding guide
+        let schema = Schema::structure("Test1").field("a", Schema::U32);
+        let value = Value::Map(vec![field("a", Value::Int(150))]);
+        assert_eq!(encode_value(&schema, &value).unwrap(), [0x08, 0x96, 0x01]);
+
+        let schema = Schema::structure("Test2").field("b", Schema::String);
+        let value = Value::Map(vec![field("b", Value::Str("testing".into()))]);
+        assert_eq!(
+            encode_value(&schema, &value).unwrap(),
+            [0x0A, 0x07, 0x74, 0x65, 0x73, 0x74, 0x69, 0x6E, 0x67]
+        );
+    }
+
+    #[test]
+    fn nested_round_trip() {
+        let point = Schema::structure("Point")
+            .field("x", Schema::F32)
+            .field("y", Schema::I64);
+        let schema = Schema::structure("Shape")
+            .field("name", Schema::String)
+            .field("points", Schema::sequence(point))
+            .field("ids", Schema::sequence(Schema::U16))
+            .field("labels", Schema::map(Schema::String, Schema::Bool));
+        let value = Value::Map(vec![
+            field("name", Value::Str("line".into())),
+            field(
+                "points",
+                Value::List(vec![
+                    Value::Map(vec![
+                        field("x", Value::Float(1.5)),
+                        field("y", Value::Int(-3)),
+                    ]),
+                    Value::Map(vec![
+                        field("x", Value::Float(0.0)),
+                        field("y", Value::Int(0)),
+                    ]),
+                ]),
+            ),
+            field("ids", Value::List(vec![Value::Int(1), Value::Int(500)])),
+            field(
+                "labels",
+                Value::Map(vec![(Value::Str("open".into()), Value::Bool(true))]),
+            ),
+        ]);
+        let bytes = encode_value(&schema, &value).unwrap();
+        assert_eq!(decode_value(&schema, &bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn skip_unknown_fields() {
+        let schema = Schema::structure("Test1").field("a", Schema::U32);
+        let bytes = [0x10, 0x01, 0x1A, 0x01, 0x00, 0x08, 0x02];
+        let value = decode_value(&schema, &bytes).unwrap();
+        assert_eq!(value, Value::Map(vec![field("a", Value::Int(2))]));
+    }
+
+    #[test]
+    fn reject_non_struct() {
+        let bytes = to_protobuf(&[1u8, 2]);
+        assert!(matches!(bytes, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn reject_128_bit_integers() {
+        let schema = Schema::structure("Big").field("a", Schema::U128);
+        let value = Value::Map(vec![field("a", Value::Int(1))]);
+        assert!(matches!(
+            encode_value(&schema, &value),
+            Err(Error::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn explicit_field_numbers() {
+        let point = Schema::structure("Point")
+            .field("x", Schema::U32)
+            .field("y", Schema::U32);
+        let schema = Schema::structure("Shape")
+            .field("name", Schema::String)
+            .field("origin", point);
+        let numbers = FieldNumbers::new()
+            .field("Shape", "origin", 16)
+            .field("Point", "x", 2)
+            .field("Point", "y", 1);
+        let value = Value::Map(vec![
+            field("name", Value::Str("a".into())),
+            field(
+                "origin",
+                Value::Map(vec![field("x", Value::Int(3)), field("y", Value::Int(4))]),
+            ),
+        ]);
+        let bytes = encode_value_with(&schema, &value, &numbers).unwrap();
+        assert_eq!(
+            bytes,
+            [0x0A, 0x01, 0x61, 0x82, 0x01, 0x04, 0x10, 0x03, 0x08, 0x04]
+        );
+        assert_eq!(decode_value_with(&schema, &bytes, &numbers).unwrap(), value);
+    }
+
+    #[test]
+    fn reject_invalid_field_numbers() {
+        let schema = Schema::structure("Test")
+            .field("a", Schema::U32)
+            .field("b", Schema::U32);
+        let value = Value::Map(vec![field("a", Value::Int(1)), field("b", Value::Int(2))]);
+
+        for numbers in [
+            FieldNumbers::new().field("Test", "b", 1),
+            FieldNumbers::new().field("Test", "a", 0),
+            FieldNumbers::new().field("Test", "a", 19000),
+            FieldNumbers::new().field("Test", "a", 1 << 29),
+        ] {
+            assert!(matches!(
+                encode_value_with(&schema, &value, &numbers),
+                Err(Error::InvalidValue(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn reject_mismatching_repeated_wire_type() {
+        let schema = Schema::structure("Test").field("a", Schema::sequence(Schema::F32));
+        let bytes = [0x08, 0x01];
+        assert!(matches!(
+            decode_value(&schema, &bytes),
+            Err(Error::InvalidValue(_))
+        ));
+    }
+}
@@ -0,0 +1,157 @@
+//! Envelope for publish/subscribe messaging
+//!
+//! A [`Published`] value is packed as its topic, the sequence number
+//! (`u64`), the timestamp in microseconds since the Unix epoch (`u64`) and
+//! the payload. Publishers number the messages of every topic
+//! consecutively, so a [`SequenceTracker`] on the consumer side can detect
+//! lost and duplicated messages
+
+use crate::pack::{self, Pack};
+use crate::unpack::{Result, Unpack};
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Message published on a topic
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Published<T> {
+    pub topic: String,
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub payload: T,
+}
+
+impl<T> Published<T> {
+    /// Creates a message stamped with the current time
+    pub fn new(topic: impl Into<String>, sequence: u64, payload: T) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_micros() as u64);
+
+        Self {
+            topic: topic.into(),
+            sequence,
+            timestamp,
+            payload,
+        }
+    }
+
+    /// Returns the time the message was published at
+    pub fn time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_micros(self.timestamp)
+    }
+}
+
+impl<T: Pack> Pack for Published<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = self.topic.pack_into(writer)?;
+        written += self.sequence.pack_into(writer)?;
+        written += self.timestamp.pack_into(writer)?;
+        written += self.payload.pack_into(writer)?;
+        Ok(written)
+    }
+}
+
+impl<T: Unpack> Unpack for Published<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        Ok(Published {
+            topic: String::unpack_from(reader)?,
+            sequence: u64::unpack_from(reader)?,
+            timestamp: u64::unpack_from(reader)?,
+            payload: T::unpack_from(reader)?,
+        })
+    }
+}
+
+/// Classification of a received sequence number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Delivery {
+    /// First message received on the topic
+    First,
+    /// Message directly following the previous one
+    Next,
+    /// Messages between the previous and this one were lost, `missing`
+    /// holds their number
+    Gap { missing: u64 },
+    /// Message with a sequence number that was already received or is
+    /// older than the last received one
+    Duplicate,
+}
+
+/// Tracker of the last received sequence number per topic
+#[derive(Debug, Clone, Default)]
+pub struct SequenceTracker {
+    last: HashMap<String, u64>,
+}
+
+impl SequenceTracker {
+    /// Creates a tracker that has not seen any topic yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the sequence number of a received message
+    ///
+    /// Duplicates do not change the recorded sequence number
+    pub fn observe(&mut self, topic: &str, sequence: u64) -> Delivery {
+        let last = match self.last.get_mut(topic) {
+            Some(last) => last,
+            None => {
+                self.last.insert(topic.to_owned(), sequence);
+                return Delivery::First;
+            }
+        };
+
+        if sequence <= *last {
+            return Delivery::Duplicate;
+        }
+
+        let missing = sequence - *last - 1;
+        *last = sequence;
+
+        match missing {
+            0 => Delivery::Next,
+            missing => Delivery::Gap { missing },
+        }
+    }
+
+    /// Records the sequence number of a received message
+    pub fn observe_message<T>(&mut self, message: &Published<T>) -> Delivery {
+        self.observe(&message.topic, message.sequence)
+    }
+
+    /// Returns the last sequence number received on a topic
+    pub fn last(&self, topic: &str) -> Option<u64> {
+        self.last.get(topic).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn published_round_trip() {
+        let message = Published::new("prices", 4, 25u32);
+        assert!(message.time() <= SystemTime::now());
+
+        let bytes = message.pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 10 + 8 + 8 + 4);
+        assert_eq!(Published::<u32>::unpack_exact(&bytes).unwrap(), message);
+    }
+
+    #[test]
+    fn detect_gaps_and_duplicates() {
+        let mut tracker = SequenceTracker::new();
+        assert_eq!(tracker.observe("a", 1), Delivery::First);
+        assert_eq!(tracker.observe("a", 2), Delivery::Next);
+        assert_eq!(tracker.observe("a", 2), Delivery::Duplicate);
+        assert_eq!(tracker.observe("a", 6), Delivery::Gap { missing: 3 });
+        assert_eq!(tracker.observe("a", 5), Delivery::Duplicate);
+        assert_eq!(tracker.observe("b", 9), Delivery::First);
+        assert_eq!(tracker.last("a"), Some(6));
+
+        let message = Published::new("b", 10, ());
+        assert_eq!(tracker.observe_message(&message), Delivery::Next);
+    }
+}
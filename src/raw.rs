@@ -0,0 +1,166 @@
+//! Raw byte payloads
+//!
+//! [`RawBytes`] is packed verbatim without a length prefix and consumes all
+//! remaining bytes when unpacked, so it can only be the last value of a
+//! frame. This allows framing a header followed by an opaque payload:
+//!
+//! ```
+//! use serial_container::pack::Pack;
+//! use serial_container::raw::RawBytes;
+//! use serial_container::unpack::Unpack;
+//!
+//! let mut frame = 7u16.pack_to_vec().unwrap();
+//! RawBytes(vec![1, 2, 3]).pack_into(&mut frame).unwrap();
+//! assert_eq!(frame, [0, 7, 1, 2, 3]);
+//!
+//! let mut reader = frame.as_slice();
+//! let header = u16::unpack_from(&mut reader).unwrap();
+//! let payload = RawBytes::unpack_from(&mut reader).unwrap();
+//! assert_eq!((header, payload.0), (7, vec![1, 2, 3]));
+//! ```
+//!
+//! [`Blob`] on the other hand is self-delimited by a `u32` length prefix.
+//! Its encoding is fixed independently of the encoding of `Vec<u8>`, which
+//! makes it suitable for attachments and embedded files
+
+use crate::pack::{self, checked_len, Pack};
+use crate::schema::{Describe, Schema};
+use crate::unpack::{read_bytes, Result, Unpack};
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+/// Bytes packed verbatim without a length prefix, unpacked from all
+/// remaining bytes of the bytesource
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RawBytes(pub Vec<u8>);
+
+impl Deref for RawBytes {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl DerefMut for RawBytes {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+}
+
+impl From<Vec<u8>> for RawBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        RawBytes(bytes)
+    }
+}
+
+impl From<RawBytes> for Vec<u8> {
+    fn from(bytes: RawBytes) -> Self {
+        bytes.0
+    }
+}
+
+impl Pack for RawBytes {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        writer.write_all(&self.0)?;
+        Ok(self.0.len())
+    }
+}
+
+impl Unpack for RawBytes {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(RawBytes(bytes))
+    }
+
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()> {
+        self.0.clear();
+        reader.read_to_end(&mut self.0)?;
+        Ok(())
+    }
+}
+
+/// Bytes packed with a `u32` length prefix
+///
+/// The encoding is guaranteed to stay the length in bytes followed by the
+/// bytes themselves
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Blob(pub Vec<u8>);
+
+impl Deref for Blob {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl DerefMut for Blob {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+}
+
+impl From<Vec<u8>> for Blob {
+    fn from(bytes: Vec<u8>) -> Self {
+        Blob(bytes)
+    }
+}
+
+impl From<Blob> for Vec<u8> {
+    fn from(bytes: Blob) -> Self {
+        bytes.0
+    }
+}
+
+impl Pack for Blob {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = checked_len(self.0.len())?.pack_into(writer)?;
+        writer.write_all(&self.0)?;
+        Ok(written + self.0.len())
+    }
+}
+
+impl Unpack for Blob {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        read_bytes(reader, len).map(Blob)
+    }
+}
+
+impl Describe for Blob {
+    fn schema() -> Schema {
+        Schema::sequence(Schema::U8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_round_trip() {
+        let bytes = RawBytes(vec![0xAB, 0xCD]).pack_to_vec().unwrap();
+        assert_eq!(bytes, [0xAB, 0xCD]);
+        assert_eq!(RawBytes::unpack_exact(&bytes).unwrap().0, [0xAB, 0xCD]);
+        assert!(RawBytes::unpack_exact(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unpack_into_replaces_bytes() {
+        let mut value = RawBytes(vec![9; 4]);
+        value.unpack_into(&mut [1u8, 2].as_slice()).unwrap();
+        assert_eq!(value.0, [1, 2]);
+    }
+
+    #[test]
+    fn blob_round_trip() {
+        let bytes = Blob(vec![0xAB, 0xCD]).pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x02, 0xAB, 0xCD]);
+
+        let mut reader = [0x00, 0x00, 0x00, 0x01, 0xAB, 0xCD].as_slice();
+        assert_eq!(Blob::unpack_from(&mut reader).unwrap().0, [0xAB]);
+        assert_eq!(reader, [0xCD]);
+    }
+}
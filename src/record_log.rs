@@ -0,0 +1,250 @@
+//! Append-only file of checksummed records
+//!
+//! Every record is packed as a frame of its length as `u32`, the packed
+//! value and a CRC-32 over the packed value. A crash while appending can
+//! only leave an incomplete or corrupted frame at the end of the file, which
+//! is cut off when the log is opened again
+
+use crate::checksum::{Checksum, Crc32};
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Append-only log of records of type `T` stored in a single file
+///
+/// ```
+/// use serial_container::record_log::RecordLog;
+///
+/// let path = std::env::temp_dir().join("serial_container_doc.log");
+/// # let _ = std::fs::remove_file(&path);
+/// let mut log = RecordLog::<u32>::open(&path).unwrap();
+/// log.append(&1).unwrap();
+/// log.append(&2).unwrap();
+///
+/// let records = log.iter().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(records, [1, 2]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct RecordLog<T> {
+    path: PathBuf,
+    file: File,
+    len: u64,
+    truncated: u64,
+    marker: PhantomData<fn(T) -> T>,
+}
+
+impl<T> RecordLog<T> {
+    /// Opens the log at the given path, creating it if it does not exist
+    ///
+    /// All frames are verified. The file is truncated at the first
+    /// incomplete or corrupted frame, see [`RecordLog::truncated`]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let size = file.metadata()?.len();
+        let len = valid_len(&mut BufReader::new(&file), size)?;
+
+        if len < size {
+            file.set_len(len)?;
+            file.sync_data()?;
+        }
+
+        Ok(Self {
+            path,
+            file,
+            len,
+            truncated: size - len,
+            marker: PhantomData,
+        })
+    }
+
+    /// Returns the number of bytes cut off when the log was opened
+    pub fn truncated(&self) -> u64 {
+        self.truncated
+    }
+
+    /// Returns the size of all complete frames in bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether the log contains no records
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Waits until all appended records have reached the disk
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+}
+
+impl<T: Pack> RecordLog<T> {
+    /// Appends a record and returns the offset of its frame
+    ///
+    /// The frame is written with a single call, but is only guaranteed to
+    /// survive a power loss after [`RecordLog::sync`]
+    pub fn append(&mut self, value: &T) -> pack::Result<u64> {
+        let payload = value.pack_to_vec()?;
+        let mut checksum = Crc32::default();
+        checksum.update(&payload);
+
+        let mut frame = Vec::with_capacity(payload.len() + 8);
+        checked_len(payload.len())?.pack_into(&mut frame)?;
+        frame.extend_from_slice(&payload);
+        checksum.finish().pack_into(&mut frame)?;
+
+        let offset = self.len;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&frame)?;
+        self.len += frame.len() as u64;
+        Ok(offset)
+    }
+}
+
+impl<T: Unpack> RecordLog<T> {
+    /// Returns an iterator over all records from the start of the log
+    ///
+    /// The file is opened a second time, so records appended afterwards
+    /// are not visited
+    pub fn iter(&self) -> Result<RecordIter<T>> {
+        let file = File::open(&self.path)?;
+
+        Ok(RecordIter {
+            reader: BufReader::new(file).take(self.len),
+            marker: PhantomData,
+        })
+    }
+}
+
+/// Iterator over the records of a [`RecordLog`]
+pub struct RecordIter<T> {
+    reader: io::Take<BufReader<File>>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Unpack> Iterator for RecordIter<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.reader.limit() == 0 {
+            return None;
+        }
+
+        let record = read_frame(&mut self.reader).and_then(|payload| T::unpack_exact(&payload));
+
+        if record.is_err() {
+            self.reader.set_limit(0);
+        }
+
+        Some(record)
+    }
+}
+
+/// Reads a frame and verifies its checksum
+fn read_frame(reader: &mut impl io::Read) -> Result<Vec<u8>> {
+    let len = u32::unpack_from(reader)? as usize;
+    let payload = read_bytes(reader, len)?;
+    let mut checksum = Crc32::default();
+    checksum.update(&payload);
+
+    if u32::unpack_from(reader)? != checksum.finish() {
+        return Err(Error::ChecksumMismatch);
+    }
+
+    Ok(payload)
+}
+
+/// Returns the size of the complete and intact frames at the start
+fn valid_len(reader: &mut impl io::Read, size: u64) -> Result<u64> {
+    let mut len = 0;
+
+    while len < size {
+        let mut remaining = reader.take(size - len);
+
+        match read_frame(&mut remaining) {
+            Ok(payload) => len += payload.len() as u64 + 8,
+            Err(Error::ChecksumMismatch) => break,
+            Err(Error::IO(error)) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("serial_container_{}.log", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn append_and_reopen() {
+        let path = path("reopen");
+        let mut log = RecordLog::<u64>::open(&path).unwrap();
+        assert_eq!(log.append(&1).unwrap(), 0);
+        assert_eq!(log.append(&2).unwrap(), 16);
+        drop(log);
+
+        let mut log = RecordLog::<u64>::open(&path).unwrap();
+        assert_eq!(log.truncated(), 0);
+        log.append(&3).unwrap();
+        let records: Vec<u64> = log.iter().unwrap().map(Result::unwrap).collect();
+        assert_eq!(records, [1, 2, 3]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn recover_from_torn_write() {
+        let path = path("torn");
+        let mut log = RecordLog::<u32>::open(&path).unwrap();
+        log.append(&1).unwrap();
+        log.append(&2).unwrap();
+        drop(log);
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 3);
+        fs::write(&path, &bytes).unwrap();
+
+        let mut log = RecordLog::<u32>::open(&path).unwrap();
+        assert_eq!(log.truncated(), 9);
+        assert_eq!(fs::metadata(&path).unwrap().len(), 12);
+        log.append(&3).unwrap();
+        let records: Vec<u32> = log.iter().unwrap().map(Result::unwrap).collect();
+        assert_eq!(records, [1, 3]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn recover_from_corruption() {
+        let path = path("corrupt");
+        let mut log = RecordLog::<u32>::open(&path).unwrap();
+        log.append(&1).unwrap();
+        log.append(&2).unwrap();
+        log.append(&3).unwrap();
+        drop(log);
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[16] ^= 0xFF;
+        fs::write(&path, &bytes).unwrap();
+
+        let log = RecordLog::<u32>::open(&path).unwrap();
+        assert_eq!(log.truncated(), 24);
+        assert_eq!(log.iter().unwrap().count(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,259 @@
+//! Envelopes for request/response protocols
+//!
+//! A [`Request`] is packed as its correlation id (`u64`), the method tag
+//! (`u16`) and the payload. A [`Response`] is packed as the correlation id
+//! of its request and a status code (`u16`), followed by the payload if the
+//! status is zero or by the error message otherwise
+
+use crate::pack::{self, Pack};
+use crate::unpack::{Result, Unpack};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+/// Request carrying a correlation id, a method tag and a payload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Request<T> {
+    pub id: u64,
+    pub method: u16,
+    pub payload: T,
+}
+
+impl<T> Request<T> {
+    /// Creates a request
+    pub fn new(id: u64, method: u16, payload: T) -> Self {
+        Self {
+            id,
+            method,
+            payload,
+        }
+    }
+
+    /// Creates the response to this request
+    pub fn respond<R>(&self, result: std::result::Result<R, Failure>) -> Response<R> {
+        Response {
+            id: self.id,
+            result,
+        }
+    }
+}
+
+impl<T: Pack> Pack for Request<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = self.id.pack_into(writer)?;
+        written += self.method.pack_into(writer)?;
+        written += self.payload.pack_into(writer)?;
+        Ok(written)
+    }
+}
+
+impl<T: Unpack> Unpack for Request<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        Ok(Request {
+            id: u64::unpack_from(reader)?,
+            method: u16::unpack_from(reader)?,
+            payload: T::unpack_from(reader)?,
+        })
+    }
+}
+
+/// First status code applications may use with [`Status::Other`]
+pub const FIRST_OTHER_CODE: u16 = 100;
+
+/// Status of a failed request
+///
+/// Codes below [`FIRST_OTHER_CODE`] are reserved for the named statuses,
+/// applications use `Other` with codes of 100 and above for their own
+/// statuses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    InvalidRequest,
+    UnknownMethod,
+    NotFound,
+    Unavailable,
+    Internal,
+    Other(u16),
+}
+
+impl Status {
+    /// Returns the code this status is packed as
+    pub fn code(self) -> u16 {
+        match self {
+            Status::InvalidRequest => 1,
+            Status::UnknownMethod => 2,
+            Status::NotFound => 3,
+            Status::Unavailable => 4,
+            Status::Internal => 5,
+            Status::Other(code) => code,
+        }
+    }
+
+    /// Returns the status of a code, or `None` for the success code zero
+    pub fn from_code(code: u16) -> Option<Self> {
+        match code {
+            0 => None,
+            1 => Some(Status::InvalidRequest),
+            2 => Some(Status::UnknownMethod),
+            3 => Some(Status::NotFound),
+            4 => Some(Status::Unavailable),
+            5 => Some(Status::Internal),
+            code => Some(Status::Other(code)),
+        }
+    }
+}
+
+/// Error payload of a failed request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Failure {
+    pub status: Status,
+    pub message: String,
+}
+
+impl Failure {
+    /// Creates a failure with the given status and message
+    pub fn new(status: Status, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        write!(destination, "{:?}: {}", self.status, self.message)
+    }
+}
+
+impl error::Error for Failure {}
+
+/// Response carrying the correlation id of its request and either the
+/// result payload or a [`Failure`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Response<T> {
+    pub id: u64,
+    pub result: std::result::Result<T, Failure>,
+}
+
+impl<T> Response<T> {
+    /// Creates a successful response
+    pub fn ok(id: u64, payload: T) -> Self {
+        Self {
+            id,
+            result: Ok(payload),
+        }
+    }
+
+    /// Creates a failed response
+    pub fn error(id: u64, status: Status, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            result: Err(Failure::new(status, message)),
+        }
+    }
+}
+
+impl<T: Pack> Pack for Response<T> {
+    /// Fails with `Error::InvalidValue` for a failure with the status
+    /// `Other` and a code below [`FIRST_OTHER_CODE`], which would not unpack
+    /// as the same status. Nothing is written in that case
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        if let Err(Failure {
+            status: Status::Other(code),
+            ..
+        }) = &self.result
+        {
+            if *code < FIRST_OTHER_CODE {
+                return Err(pack::Error::InvalidValue(format!(
+                    "status code {} is reserved, other statuses start at {}",
+                    code, FIRST_OTHER_CODE
+                )));
+            }
+        }
+
+        let mut written = self.id.pack_into(writer)?;
+
+        match &self.result {
+            Ok(payload) => {
+                written += 0u16.pack_into(writer)?;
+                written += payload.pack_into(writer)?;
+            }
+            Err(failure) => {
+                written += failure.status.code().pack_into(writer)?;
+                written += failure.message.pack_into(writer)?;
+            }
+        }
+
+        Ok(written)
+    }
+}
+
+impl<T: Unpack> Unpack for Response<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let id = u64::unpack_from(reader)?;
+        let result = match Status::from_code(u16::unpack_from(reader)?) {
+            None => Ok(T::unpack_from(reader)?),
+            Some(status) => Err(Failure::new(status, String::unpack_from(reader)?)),
+        };
+        Ok(Response { id, result })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_round_trip() {
+        let request = Request::new(7, 2, [1u8, 2]);
+        let bytes = request.pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 8 + 2 + 4 + 2);
+        let copy = Request::<Vec<u8>>::unpack_exact(&bytes).unwrap();
+        assert_eq!((copy.id, copy.method), (7, 2));
+        assert_eq!(copy.payload, [1, 2]);
+    }
+
+    #[test]
+    fn response_round_trip() {
+        let request = Request::new(9, 1, 0u8);
+        let response = request.respond(Ok(300u16));
+        let bytes = response.pack_to_vec().unwrap();
+        assert_eq!(bytes, [0, 0, 0, 0, 0, 0, 0, 9, 0x00, 0x00, 0x01, 0x2C]);
+        assert_eq!(Response::<u16>::unpack_exact(&bytes).unwrap(), response);
+
+        let response = Response::<u16>::error(9, Status::Other(404), "gone");
+        let bytes = response.pack_to_vec().unwrap();
+        assert_eq!(bytes[8..10], [0x01, 0x94]);
+        assert_eq!(Response::<u16>::unpack_exact(&bytes).unwrap(), response);
+    }
+
+    #[test]
+    fn reserved_success_code() {
+        let response = Response::<u8>::error(1, Status::Other(0), "");
+        assert!(response.pack_to_vec().is_err());
+        assert_eq!(Status::from_code(3), Some(Status::NotFound));
+    }
+
+    #[test]
+    fn reserved_status_codes() {
+        for code in [3, FIRST_OTHER_CODE - 1] {
+            let response = Response::<u8>::error(1, Status::Other(code), "");
+            let mut bytes = Vec::new();
+            assert!(matches!(
+                response.pack_into(&mut bytes),
+                Err(pack::Error::InvalidValue(_))
+            ));
+            assert!(bytes.is_empty());
+        }
+
+        for status in [
+            Status::InvalidRequest,
+            Status::Internal,
+            Status::Other(FIRST_OTHER_CODE),
+        ] {
+            let response = Response::<u8>::error(1, status, "failed");
+            let bytes = response.pack_to_vec().unwrap();
+            assert_eq!(Response::<u8>::unpack_exact(&bytes).unwrap(), response);
+        }
+    }
+}
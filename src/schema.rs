@@ -0,0 +1,440 @@
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{check_depth, Error, Result, Unpack};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::io;
+use std::num::*;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Runtime description of the wire format of a type
+///
+/// Schemas can be built manually or obtained from types implementing
+/// [`Describe`]. They are packable themselves, so two services can exchange
+/// their schemas and check with [`Schema::compatible_with`] whether they
+/// agree on a message layout before exchanging packed data
+///
+/// `NonZero` integers and their `Option` counterparts have the same wire
+/// format as the plain integers and are therefore described by them
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Schema {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    String,
+    /// Length-prefixed sequence of elements (e.g. `Vec`, sets, arrays)
+    Sequence(Box<Schema>),
+    /// Length-prefixed sequence of key-value pairs
+    Map(Box<Schema>, Box<Schema>),
+    /// Named fields packed one after another in declaration order
+    Struct {
+        name: String,
+        fields: Vec<(String, Schema)>,
+    },
+    /// Type with a custom wire format identified only by its name
+    Custom(String),
+}
+
+impl Schema {
+    /// Creates the schema of a sequence of the given elements
+    pub fn sequence(element: Schema) -> Self {
+        Schema::Sequence(Box::new(element))
+    }
+
+    /// Creates the schema of a map with the given keys and values
+    pub fn map(key: Schema, value: Schema) -> Self {
+        Schema::Map(Box::new(key), Box::new(value))
+    }
+
+    /// Creates the schema of a struct without any fields yet
+    pub fn structure(name: impl Into<String>) -> Self {
+        Schema::Struct {
+            name: name.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Appends a field to the schema of a struct
+    ///
+    /// # Panics
+    ///
+    /// Panics if this schema does not describe a struct
+    pub fn field(mut self, name: impl Into<String>, schema: Schema) -> Self {
+        match &mut self {
+            Schema::Struct { fields, .. } => fields.push((name.into(), schema)),
+            _ => panic!("fields can only be added to a struct schema"),
+        }
+        self
+    }
+
+    /// Returns a stable 64 bit fingerprint of this schema
+    ///
+    /// The fingerprint is the FNV-1a hash of the packed schema, so it is
+    /// the same on every platform and across releases. It covers the names
+    /// of structs and fields as well; use [`Schema::compatible_with`] to
+    /// compare only the wire format
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = 0xCBF2_9CE4_8422_2325u64;
+
+        for byte in self.pack_to_vec().expect("packing into a Vec never fails") {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+
+        hash
+    }
+
+    /// Returns whether values packed according to this schema can be
+    /// unpacked according to the other schema and vice versa
+    ///
+    /// Names of structs and fields do not appear on the wire and are
+    /// therefore ignored, except for custom types which are only known by
+    /// their name
+    pub fn compatible_with(&self, other: &Schema) -> bool {
+        use Schema::*;
+        match (self, other) {
+            (Sequence(left), Sequence(right)) => left.compatible_with(right),
+            (Map(left_key, left_value), Map(right_key, right_value)) => {
+                left_key.compatible_with(right_key) && left_value.compatible_with(right_value)
+            }
+            (Struct { fields: left, .. }, Struct { fields: right, .. }) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|((_, left), (_, right))| left.compatible_with(right))
+            }
+            (left, right) => left == right,
+        }
+    }
+
+    /// Returns whether values of this schema occupy no bytes on the wire,
+    /// so a length prefix alone would decide how many of them are read
+    pub(crate) fn is_zero_sized(&self) -> bool {
+        match self {
+            Schema::Struct { fields, .. } => {
+                fields.iter().all(|(_, schema)| schema.is_zero_sized())
+            }
+            _ => false,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        use Schema::*;
+        match self {
+            Bool => 0,
+            U8 => 1,
+            U16 => 2,
+            U32 => 3,
+            U64 => 4,
+            U128 => 5,
+            I16 => 6,
+            I32 => 7,
+            I64 => 8,
+            I128 => 9,
+            F32 => 10,
+            F64 => 11,
+            String => 12,
+            Sequence(_) => 13,
+            Map(_, _) => 14,
+            Struct { .. } => 15,
+            Custom(_) => 16,
+        }
+    }
+}
+
+impl Pack for Schema {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = self.tag().pack_into(writer)?;
+
+        match self {
+            Schema::Sequence(element) => written += element.pack_into(writer)?,
+            Schema::Map(key, value) => {
+                written += key.pack_into(writer)?;
+                written += value.pack_into(writer)?;
+            }
+            Schema::Struct { name, fields } => {
+                written += name.pack_into(writer)?;
+                written += checked_len(fields.len())?.pack_into(writer)?;
+
+                for (name, schema) in fields {
+                    written += name.pack_into(writer)?;
+                    written += schema.pack_into(writer)?;
+                }
+            }
+            Schema::Custom(name) => written += name.pack_into(writer)?,
+            _ => {}
+        }
+
+        Ok(written)
+    }
+}
+
+/// Fails with `Error::InvalidValue` if the schema is nested too deeply
+impl Unpack for Schema {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        Schema::unpack_nested(reader, 0)
+    }
+}
+
+impl Schema {
+    fn unpack_nested(reader: &mut impl io::Read, depth: usize) -> Result<Self> {
+        use Schema::*;
+        check_depth(depth)?;
+        let schema = match u8::unpack_from(reader)? {
+            0 => Bool,
+            1 => U8,
+            2 => U16,
+            3 => U32,
+            4 => U64,
+            5 => U128,
+            6 => I16,
+            7 => I32,
+            8 => I64,
+            9 => I128,
+            10 => F32,
+            11 => F64,
+            12 => String,
+            13 => Sequence(Box::new(Schema::unpack_nested(reader, depth + 1)?)),
+            14 => Map(
+                Box::new(Schema::unpack_nested(reader, depth + 1)?),
+                Box::new(Schema::unpack_nested(reader, depth + 1)?),
+            ),
+            15 => {
+                let name = std::string::String::unpack_from(reader)?;
+                let len = u32::unpack_from(reader)? as usize;
+                let mut fields = Vec::new();
+
+                for _i in 0..len {
+                    let field = std::string::String::unpack_from(reader)?;
+                    fields.push((field, Schema::unpack_nested(reader, depth + 1)?));
+                }
+
+                Struct { name, fields }
+            }
+            16 => Custom(std::string::String::unpack_from(reader)?),
+            tag => return Err(Error::InvalidValue(format!("unknown schema tag {}", tag))),
+        };
+        Ok(schema)
+    }
+}
+
+/// Describes the ability to report the wire format of a type at runtime
+pub trait Describe {
+    /// Returns the schema of this type
+    fn schema() -> Schema;
+}
+
+macro_rules! describe_primitives {
+    ($($schema:ident => $($type:ty),+;)*) => {
+        $($(
+            impl Describe for $type {
+                fn schema() -> Schema {
+                    Schema::$schema
+                }
+            }
+        )+)*
+    };
+}
+
+describe_primitives! {
+    Bool => bool;
+    U8 => u8, NonZeroU8, Option<NonZeroU8>;
+    U16 => u16, NonZeroU16, Option<NonZeroU16>;
+    U32 => u32, NonZeroU32, Option<NonZeroU32>;
+    U64 => u64, NonZeroU64, Option<NonZeroU64>;
+    U128 => u128, NonZeroU128, Option<NonZeroU128>;
+    I16 => i16, NonZeroI16, Option<NonZeroI16>;
+    I32 => i32, NonZeroI32, Option<NonZeroI32>;
+    I64 => i64, NonZeroI64, Option<NonZeroI64>;
+    I128 => i128, NonZeroI128, Option<NonZeroI128>;
+    F32 => f32;
+    F64 => f64;
+    String => str, String;
+}
+
+impl<T: Describe> Describe for [T] {
+    fn schema() -> Schema {
+        Schema::sequence(T::schema())
+    }
+}
+
+impl<T: Describe, const N: usize> Describe for [T; N] {
+    fn schema() -> Schema {
+        Schema::sequence(T::schema())
+    }
+}
+
+impl<T: Describe> Describe for Vec<T> {
+    fn schema() -> Schema {
+        Schema::sequence(T::schema())
+    }
+}
+
+impl<T: Describe> Describe for HashSet<T> {
+    fn schema() -> Schema {
+        Schema::sequence(T::schema())
+    }
+}
+
+impl<T: Describe> Describe for BTreeSet<T> {
+    fn schema() -> Schema {
+        Schema::sequence(T::schema())
+    }
+}
+
+impl<T: Describe> Describe for BinaryHeap<T> {
+    fn schema() -> Schema {
+        Schema::sequence(T::schema())
+    }
+}
+
+impl<K: Describe, V: Describe> Describe for HashMap<K, V> {
+    fn schema() -> Schema {
+        Schema::map(K::schema(), V::schema())
+    }
+}
+
+impl<K: Describe, V: Describe> Describe for BTreeMap<K, V> {
+    fn schema() -> Schema {
+        Schema::map(K::schema(), V::schema())
+    }
+}
+
+impl<T: Describe + ?Sized> Describe for &T {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: Describe + ?Sized> Describe for Box<T> {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: Describe + ?Sized> Describe for Rc<T> {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: Describe + ?Sized> Describe for Arc<T> {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+/// Wrapper that packs a value together with its schema
+///
+/// The schema is packed in front of the value, so the data can be read
+/// without compile-time knowledge of its type, e.g. as a
+/// [`Value`](crate::value::Value) in debugging tools or gateways.
+/// Unpacking into a concrete type fails with `Error::InvalidValue` if the
+/// packed schema is not compatible with the schema of that type
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SelfDescribing<T>(pub T);
+
+impl<T: Pack + Describe> Pack for SelfDescribing<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = T::schema().pack_into(writer)?;
+        Ok(written + self.0.pack_into(writer)?)
+    }
+}
+
+impl<T: Unpack + Describe> Unpack for SelfDescribing<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let schema = Schema::unpack_from(reader)?;
+
+        if !schema.compatible_with(&T::schema()) {
+            return Err(Error::InvalidValue(format!(
+                "packed schema {:?} is not compatible with {:?}",
+                schema,
+                T::schema()
+            )));
+        }
+
+        T::unpack_from(reader).map(SelfDescribing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(name: &str, x: &str) -> Schema {
+        Schema::structure(name)
+            .field(x, Schema::I32)
+            .field("tags", Vec::<String>::schema())
+    }
+
+    #[test]
+    fn describe_collections() {
+        let schema = HashMap::<u8, Vec<NonZeroU16>>::schema();
+        assert_eq!(
+            schema,
+            Schema::map(Schema::U8, Schema::sequence(Schema::U16))
+        );
+    }
+
+    #[test]
+    fn schema_round_trip() {
+        let schema = point("Point", "x");
+        let bytes = schema.pack_to_vec().unwrap();
+        assert_eq!(Schema::unpack_exact(&bytes).unwrap(), schema);
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        assert_eq!(Schema::U8.fingerprint(), 0xAF63_BC4C_8601_B62C);
+        assert_eq!(
+            point("Point", "x").fingerprint(),
+            point("Point", "x").fingerprint()
+        );
+        assert_ne!(
+            point("Point", "x").fingerprint(),
+            point("Point", "y").fingerprint()
+        );
+    }
+
+    #[test]
+    fn compatibility_ignores_names() {
+        assert!(point("Point", "x").compatible_with(&point("Vector", "y")));
+        assert!(!point("Point", "x").compatible_with(&Schema::structure("Point")));
+        assert!(!Schema::Custom("a".into()).compatible_with(&Schema::Custom("b".into())));
+    }
+
+    #[test]
+    fn self_describing_round_trip() {
+        let bytes = SelfDescribing(2u16).pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x02, 0x00, 0x02]);
+        let value = SelfDescribing::<u16>::unpack_exact(&bytes).unwrap();
+        assert_eq!(value.0, 2);
+    }
+
+    #[test]
+    fn self_describing_incompatible() {
+        let bytes = SelfDescribing(2u16).pack_to_vec().unwrap();
+        let result = SelfDescribing::<i16>::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn unpack_deeply_nested_schema() {
+        let bytes = vec![13u8; 100_000];
+        let result = Schema::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+
+        let mut bytes = vec![13u8; 100];
+        bytes.push(0);
+        assert!(Schema::unpack_exact(&bytes).is_ok());
+    }
+}
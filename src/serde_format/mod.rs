@@ -0,0 +1,289 @@
+//! Bridge between serde and the packed format
+//!
+//! Values are packed in the same way as their hand-written counterparts:
+//! structs and tuples as their fields in declaration order, sequences and
+//! maps with a `u32` length prefix. Types without a counterpart use the
+//! following encodings:
+//!
+//! - `i8` is packed as a single byte in two's complement
+//! - `char` is packed as its `u32` code point
+//! - `Option` is packed as a `u8` tag (0 for `None`, 1 for `Some`) followed
+//!   by the value
+//! - enum variants are packed as their `u32` index followed by their fields,
+//!   or as their name like a `str` with [`Serializer::with_variant_names`],
+//!   [`to_vec_with_variant_names`] or the [`SerdeNamed`] wrapper
+//! - units and unit structs are not packed at all
+//!
+//! Note that serde treats fixed-size arrays as tuples, so unlike the
+//! hand-written implementation they are packed without a length prefix
+//!
+//! # Example
+//!
+//! ```
+//! use serial_container::serde_format::{from_slice, to_vec};
+//!
+//! let bytes = to_vec(&(1u16, "a")).unwrap();
+//! assert_eq!(bytes, [0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x61]);
+//!
+//! let value: (u16, String) = from_slice(&bytes).unwrap();
+//! assert_eq!(value, (1, "a".to_string()));
+//! ```
+
+mod de;
+mod ser;
+
+pub use de::Deserializer;
+pub use ser::Serializer;
+
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+
+/// Serializes a value into the given writer and returns the number of
+/// bytes written
+pub fn to_writer<T: Serialize + ?Sized>(value: &T, writer: impl io::Write) -> pack::Result<usize> {
+    serialize(value, Serializer::new(writer))
+}
+
+/// Serializes a value into a new byte vector
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> pack::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    to_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Serializes a value into a new byte vector, packing enum variants by
+/// their name, see [`Serializer::with_variant_names`]
+pub fn to_vec_with_variant_names<T: Serialize + ?Sized>(value: &T) -> pack::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    serialize(value, Serializer::new(&mut bytes).with_variant_names())?;
+    Ok(bytes)
+}
+
+fn serialize<T: Serialize + ?Sized>(
+    value: &T,
+    mut serializer: Serializer<impl io::Write>,
+) -> pack::Result<usize> {
+    value.serialize(&mut serializer)?;
+    Ok(serializer.written())
+}
+
+/// Deserializes a value from the given reader
+pub fn from_reader<T: DeserializeOwned>(reader: impl io::Read) -> Result<T> {
+    T::deserialize(&mut Deserializer::new(reader))
+}
+
+/// Deserializes a value from the given bytes
+///
+/// Fails with `Error::TrailingBytes` if the bytes are not consumed entirely
+pub fn from_slice<T: DeserializeOwned>(mut bytes: &[u8]) -> Result<T> {
+    let value = T::deserialize(&mut Deserializer::new(&mut bytes))?;
+    check_consumed(bytes)?;
+    Ok(value)
+}
+
+/// Deserializes a value from the given bytes, reading enum variants by
+/// their name, see [`Deserializer::with_variant_names`]
+///
+/// Fails with `Error::TrailingBytes` if the bytes are not consumed entirely
+pub fn from_slice_with_variant_names<T: DeserializeOwned>(mut bytes: &[u8]) -> Result<T> {
+    let value = T::deserialize(&mut Deserializer::new(&mut bytes).with_variant_names())?;
+    check_consumed(bytes)?;
+    Ok(value)
+}
+
+fn check_consumed(bytes: &[u8]) -> Result<()> {
+    match bytes.len() {
+        0 => Ok(()),
+        len => Err(Error::TrailingBytes(len)),
+    }
+}
+
+/// Wrapper that packs a serde-compatible value in the packed format
+///
+/// This allows packing types of third-party crates that implement
+/// `Serialize`/`Deserialize`, but neither `Pack` nor `Unpack`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SerdePacked<T>(pub T);
+
+impl<T: Serialize> Pack for SerdePacked<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        to_writer(&self.0, writer)
+    }
+}
+
+impl<T: DeserializeOwned> Unpack for SerdePacked<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        from_reader(reader).map(SerdePacked)
+    }
+}
+
+/// Wrapper like [`SerdePacked`] that packs enum variants by their name, so
+/// adding or reordering variants keeps old bytes readable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SerdeNamed<T>(pub T);
+
+impl<T: Serialize> Pack for SerdeNamed<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        serialize(&self.0, Serializer::new(writer).with_variant_names())
+    }
+}
+
+impl<T: DeserializeOwned> Unpack for SerdeNamed<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        T::deserialize(&mut Deserializer::new(reader).with_variant_names()).map(SerdeNamed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Point {
+        x: u16,
+        name: String,
+        tags: Vec<i32>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f32),
+        Rect { width: u8, height: u8 },
+    }
+
+    #[test]
+    fn pack_matches_hand_written_layout() {
+        let point = Point {
+            x: 1,
+            name: "a".into(),
+            tags: vec![-1],
+        };
+        let mut expected = 1u16.pack_to_vec().unwrap();
+        "a".pack_into(&mut expected).unwrap();
+        [-1i32].pack_into(&mut expected).unwrap();
+
+        let bytes = SerdePacked(point).pack_to_vec().unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn round_trip_struct() {
+        let point = Point {
+            x: 7,
+            name: "point".into(),
+            tags: vec![1, 2, 3],
+        };
+        let bytes = SerdePacked(&point).pack_to_vec().unwrap();
+        let result = SerdePacked::<Point>::unpack_exact(&bytes).unwrap();
+        assert_eq!(result.0, point);
+    }
+
+    #[test]
+    fn round_trip_enums_and_options() {
+        let value = (
+            vec![
+                Shape::Empty,
+                Shape::Circle(0.5),
+                Shape::Rect {
+                    width: 2,
+                    height: 3,
+                },
+            ],
+            Some('x'),
+            None::<i8>,
+            BTreeMap::from([(1u8, -2i8)]),
+        );
+        let bytes = SerdePacked(&value).pack_to_vec().unwrap();
+        let result =
+            SerdePacked::<(Vec<Shape>, Option<char>, Option<i8>, BTreeMap<u8, i8>)>::unpack_exact(
+                &bytes,
+            )
+            .unwrap();
+        assert_eq!(result.0, value);
+    }
+
+    #[test]
+    fn variants_by_name() {
+        let mut serializer = Serializer::new(Vec::new()).with_variant_names();
+        Shape::Circle(0.5).serialize(&mut serializer).unwrap();
+        let bytes = serializer.into_inner();
+        assert_eq!(
+            bytes[..10],
+            [0, 0, 0, 6, b'C', b'i', b'r', b'c', b'l', b'e']
+        );
+
+        let mut deserializer = Deserializer::new(bytes.as_slice()).with_variant_names();
+        let shape = Shape::deserialize(&mut deserializer).unwrap();
+        assert_eq!(shape, Shape::Circle(0.5));
+
+        let bytes = "Hexagon".pack_to_vec().unwrap();
+        let mut deserializer = Deserializer::new(bytes.as_slice()).with_variant_names();
+        let result = Shape::deserialize(&mut deserializer);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn round_trip_variants_by_name() {
+        let shapes = vec![
+            Shape::Empty,
+            Shape::Rect {
+                width: 2,
+                height: 3,
+            },
+        ];
+        let bytes = to_vec_with_variant_names(&shapes).unwrap();
+        assert_eq!(bytes[4..13], [0, 0, 0, 5, b'E', b'm', b'p', b't', b'y']);
+        assert_eq!(
+            from_slice_with_variant_names::<Vec<Shape>>(&bytes).unwrap(),
+            shapes
+        );
+        assert!(from_slice::<Vec<Shape>>(&bytes).is_err());
+
+        assert_eq!(SerdeNamed(&shapes).pack_to_vec().unwrap(), bytes);
+        let result = SerdeNamed::<Vec<Shape>>::unpack_exact(&bytes).unwrap();
+        assert_eq!(result.0, shapes);
+    }
+
+    #[test]
+    fn unpack_invalid_option_tag() {
+        let result = SerdePacked::<Option<u8>>::unpack_exact(&[0x02, 0x00]);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn to_vec_and_from_slice() {
+        let bytes = to_vec(&[true, false]).unwrap();
+        assert_eq!(bytes, [0x00, 0xFF]);
+        assert_eq!(from_slice::<[bool; 2]>(&bytes).unwrap(), [true, false]);
+    }
+
+    #[test]
+    fn from_slice_trailing_bytes() {
+        let result = from_slice::<u8>(&[0x01, 0x02]);
+        assert!(matches!(result, Err(Error::TrailingBytes(1))));
+    }
+
+    #[test]
+    fn to_writer_unknown_length() {
+        struct Unsized;
+
+        impl Serialize for Unsized {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                use serde::ser::SerializeSeq;
+                serializer.serialize_seq(None)?.end()
+            }
+        }
+
+        let result = to_vec(&Unsized);
+        assert!(matches!(result, Err(pack::Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::pack::{checked_len, Error, Pack, Result};
+use serde::ser::{self, Serialize};
+use std::io;
+
+/// Serde serializer writing the packed format into a writer
+pub struct Serializer<W> {
+    writer: W,
+    written: usize,
+    variant_names: bool,
+}
+
+impl<W: io::Write> Serializer<W> {
+    /// Creates a serializer writing into the given writer
+    pub fn new(writer: W) -> Self {
+        Serializer {
+            writer,
+            written: 0,
+            variant_names: false,
+        }
+    }
+
+    /// Packs enum variants by their name as a `str` instead of their `u32`
+    /// index, so adding or reordering variants keeps old bytes readable
+    ///
+    /// The bytes have to be read by a [`Deserializer`] with the same option
+    ///
+    /// [`Deserializer`]: super::Deserializer
+    pub fn with_variant_names(mut self) -> Self {
+        self.variant_names = true;
+        self
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write<T: Pack + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.written += value.pack_into(&mut self.writer)?;
+        Ok(())
+    }
+
+    fn write_variant(&mut self, index: u32, name: &str) -> Result<()> {
+        if self.variant_names {
+            self.write(name)
+        } else {
+            self.write(&index)
+        }
+    }
+
+    fn write_len(&mut self, len: Option<usize>) -> Result<()> {
+        match len {
+            Some(len) => self.write(&checked_len(len)?),
+            None => Err(Error::InvalidValue(
+                "sequences and maps must know their length in advance".into(),
+            )),
+        }
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: std::fmt::Display>(message: T) -> Self {
+        Error::InvalidValue(message.to_string())
+    }
+}
+
+impl<W: io::Write> ser::Serializer for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, value: bool) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<()> {
+        self.write(&(value as u8))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<()> {
+        self.write(&value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<()> {
+        self.write(&(value as u32))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<()> {
+        self.write(value)
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+        self.write(value)
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.write(&0u8)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        self.write(&1u8)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.write_variant(variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.write_variant(variant_index, variant)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self> {
+        self.write_variant(variant_index, variant)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self> {
+        self.write_variant(variant_index, variant)?;
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl<W: io::Write> ser::SerializeSeq for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: io::Write> ser::SerializeTuple for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: io::Write> ser::SerializeTupleStruct for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: io::Write> ser::SerializeTupleVariant for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: io::Write> ser::SerializeMap for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: io::Write> ser::SerializeStruct for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: io::Write> ser::SerializeStructVariant for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
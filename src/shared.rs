@@ -0,0 +1,281 @@
+//! Deduplicating encoding of shared pointers
+//!
+//! `Rc<T>` and `Arc<T>` are packed by value like any other reference, so a
+//! node shared by several owners is packed once per owner and unpacked into
+//! independent copies. Wrapping the pointers in [`Shared`] and packing them
+//! through a [`PointerTable`] with [`PackWith`] packs every distinct pointee
+//! only once and restores the sharing on unpack
+//!
+//! The first occurrence of a pointee is packed as `0u32` followed by the
+//! value, every further occurrence as the index of the pointee plus one.
+//! Indices are assigned after the value is complete, so nested shared
+//! pointers are numbered before their owners on both sides
+//!
+//! Cycles (e.g. in a graph of `Rc<RefCell<Node>>`) cannot be restored, as
+//! a pointee only gets its index once it is complete. Packing a pointer
+//! while its pointee is still being packed fails with `InvalidValue`
+//! instead of recursing until the stack overflows
+
+use crate::contextual::{PackWith, UnpackWith};
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Context assigning indices to the pointees of [`Shared`] pointers
+///
+/// The table keeps every packed or unpacked pointer alive, so addresses are
+/// not reused while it is in use. Reader and writer have to start with the
+/// same table, usually an empty one, and process the same sequence of values
+#[derive(Default)]
+pub struct PointerTable {
+    indices: HashMap<usize, u32>,
+    pointers: Vec<Box<dyn Any>>,
+    active: HashSet<usize>,
+}
+
+impl PointerTable {
+    /// Creates an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct pointees seen so far
+    pub fn len(&self) -> usize {
+        self.pointers.len()
+    }
+
+    /// Returns whether no pointee has been seen yet
+    pub fn is_empty(&self) -> bool {
+        self.pointers.is_empty()
+    }
+
+    /// Removes all pointers, e.g. at the start of a new section
+    pub fn clear(&mut self) {
+        self.indices.clear();
+        self.pointers.clear();
+        self.active.clear();
+    }
+
+    fn insert(&mut self, address: usize, pointer: Box<dyn Any>) -> pack::Result<()> {
+        self.indices
+            .insert(address, checked_len(self.pointers.len())?);
+        self.pointers.push(pointer);
+        Ok(())
+    }
+
+    fn get<P: Clone + 'static>(&self, reference: u32) -> Result<P> {
+        self.pointers
+            .get(reference as usize - 1)
+            .and_then(|pointer| pointer.downcast_ref::<P>())
+            .cloned()
+            .ok_or_else(|| Error::InvalidValue(format!("unknown pointer reference {}", reference)))
+    }
+}
+
+/// Wrapper that packs a shared pointer through a [`PointerTable`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Shared<P>(pub P);
+
+macro_rules! shared_pointers {
+    ($($pointer:ident),*) => {
+        $(
+            impl<T: PackWith<PointerTable> + 'static> PackWith<PointerTable> for Shared<$pointer<T>> {
+                fn pack_with(
+                    &self,
+                    context: &mut PointerTable,
+                    writer: &mut impl io::Write,
+                ) -> pack::Result<usize> {
+                    let address = $pointer::as_ptr(&self.0) as usize;
+
+                    if let Some(index) = context.indices.get(&address) {
+                        return (index + 1).pack_into(writer);
+                    }
+
+                    if !context.active.insert(address) {
+                        return Err(pack::Error::InvalidValue(String::from(
+                            "shared pointers form a cycle",
+                        )));
+                    }
+
+                    let result = 0u32
+                        .pack_into(writer)
+                        .and_then(|written| Ok(written + self.0.pack_with(context, writer)?));
+                    context.active.remove(&address);
+                    let written = result?;
+                    context.insert(address, Box::new($pointer::clone(&self.0)))?;
+                    Ok(written)
+                }
+            }
+
+            impl<T: UnpackWith<PointerTable> + 'static> UnpackWith<PointerTable> for Shared<$pointer<T>> {
+                fn unpack_with(context: &mut PointerTable, reader: &mut impl io::Read) -> Result<Self> {
+                    match u32::unpack_from(reader)? {
+                        0 => {
+                            let pointer = $pointer::new(T::unpack_with(context, reader)?);
+                            let address = $pointer::as_ptr(&pointer) as usize;
+                            context.insert(address, Box::new($pointer::clone(&pointer)))?;
+                            Ok(Shared(pointer))
+                        }
+                        reference => context.get(reference).map(Shared),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+shared_pointers!(Rc, Arc);
+
+impl<T: PackWith<PointerTable>> PackWith<PointerTable> for RefCell<T> {
+    fn pack_with(
+        &self,
+        context: &mut PointerTable,
+        writer: &mut impl io::Write,
+    ) -> pack::Result<usize> {
+        self.borrow().pack_with(context, writer)
+    }
+}
+
+impl<T: UnpackWith<PointerTable>> UnpackWith<PointerTable> for RefCell<T> {
+    fn unpack_with(context: &mut PointerTable, reader: &mut impl io::Read) -> Result<Self> {
+        T::unpack_with(context, reader).map(RefCell::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contextual::{pack_slice_with, unpack_vec_with};
+
+    struct Node {
+        value: u32,
+        child: Option<Shared<Rc<Node>>>,
+    }
+
+    impl PackWith<PointerTable> for Node {
+        fn pack_with(
+            &self,
+            context: &mut PointerTable,
+            writer: &mut impl io::Write,
+        ) -> pack::Result<usize> {
+            let mut written = self.value.pack_into(writer)?;
+
+            match &self.child {
+                Some(child) => {
+                    written += 1u8.pack_into(writer)? + child.pack_with(context, writer)?
+                }
+                None => written += 0u8.pack_into(writer)?,
+            }
+
+            Ok(written)
+        }
+    }
+
+    impl UnpackWith<PointerTable> for Node {
+        fn unpack_with(context: &mut PointerTable, reader: &mut impl io::Read) -> Result<Self> {
+            let value = u32::unpack_from(reader)?;
+            let child = match u8::unpack_from(reader)? {
+                0 => None,
+                _ => Some(Shared::unpack_with(context, reader)?),
+            };
+            Ok(Node { value, child })
+        }
+    }
+
+    #[test]
+    fn restore_sharing() {
+        let leaf = Rc::new(Node {
+            value: 1,
+            child: None,
+        });
+        let nodes = [
+            Shared(Rc::new(Node {
+                value: 2,
+                child: Some(Shared(leaf.clone())),
+            })),
+            Shared(leaf.clone()),
+            Shared(leaf),
+        ];
+
+        let mut table = PointerTable::new();
+        let mut bytes = Vec::new();
+        pack_slice_with(&nodes, &mut table, &mut bytes).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(bytes.len(), 4 + (4 + 4 + 1 + 4 + 4 + 1) + 4 + 4);
+
+        let mut table = PointerTable::new();
+        let decoded: Vec<Shared<Rc<Node>>> =
+            unpack_vec_with(&mut table, &mut bytes.as_slice()).unwrap();
+        let child = decoded[0].0.child.as_ref().unwrap();
+        assert_eq!(child.0.value, 1);
+        assert!(Rc::ptr_eq(&child.0, &decoded[1].0));
+        assert!(Rc::ptr_eq(&decoded[1].0, &decoded[2].0));
+    }
+
+    #[test]
+    fn shared_arc_values() {
+        let value = Arc::new(7u16);
+        let values = [Shared(value.clone()), Shared(value)];
+        let mut bytes = Vec::new();
+        pack_slice_with(&values, &mut PointerTable::new(), &mut bytes).unwrap();
+        assert_eq!(bytes, [0, 0, 0, 2, 0, 0, 0, 0, 0x00, 0x07, 0, 0, 0, 1]);
+
+        let decoded: Vec<Shared<Arc<u16>>> =
+            unpack_vec_with(&mut PointerTable::new(), &mut bytes.as_slice()).unwrap();
+        assert!(Arc::ptr_eq(&decoded[0].0, &decoded[1].0));
+    }
+
+    #[test]
+    fn unknown_reference() {
+        let result = Shared::<Rc<u8>>::unpack_exact_with(&mut PointerTable::new(), &[0, 0, 0, 1]);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    struct Cell {
+        value: u8,
+        next: Option<Shared<Rc<RefCell<Cell>>>>,
+    }
+
+    impl PackWith<PointerTable> for Cell {
+        fn pack_with(
+            &self,
+            context: &mut PointerTable,
+            writer: &mut impl io::Write,
+        ) -> pack::Result<usize> {
+            let mut written = self.value.pack_into(writer)?;
+
+            if let Some(next) = &self.next {
+                written += next.pack_with(context, writer)?;
+            }
+
+            Ok(written)
+        }
+    }
+
+    #[test]
+    fn detect_cycle() {
+        let first = Rc::new(RefCell::new(Cell {
+            value: 1,
+            next: None,
+        }));
+        let second = Rc::new(RefCell::new(Cell {
+            value: 2,
+            next: Some(Shared(first.clone())),
+        }));
+        first.borrow_mut().next = Some(Shared(second.clone()));
+
+        let mut table = PointerTable::new();
+        let result = Shared(first.clone()).pack_to_vec_with(&mut table);
+        assert!(matches!(result, Err(pack::Error::InvalidValue(_))));
+
+        first.borrow_mut().next = None;
+        let mut table = PointerTable::new();
+        let bytes = Shared(second).pack_to_vec_with(&mut table).unwrap();
+        assert_eq!(bytes, [0, 0, 0, 0, 0x02, 0, 0, 0, 0, 0x01]);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::pack::{self, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use ed25519_dalek::{Signature, Signer};
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::marker::PhantomData;
+
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+/// Envelope holding a packed value together with its ed25519 signature
+///
+/// The envelope is packed as the payload in the form of a byte array
+/// followed by the 64 byte signature. The payload can only be unpacked
+/// after the signature has been verified against a public key, which fails
+/// with `Error::SignatureMismatch` if the envelope has been tampered with
+pub struct Signed<T> {
+    payload: Vec<u8>,
+    signature: Signature,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Pack> Signed<T> {
+    /// Packs a value and signs the packed bytes
+    pub fn sign(value: &T, key: &SigningKey) -> pack::Result<Self> {
+        let payload = value.pack_to_vec()?;
+        let signature = key.sign(&payload);
+
+        Ok(Self {
+            payload,
+            signature,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<T: Unpack> Signed<T> {
+    /// Verifies the signature against the given public key and unpacks the
+    /// value
+    pub fn verify(&self, key: &VerifyingKey) -> Result<T> {
+        key.verify_strict(&self.payload, &self.signature)
+            .map_err(|_| Error::SignatureMismatch)?;
+        T::unpack_exact(&self.payload)
+    }
+
+    /// Tries to deserialize an envelope from a bytesource and verifies it
+    /// against the given public key in one step
+    pub fn unpack_verified(reader: &mut impl io::Read, key: &VerifyingKey) -> Result<T> {
+        Self::unpack_from(reader)?.verify(key)
+    }
+}
+
+impl<T> Clone for Signed<T> {
+    fn clone(&self) -> Self {
+        Self {
+            payload: self.payload.clone(),
+            signature: self.signature,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for Signed<T> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_struct("Signed")
+            .field("len", &self.payload.len())
+            .field("signature", &self.signature)
+            .finish()
+    }
+}
+
+impl<T> Pack for Signed<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let written = self.payload.as_slice().pack_into(writer)?;
+        let signature = self.signature.to_bytes();
+        writer.write_all(&signature)?;
+        Ok(written + signature.len())
+    }
+}
+
+impl<T> Unpack for Signed<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        let payload = read_bytes(reader, len)?;
+        let mut signature = [0x00; Signature::BYTE_SIZE];
+        reader.read_exact(&mut signature).map_err(Error::IO)?;
+
+        Ok(Self {
+            payload,
+            signature: Signature::from_bytes(&signature),
+            marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    #[test]
+    fn signed_round_trip() {
+        let bytes = Signed::sign(&2u16, &key(1)).unwrap().pack_to_vec().unwrap();
+        assert_eq!(bytes.len(), 4 + 2 + 64);
+
+        let value = Signed::<u16>::unpack_verified(&mut bytes.as_slice(), &key(1).verifying_key());
+        assert_eq!(value.unwrap(), 2);
+    }
+
+    #[test]
+    fn verify_with_other_key() {
+        let signed = Signed::sign(&2u16, &key(1)).unwrap();
+        let result = signed.verify(&key(2).verifying_key());
+        assert!(matches!(result, Err(Error::SignatureMismatch)));
+    }
+
+    #[test]
+    fn verify_tampered() {
+        let mut bytes = Signed::sign(&2u16, &key(1)).unwrap().pack_to_vec().unwrap();
+        bytes[5] = 0x03;
+        let result = Signed::<u16>::unpack_verified(&mut bytes.as_slice(), &key(1).verifying_key());
+        assert!(matches!(result, Err(Error::SignatureMismatch)));
+    }
+}
@@ -0,0 +1,395 @@
+//! Skipping packed values without decoding them
+//!
+//! [`skip_packed`] and [`skip_packed_seek`] jump over a packed value, e.g. a
+//! record or field a reader does not care about. Values of a fixed size are
+//! skipped at once, sequences by reading their length prefix and skipping
+//! their elements, so nothing is materialized. With a seekable bytesource
+//! the skipped bytes are not even read
+//!
+//! Custom types implement [`Skip`] by skipping their fields in order
+//!
+//! ```
+//! use serial_container::pack::Pack;
+//! use serial_container::skip::skip_packed;
+//! use serial_container::unpack::Unpack;
+//!
+//! let mut bytes = [1u32, 2, 3].pack_to_vec().unwrap();
+//! "tail".pack_into(&mut bytes).unwrap();
+//!
+//! let mut reader = bytes.as_slice();
+//! assert_eq!(skip_packed::<Vec<u32>>(&mut reader).unwrap(), 16);
+//! assert_eq!(String::unpack_from(&mut reader).unwrap(), "tail");
+//! ```
+
+use crate::pack::PackFixed;
+use crate::raw::Blob;
+use crate::schema::Schema;
+use crate::unpack::{Error, Result, Unpack};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::io::{self, Read};
+use std::num::*;
+
+/// Bytesource able to pass over a number of bytes
+pub trait Skipper: io::Read {
+    /// Passes over the given number of bytes
+    fn discard(&mut self, len: u64) -> io::Result<()>;
+}
+
+/// Describes the ability to skip a packed value of this type
+pub trait Skip {
+    /// Number of bytes every packed value of this type occupies, if that
+    /// number is fixed
+    const FIXED_SIZE: Option<usize> = None;
+
+    /// Skips a packed value and returns the number of skipped bytes
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64>;
+}
+
+/// Skips a packed value by reading and discarding its bytes
+pub fn skip_packed<T: Skip + ?Sized>(reader: &mut impl io::Read) -> Result<u64> {
+    T::skip_from(&mut Reading(reader))
+}
+
+/// Skips a packed value by seeking over everything but its length prefixes
+///
+/// Seeking beyond the end of the bytesource is not detected until the next
+/// read from it
+pub fn skip_packed_seek<T: Skip + ?Sized>(reader: &mut (impl io::Read + io::Seek)) -> Result<u64> {
+    T::skip_from(&mut Seeking(reader))
+}
+
+pub(crate) struct Reading<'a, R>(pub(crate) &'a mut R);
+
+impl<R: io::Read> io::Read for Reading<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buffer)
+    }
+}
+
+impl<R: io::Read> Skipper for Reading<'_, R> {
+    fn discard(&mut self, len: u64) -> io::Result<()> {
+        let discarded = io::copy(&mut (&mut *self.0).take(len), &mut io::sink())?;
+
+        if discarded < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(())
+    }
+}
+
+struct Seeking<'a, R>(&'a mut R);
+
+impl<R: io::Read> io::Read for Seeking<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buffer)
+    }
+}
+
+impl<R: io::Read + io::Seek> Skipper for Seeking<'_, R> {
+    fn discard(&mut self, len: u64) -> io::Result<()> {
+        let offset = i64::try_from(len)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "skip too large"))?;
+        self.0.seek(io::SeekFrom::Current(offset))?;
+        Ok(())
+    }
+}
+
+/// Implements `Skip` for types with the size taken from `PackFixed::SIZE`
+macro_rules! skip_fixed {
+    ($($type:ty),+ $(,)?) => {
+        $(
+            impl Skip for $type {
+                const FIXED_SIZE: Option<usize> = Some(<$type as PackFixed>::SIZE);
+
+                fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+                    reader.discard(<$type as PackFixed>::SIZE as u64)?;
+                    Ok(<$type as PackFixed>::SIZE as u64)
+                }
+            }
+        )+
+    };
+}
+
+skip_fixed! {
+    bool, u8, NonZeroU8, Option<NonZeroU8>,
+    u16, NonZeroU16, Option<NonZeroU16>, i16, NonZeroI16, Option<NonZeroI16>,
+    u32, NonZeroU32, Option<NonZeroU32>, i32, NonZeroI32, Option<NonZeroI32>, f32,
+    u64, NonZeroU64, Option<NonZeroU64>, i64, NonZeroI64, Option<NonZeroI64>, f64,
+    u128, NonZeroU128, Option<NonZeroU128>, i128, NonZeroI128, Option<NonZeroI128>,
+}
+
+/// Skips `len` values of the given type packed back-to-back
+fn skip_elements<T: Skip + ?Sized>(reader: &mut impl Skipper, len: u64) -> Result<u64> {
+    if let Some(size) = T::FIXED_SIZE {
+        let bytes = len
+            .checked_mul(size as u64)
+            .ok_or_else(|| Error::InvalidValue(format!("{} elements overflow", len)))?;
+        reader.discard(bytes)?;
+        return Ok(bytes);
+    }
+
+    let mut skipped = 0;
+
+    for _i in 0..len {
+        skipped += T::skip_from(reader)?;
+    }
+
+    Ok(skipped)
+}
+
+/// Skips a value packed according to the given schema
+///
+/// Fails with `Error::InvalidValue` for custom types, whose wire format is
+/// unknown
+pub fn skip_with_schema(schema: &Schema, reader: &mut impl Skipper) -> Result<u64> {
+    if let Some(size) = fixed_size(schema) {
+        reader.discard(size as u64)?;
+        return Ok(size as u64);
+    }
+
+    match schema {
+        Schema::String => skip_sequence::<u8>(reader),
+        Schema::Sequence(element) => {
+            let len = u32::unpack_from(reader)?;
+
+            if let Some(size) = fixed_size(element) {
+                let bytes = u64::from(len) * size as u64;
+                reader.discard(bytes)?;
+                return Ok(4 + bytes);
+            }
+
+            let mut skipped = 4;
+
+            for _i in 0..len {
+                skipped += skip_with_schema(element, reader)?;
+            }
+
+            Ok(skipped)
+        }
+        Schema::Map(key, value) => {
+            let mut skipped = 4;
+
+            for _i in 0..u32::unpack_from(reader)? {
+                skipped += skip_with_schema(key, reader)? + skip_with_schema(value, reader)?;
+            }
+
+            Ok(skipped)
+        }
+        Schema::Struct { fields, .. } => {
+            let mut skipped = 0;
+
+            for (_, schema) in fields {
+                skipped += skip_with_schema(schema, reader)?;
+            }
+
+            Ok(skipped)
+        }
+        Schema::Custom(name) => Err(Error::InvalidValue(format!(
+            "custom type {} cannot be skipped without its implementation",
+            name
+        ))),
+        _ => unreachable!("primitives have a fixed size"),
+    }
+}
+
+/// Returns the number of bytes every value of the schema occupies, if that
+/// number is fixed
+fn fixed_size(schema: &Schema) -> Option<usize> {
+    match schema {
+        Schema::Bool => Some(bool::SIZE),
+        Schema::U8 => Some(u8::SIZE),
+        Schema::U16 => Some(u16::SIZE),
+        Schema::I16 => Some(i16::SIZE),
+        Schema::U32 => Some(u32::SIZE),
+        Schema::I32 => Some(i32::SIZE),
+        Schema::F32 => Some(f32::SIZE),
+        Schema::U64 => Some(u64::SIZE),
+        Schema::I64 => Some(i64::SIZE),
+        Schema::F64 => Some(f64::SIZE),
+        Schema::U128 => Some(u128::SIZE),
+        Schema::I128 => Some(i128::SIZE),
+        Schema::Struct { fields, .. } => fields.iter().map(|(_, schema)| fixed_size(schema)).sum(),
+        _ => None,
+    }
+}
+
+/// Skips a length-prefixed sequence of values of the given type
+fn skip_sequence<T: Skip + ?Sized>(reader: &mut impl Skipper) -> Result<u64> {
+    let len = u32::unpack_from(reader)?;
+    Ok(4 + skip_elements::<T>(reader, len.into())?)
+}
+
+impl Skip for str {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<u8>(reader)
+    }
+}
+
+impl Skip for String {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<u8>(reader)
+    }
+}
+
+impl Skip for Blob {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<u8>(reader)
+    }
+}
+
+impl<T: Skip> Skip for [T] {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<T>(reader)
+    }
+}
+
+impl<T: Skip, const N: usize> Skip for [T; N] {
+    const FIXED_SIZE: Option<usize> = match T::FIXED_SIZE {
+        Some(size) => Some(4 + N * size),
+        None => None,
+    };
+
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<T>(reader)
+    }
+}
+
+impl<T: Skip> Skip for Vec<T> {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<T>(reader)
+    }
+}
+
+impl<T: Skip> Skip for HashSet<T> {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<T>(reader)
+    }
+}
+
+impl<T: Skip> Skip for BTreeSet<T> {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<T>(reader)
+    }
+}
+
+impl<T: Skip> Skip for BinaryHeap<T> {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_sequence::<T>(reader)
+    }
+}
+
+/// Skips a length-prefixed sequence of key-value pairs
+fn skip_map<K: Skip, V: Skip>(reader: &mut impl Skipper) -> Result<u64> {
+    let len = u32::unpack_from(reader)?;
+
+    if let (Some(key), Some(value)) = (K::FIXED_SIZE, V::FIXED_SIZE) {
+        let bytes = u64::from(len) * (key + value) as u64;
+        reader.discard(bytes)?;
+        return Ok(4 + bytes);
+    }
+
+    let mut skipped = 4;
+
+    for _i in 0..len {
+        skipped += K::skip_from(reader)? + V::skip_from(reader)?;
+    }
+
+    Ok(skipped)
+}
+
+impl<K: Skip, V: Skip> Skip for HashMap<K, V> {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_map::<K, V>(reader)
+    }
+}
+
+impl<K: Skip, V: Skip> Skip for BTreeMap<K, V> {
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        skip_map::<K, V>(reader)
+    }
+}
+
+impl<T: Skip + ?Sized> Skip for Box<T> {
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+        T::skip_from(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::Pack;
+    use std::io::{Cursor, Seek};
+
+    struct Record;
+
+    impl Skip for Record {
+        fn skip_from(reader: &mut impl Skipper) -> Result<u64> {
+            Ok(u16::skip_from(reader)?
+                + String::skip_from(reader)?
+                + Vec::<f32>::skip_from(reader)?)
+        }
+    }
+
+    fn record() -> Vec<u8> {
+        let mut bytes = 7u16.pack_to_vec().unwrap();
+        "name".pack_into(&mut bytes).unwrap();
+        [0.5f32, 1.5].pack_into(&mut bytes).unwrap();
+        0xABCDu16.pack_into(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn skip_by_reading() {
+        let bytes = record();
+        let mut reader = bytes.as_slice();
+        assert_eq!(skip_packed::<Record>(&mut reader).unwrap(), 2 + 8 + 12);
+        assert_eq!(reader, [0xAB, 0xCD]);
+    }
+
+    #[test]
+    fn skip_by_seeking() {
+        let mut reader = Cursor::new(record());
+        assert_eq!(skip_packed_seek::<Record>(&mut reader).unwrap(), 22);
+        assert_eq!(reader.stream_position().unwrap(), 22);
+        assert_eq!(u16::unpack_from(&mut reader).unwrap(), 0xABCD);
+    }
+
+    #[test]
+    fn skip_nested_map() {
+        let mut map = BTreeMap::new();
+        map.insert(1u8, ["a", "bc"]);
+        map.insert(2u8, ["", "d"]);
+        let bytes = map.pack_to_vec().unwrap();
+        let skipped = skip_packed::<BTreeMap<u8, Vec<String>>>(&mut bytes.as_slice()).unwrap();
+        assert_eq!(skipped, bytes.len() as u64);
+    }
+
+    #[test]
+    fn skip_schema() {
+        let schema = Schema::structure("Record")
+            .field("id", Schema::U16)
+            .field("name", Schema::String)
+            .field("values", Schema::sequence(Schema::F32));
+        let bytes = record();
+        let mut reader = bytes.as_slice();
+        assert_eq!(
+            skip_with_schema(&schema, &mut Reading(&mut reader)).unwrap(),
+            22
+        );
+        assert_eq!(reader, [0xAB, 0xCD]);
+
+        let result = skip_with_schema(&Schema::Custom("A".into()), &mut Reading(&mut reader));
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn skip_truncated() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x01];
+        let result = skip_packed::<Vec<u8>>(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+}
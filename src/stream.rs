@@ -0,0 +1,390 @@
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use std::io::{self, Read};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+/// Iterator over consecutive values packed into a bytesource
+///
+/// The iterator yields values until the bytesource ends. Ending exactly
+/// between two values is a clean end and simply finishes the iteration,
+/// while ending in the middle of a value yields an `UnexpectedEof` error.
+/// The iteration also finishes after the first error
+pub struct UnpackIter<T, R> {
+    reader: R,
+    finished: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T, R: io::Read> UnpackIter<T, R> {
+    /// Creates an iterator reading values from the given bytesource
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            finished: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the underlying bytesource
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_first_byte(&mut self) -> Result<Option<u8>> {
+        let mut buffer = [0x00];
+
+        loop {
+            match self.reader.read(&mut buffer) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buffer[0])),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(Error::IO(error)),
+            }
+        }
+    }
+}
+
+impl<T: Unpack, R: io::Read> Iterator for UnpackIter<T, R> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = match self.read_first_byte() {
+            Ok(None) => {
+                self.finished = true;
+                return None;
+            }
+            Ok(Some(first)) => T::unpack_from(&mut [first].as_ref().chain(&mut self.reader)),
+            Err(error) => Err(error),
+        };
+
+        self.finished = result.is_err();
+        Some(result)
+    }
+}
+
+impl<T: Unpack, R: io::Read> FusedIterator for UnpackIter<T, R> {}
+
+/// Unpacks consecutive values until the bytesource ends
+///
+/// The bytesource is read through a buffer. Ending exactly between two
+/// values is a clean end, while ending in the middle of a value fails with
+/// an `UnexpectedEof` error, so truncated files are not mistaken for
+/// complete ones
+pub fn unpack_all<T: Unpack>(reader: impl io::Read) -> Result<Vec<T>> {
+    UnpackIter::new(io::BufReader::new(reader)).collect()
+}
+
+/// Packs all items of an iterator back-to-back into a bytestream
+///
+/// The items are packed one after another as they are produced by the
+/// iterator, without collecting them first and without any header. They
+/// can be read back with [`UnpackIter`]
+pub fn pack_all<T: Pack>(
+    items: impl IntoIterator<Item = T>,
+    writer: &mut impl io::Write,
+) -> pack::Result<usize> {
+    let mut written = 0;
+
+    for item in items {
+        written += item.pack_into(writer)?;
+    }
+
+    Ok(written)
+}
+
+/// Packs all items of an iterator preceded by their count as `u32`
+///
+/// The output has the same layout as a packed slice, so it can be read
+/// back as a `Vec`
+pub fn pack_all_counted<I>(items: I, writer: &mut impl io::Write) -> pack::Result<usize>
+where
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    I::Item: Pack,
+{
+    let items = items.into_iter();
+    let len = checked_len(items.len())?;
+    let written = len.pack_into(writer)?;
+    pack_all(items, writer).map(|x| written + x)
+}
+
+/// Extension trait to pack the items of an iterator directly
+pub trait PackAll: Iterator + Sized
+where
+    Self::Item: Pack,
+{
+    /// Packs all items back-to-back, see [`pack_all`]
+    fn pack_all(self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        pack_all(self, writer)
+    }
+
+    /// Packs all items preceded by their count, see [`pack_all_counted`]
+    fn pack_all_counted(self, writer: &mut impl io::Write) -> pack::Result<usize>
+    where
+        Self: ExactSizeIterator,
+    {
+        pack_all_counted(self, writer)
+    }
+}
+
+impl<I: Iterator> PackAll for I where I::Item: Pack {}
+
+/// Default number of items per chunk written by [`pack_stream`]
+pub const STREAM_CHUNK_LEN: usize = 1024;
+
+/// Packs an iterator of unknown length as a chunked stream
+///
+/// The items are grouped into chunks of up to [`STREAM_CHUNK_LEN`] items.
+/// Every chunk starts with its item count as `u32` and the stream ends with
+/// an empty chunk. Only a single chunk is buffered at a time, so streams of
+/// any length can be packed incrementally. Use [`unpack_stream`] to read
+/// the items back
+pub fn pack_stream<T: Pack>(
+    items: impl IntoIterator<Item = T>,
+    writer: &mut impl io::Write,
+) -> pack::Result<usize> {
+    pack_stream_chunked(items, writer, STREAM_CHUNK_LEN)
+}
+
+/// Packs an iterator of unknown length as a chunked stream with the given
+/// maximum number of items per chunk, see [`pack_stream`]
+pub fn pack_stream_chunked<T: Pack>(
+    items: impl IntoIterator<Item = T>,
+    writer: &mut impl io::Write,
+    chunk_len: usize,
+) -> pack::Result<usize> {
+    let chunk_len = chunk_len.clamp(1, u32::MAX as usize);
+    let mut items = items.into_iter().peekable();
+    let mut buffer = Vec::new();
+    let mut written = 0;
+
+    while items.peek().is_some() {
+        buffer.clear();
+        let mut len: u32 = 0;
+
+        for item in items.by_ref().take(chunk_len) {
+            item.pack_into(&mut buffer)?;
+            len += 1;
+        }
+
+        written += len.pack_into(writer)?;
+        writer.write_all(&buffer)?;
+        written += buffer.len();
+    }
+
+    written += 0u32.pack_into(writer)?;
+    Ok(written)
+}
+
+/// Creates an iterator over the items of a stream written by [`pack_stream`]
+pub fn unpack_stream<T: Unpack, R: io::Read>(reader: R) -> UnpackStream<T, R> {
+    UnpackStream {
+        reader,
+        remaining: 0,
+        finished: false,
+        marker: PhantomData,
+    }
+}
+
+/// Iterator over the items of a chunked stream, see [`unpack_stream`]
+///
+/// The iteration finishes after the terminating empty chunk or after the
+/// first error. The bytesource is not read beyond the terminating chunk
+pub struct UnpackStream<T, R> {
+    reader: R,
+    remaining: u32,
+    finished: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T, R> UnpackStream<T, R> {
+    /// Returns the underlying bytesource
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<T: Unpack, R: io::Read> UnpackStream<T, R> {
+    fn next_item(&mut self) -> Result<Option<T>> {
+        if self.remaining == 0 {
+            self.remaining = u32::unpack_from(&mut self.reader)?;
+
+            if self.remaining == 0 {
+                return Ok(None);
+            }
+        }
+
+        self.remaining -= 1;
+        T::unpack_from(&mut self.reader).map(Some)
+    }
+}
+
+impl<T: Unpack, R: io::Read> Iterator for UnpackStream<T, R> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.next_item();
+        self.finished = !matches!(result, Ok(Some(_)));
+        result.transpose()
+    }
+}
+
+impl<T: Unpack, R: io::Read> FusedIterator for UnpackStream<T, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iterate_values() {
+        let bytes = [0x00, 0x01, 0x00, 0x02];
+        let values: Vec<u16> = UnpackIter::new(bytes.as_ref())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(values, [1, 2]);
+    }
+
+    #[test]
+    fn iterate_empty() {
+        let bytes: [u8; 0] = [];
+        let mut iter = UnpackIter::<u32, _>::new(bytes.as_ref());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn iterate_truncated() {
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0x61, 0x00, 0x00];
+        let mut iter = UnpackIter::<String, _>::new(bytes.as_ref());
+        assert_eq!(iter.next().unwrap().unwrap(), "a");
+
+        match iter.next() {
+            Some(Err(Error::IO(error))) => assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof),
+            _ => panic!("expected a truncation error"),
+        }
+
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn pack_all_values() {
+        let mut bytes = Vec::new();
+        let written = pack_all((1..=2).map(|x: u16| x * 2), &mut bytes).unwrap();
+        assert_eq!(written, 4);
+        assert_eq!(bytes, [0x00, 0x02, 0x00, 0x04]);
+    }
+
+    #[test]
+    fn pack_all_counted_values() {
+        let values: [u8; 2] = [1, 2];
+        let mut bytes = Vec::new();
+        values.iter().pack_all_counted(&mut bytes).unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x02, 0x01, 0x02]);
+
+        let value = Vec::<u8>::unpack_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(value, values);
+    }
+
+    #[test]
+    fn pack_all_counted_overflow() {
+        struct Endless;
+
+        impl Iterator for Endless {
+            type Item = u8;
+
+            fn next(&mut self) -> Option<u8> {
+                Some(0)
+            }
+        }
+
+        impl ExactSizeIterator for Endless {
+            fn len(&self) -> usize {
+                (u32::MAX as usize).wrapping_add(1)
+            }
+        }
+
+        if usize::BITS > 32 {
+            let mut bytes = Vec::new();
+            let result = Endless.pack_all_counted(&mut bytes);
+            assert!(matches!(result, Err(pack::Error::LengthOverflow(_))));
+            assert!(bytes.is_empty());
+        }
+    }
+
+    #[test]
+    fn pack_all_round_trip() {
+        let mut bytes = Vec::new();
+        ["a", "bc"].into_iter().pack_all(&mut bytes).unwrap();
+        let values: Vec<String> = UnpackIter::new(bytes.as_slice())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(values, ["a", "bc"]);
+    }
+
+    #[test]
+    fn pack_stream_chunks() {
+        let mut bytes = Vec::new();
+        let written = pack_stream_chunked(1..=3u8, &mut bytes, 2).unwrap();
+        assert_eq!(written, bytes.len());
+        assert_eq!(
+            bytes,
+            [
+                0x00, 0x00, 0x00, 0x02, 0x01, 0x02, 0x00, 0x00, 0x00, 0x01, 0x03, 0x00, 0x00, 0x00,
+                0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn pack_stream_empty() {
+        let mut bytes = Vec::new();
+        pack_stream(std::iter::empty::<u8>(), &mut bytes).unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn unpack_stream_items() {
+        let mut bytes = Vec::new();
+        pack_stream_chunked((0..10u16).map(|x| x * 3), &mut bytes, 4).unwrap();
+        bytes.push(0xFF);
+
+        let mut reader = bytes.as_slice();
+        let values: Vec<u16> = unpack_stream(&mut reader).collect::<Result<_>>().unwrap();
+        assert_eq!(values, (0..10).map(|x| x * 3).collect::<Vec<_>>());
+        assert_eq!(reader, [0xFF]);
+    }
+
+    #[test]
+    fn unpack_stream_without_terminator() {
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0x01];
+        let mut iter = unpack_stream::<u8, _>(bytes.as_ref());
+        assert_eq!(iter.next().unwrap().unwrap(), 1);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn unpack_all_values() {
+        let bytes = [0x00, 0x01, 0x00, 0x02];
+        let values: Vec<u16> = unpack_all(bytes.as_ref()).unwrap();
+        assert_eq!(values, [1, 2]);
+    }
+
+    #[test]
+    fn unpack_all_truncated() {
+        let bytes = [0x00, 0x01, 0x00];
+        let result = unpack_all::<u16>(bytes.as_ref());
+        assert!(matches!(
+            result,
+            Err(Error::IO(error)) if error.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+//! Packing of arbitrary types through their string representation
+//!
+//! Third-party types without `Pack` and `Unpack` implementations often
+//! implement `Display` and `FromStr` (e.g. identifiers or addresses).
+//! Wrapping them in [`Stringly`] packs them as their string representation:
+//!
+//! ```
+//! use serial_container::pack::Pack;
+//! use serial_container::stringly::Stringly;
+//! use serial_container::unpack::Unpack;
+//! use std::net::SocketAddr;
+//!
+//! let address: SocketAddr = "127.0.0.1:80".parse().unwrap();
+//! let bytes = Stringly(address).pack_to_vec().unwrap();
+//! assert_eq!(bytes, "127.0.0.1:80".pack_to_vec().unwrap());
+//! assert_eq!(Stringly::<SocketAddr>::unpack_exact(&bytes).unwrap().0, address);
+//! ```
+
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use std::fmt::Display;
+use std::io;
+use std::str::FromStr;
+
+/// Wrapper packing a value as its `Display` output like a `str` and
+/// unpacking it with `FromStr`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Stringly<T>(pub T);
+
+impl<T: Display> Pack for Stringly<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        self.0.to_string().pack_into(writer)
+    }
+}
+
+/// Fails with `Error::InvalidValue` containing the message of the parse
+/// error if the packed string is not a valid representation
+impl<T: FromStr> Unpack for Stringly<T>
+where
+    T::Err: Display,
+{
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let value = String::unpack_from(reader)?;
+        value
+            .parse()
+            .map(Stringly)
+            .map_err(|error: T::Err| Error::InvalidValue(format!("{:?}: {}", value, error)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stringly_round_trip() {
+        let bytes = Stringly(-12i64).pack_to_vec().unwrap();
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0x03, b'-', b'1', b'2']);
+        assert_eq!(Stringly::<i64>::unpack_exact(&bytes).unwrap().0, -12);
+    }
+
+    #[test]
+    fn unpack_invalid_representation() {
+        let bytes = "1.x".pack_to_vec().unwrap();
+        let result = Stringly::<f64>::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
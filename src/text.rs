@@ -0,0 +1,244 @@
+//! Text encodings of packed values
+//!
+//! Packed bytes can not be embedded into JSON configs, URLs or log lines
+//! directly. The functions in this module pack a value and encode the bytes
+//! as hexadecimal digits or, with the `base64` feature enabled, as Base64
+//!
+//! ```
+//! use serial_container::text::{pack_to_hex, unpack_from_hex};
+//!
+//! let text = pack_to_hex("hi").unwrap();
+//! assert_eq!(text, "000000026869");
+//! assert_eq!(unpack_from_hex::<String>(&text).unwrap(), "hi");
+//! ```
+
+use crate::pack::{self, Pack};
+use crate::unpack::{Error, Result, Unpack};
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Packs a value and encodes the bytes as lowercase hexadecimal digits
+pub fn pack_to_hex<T: Pack + ?Sized>(value: &T) -> pack::Result<String> {
+    let bytes = value.pack_to_vec()?;
+    let mut text = String::with_capacity(bytes.len() * 2);
+
+    for byte in bytes {
+        text.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        text.push(HEX_DIGITS[(byte & 0x0F) as usize] as char);
+    }
+
+    Ok(text)
+}
+
+/// Decodes hexadecimal digits and unpacks a value from the bytes
+///
+/// Upper- and lowercase digits are accepted. Fails with
+/// `Error::InvalidValue` if the text is no valid hexadecimal and with
+/// `Error::TrailingBytes` if the value does not consume all bytes
+pub fn unpack_from_hex<T: Unpack>(text: &str) -> Result<T> {
+    T::unpack_exact(&decode_hex(text)?)
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let digits = text.as_bytes();
+
+    if !digits.len().is_multiple_of(2) {
+        return Err(Error::InvalidValue(String::from(
+            "hexadecimal text has an odd number of digits",
+        )));
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| Ok(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+        .collect()
+}
+
+fn hex_digit(digit: u8) -> Result<u8> {
+    match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'a'..=b'f' => Ok(digit - b'a' + 10),
+        b'A'..=b'F' => Ok(digit - b'A' + 10),
+        _ => Err(Error::InvalidValue(format!(
+            "invalid hexadecimal digit {:?}",
+            digit as char
+        ))),
+    }
+}
+
+#[cfg(feature = "base64")]
+pub use base64::*;
+
+#[cfg(feature = "base64")]
+mod base64 {
+    use crate::pack::{self, Pack};
+    use crate::unpack::{Error, Result, Unpack};
+
+    const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+    /// Packs a value and encodes the bytes as padded Base64 with the
+    /// standard alphabet
+    pub fn pack_to_base64<T: Pack + ?Sized>(value: &T) -> pack::Result<String> {
+        Ok(encode(&value.pack_to_vec()?, STANDARD, true))
+    }
+
+    /// Packs a value and encodes the bytes as unpadded Base64 with the URL
+    /// and filename safe alphabet
+    pub fn pack_to_base64_url<T: Pack + ?Sized>(value: &T) -> pack::Result<String> {
+        Ok(encode(&value.pack_to_vec()?, URL_SAFE, false))
+    }
+
+    /// Decodes Base64 with the standard alphabet and unpacks a value from
+    /// the bytes
+    ///
+    /// Only the canonical encoding is accepted, i.e. the text has to be
+    /// padded and unused bits have to be zero. Fails with
+    /// `Error::InvalidValue` otherwise
+    pub fn unpack_from_base64<T: Unpack>(text: &str) -> Result<T> {
+        T::unpack_exact(&decode(text, STANDARD, true)?)
+    }
+
+    /// Decodes Base64 with the URL and filename safe alphabet and unpacks a
+    /// value from the bytes
+    ///
+    /// Only the canonical encoding is accepted, i.e. the text must not be
+    /// padded and unused bits have to be zero. Fails with
+    /// `Error::InvalidValue` otherwise
+    pub fn unpack_from_base64_url<T: Unpack>(text: &str) -> Result<T> {
+        T::unpack_exact(&decode(text, URL_SAFE, false)?)
+    }
+
+    fn encode(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+        let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+        for chunk in bytes.chunks(3) {
+            let group = chunk.iter().enumerate().fold(0u32, |group, (index, byte)| {
+                group | (*byte as u32) << (16 - 8 * index)
+            });
+
+            for index in 0..=chunk.len() {
+                text.push(alphabet[(group >> (18 - 6 * index) & 0x3F) as usize] as char);
+            }
+
+            if pad {
+                for _i in chunk.len()..3 {
+                    text.push('=');
+                }
+            }
+        }
+
+        text
+    }
+
+    fn decode(text: &str, alphabet: &[u8; 64], padded: bool) -> Result<Vec<u8>> {
+        let symbols = match padded {
+            true => text.trim_end_matches('=').as_bytes(),
+            false => text.as_bytes(),
+        };
+
+        if symbols.len() % 4 == 1
+            || text.len() - symbols.len() > 2
+            || (padded && !text.len().is_multiple_of(4))
+        {
+            return Err(Error::InvalidValue(String::from(
+                "base64 text has an invalid length",
+            )));
+        }
+
+        let mut bytes = Vec::with_capacity(symbols.len() * 3 / 4);
+
+        for chunk in symbols.chunks(4) {
+            let mut group = 0u32;
+
+            for (index, symbol) in chunk.iter().enumerate() {
+                let value = alphabet
+                    .iter()
+                    .position(|candidate| candidate == symbol)
+                    .ok_or_else(|| {
+                        Error::InvalidValue(format!("invalid base64 symbol {:?}", *symbol as char))
+                    })?;
+                group |= (value as u32) << (18 - 6 * index);
+            }
+
+            if group & (0xFF_FFFF >> (8 * (chunk.len() - 1))) != 0 {
+                return Err(Error::InvalidValue(String::from(
+                    "base64 text has non-zero padding bits",
+                )));
+            }
+
+            bytes.extend_from_slice(&group.to_be_bytes()[1..chunk.len()]);
+        }
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        let text = pack_to_hex(&0xABCDu16).unwrap();
+        assert_eq!(text, "abcd");
+        assert_eq!(unpack_from_hex::<u16>("ABcd").unwrap(), 0xABCD);
+    }
+
+    #[test]
+    fn invalid_hex() {
+        assert!(matches!(
+            unpack_from_hex::<u16>("abc"),
+            Err(Error::InvalidValue(_))
+        ));
+        assert!(matches!(
+            unpack_from_hex::<u16>("abcg"),
+            Err(Error::InvalidValue(_))
+        ));
+        assert!(matches!(
+            unpack_from_hex::<u8>("abcd"),
+            Err(Error::TrailingBytes(1))
+        ));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn base64_round_trip() {
+        assert_eq!(pack_to_base64("hi").unwrap(), "AAAAAmhp");
+        assert_eq!(pack_to_base64(&[0xFBu8, 0xFF]).unwrap(), "AAAAAvv/");
+        assert_eq!(pack_to_base64_url(&[0xFBu8, 0xFF]).unwrap(), "AAAAAvv_");
+        assert_eq!(pack_to_base64(&0xFFu8).unwrap(), "/w==");
+        assert_eq!(pack_to_base64_url(&0xFFu8).unwrap(), "_w");
+
+        assert_eq!(unpack_from_base64::<u8>("/w==").unwrap(), 0xFF);
+        assert_eq!(unpack_from_base64_url::<u8>("_w").unwrap(), 0xFF);
+        assert_eq!(unpack_from_base64::<String>("AAAAAmhp").unwrap(), "hi");
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn invalid_base64() {
+        assert!(matches!(
+            unpack_from_base64::<u8>("_w"),
+            Err(Error::InvalidValue(_))
+        ));
+        assert!(matches!(
+            unpack_from_base64::<u8>("AAAAA"),
+            Err(Error::InvalidValue(_))
+        ));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn non_canonical_base64() {
+        for text in ["/w", "/w=", "/w===", "/x==", "AAAAAvv=="] {
+            let result = unpack_from_base64::<Vec<u8>>(text);
+            assert!(matches!(result, Err(Error::InvalidValue(_))), "{}", text);
+        }
+
+        for text in ["_w==", "_x"] {
+            let result = unpack_from_base64_url::<u8>(text);
+            assert!(matches!(result, Err(Error::InvalidValue(_))), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Tag-length-value encoding of extensible structs
+//!
+//! Every field is packed as its tag (`u16`), the length of its packed value
+//! (`u32`) and the packed value itself, preceded by the number of fields as
+//! `u32`. Fields may appear in any order and a reader only looks up the tags
+//! it knows, so third parties can add optional extensions without breaking
+//! existing readers. Unknown fields are kept in the [`TlvRecord`] and are
+//! packed again unchanged, so they survive a pass through an older service
+//!
+//! As `Pack` and `Unpack` cannot be derived, structs use the encoding by
+//! packing a [`TlvRecord`] in their own implementations:
+//!
+//! ```
+//! use serial_container::pack::{self, Pack};
+//! use serial_container::tlv::TlvRecord;
+//! use serial_container::unpack::{Result, Unpack};
+//! use std::io;
+//!
+//! struct Config {
+//!     name: String,
+//!     retries: Option<u8>,
+//! }
+//!
+//! impl Pack for Config {
+//!     fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+//!         let mut record = TlvRecord::new();
+//!         record.insert(1, self.name.as_str())?;
+//!         if let Some(retries) = self.retries {
+//!             record.insert(2, &retries)?;
+//!         }
+//!         record.pack_into(writer)
+//!     }
+//! }
+//!
+//! impl Unpack for Config {
+//!     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+//!         let record = TlvRecord::unpack_from(reader)?;
+//!         Ok(Config {
+//!             name: record.require(1)?,
+//!             retries: record.get(2)?,
+//!         })
+//!     }
+//! }
+//!
+//! let config = Config { name: "a".into(), retries: None };
+//! let bytes = config.pack_to_vec().unwrap();
+//! assert_eq!(Config::unpack_exact(&bytes).unwrap().retries, None);
+//! ```
+
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::collections::BTreeMap;
+use std::io;
+
+/// Set of tagged fields, each holding a packed value
+///
+/// Fields are packed in ascending order of their tags, so equal records
+/// always result in equal bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TlvRecord {
+    fields: BTreeMap<u16, Vec<u8>>,
+}
+
+impl TlvRecord {
+    /// Creates a record without any fields
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Packs a value into the field with the given tag, replacing a
+    /// previous value
+    pub fn insert<T: Pack + ?Sized>(&mut self, tag: u16, value: &T) -> pack::Result<()> {
+        self.fields.insert(tag, value.pack_to_vec()?);
+        Ok(())
+    }
+
+    /// Unpacks the value of the field with the given tag if it is present
+    ///
+    /// Unpacking fails if the value does not consume the whole field
+    pub fn get<T: Unpack>(&self, tag: u16) -> Result<Option<T>> {
+        self.fields
+            .get(&tag)
+            .map(|bytes| T::unpack_exact(bytes))
+            .transpose()
+    }
+
+    /// Unpacks the value of a field that has to be present
+    ///
+    /// Unpacking fails with `Error::InvalidValue` if the field is missing
+    pub fn require<T: Unpack>(&self, tag: u16) -> Result<T> {
+        self.get(tag)?
+            .ok_or_else(|| Error::InvalidValue(format!("missing required field {}", tag)))
+    }
+
+    /// Returns the packed value of the field with the given tag
+    pub fn get_raw(&self, tag: u16) -> Option<&[u8]> {
+        self.fields.get(&tag).map(Vec::as_slice)
+    }
+
+    /// Removes the field with the given tag and returns its packed value
+    pub fn remove(&mut self, tag: u16) -> Option<Vec<u8>> {
+        self.fields.remove(&tag)
+    }
+
+    /// Returns whether a field with the given tag is present
+    pub fn contains(&self, tag: u16) -> bool {
+        self.fields.contains_key(&tag)
+    }
+
+    /// Returns the tags of all present fields in ascending order
+    pub fn tags(&self) -> impl Iterator<Item = u16> + '_ {
+        self.fields.keys().copied()
+    }
+
+    /// Returns the number of fields
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns whether the record has no fields
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl Pack for TlvRecord {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = checked_len(self.fields.len())?.pack_into(writer)?;
+
+        for (tag, bytes) in &self.fields {
+            written += tag.pack_into(writer)?;
+            written += checked_len(bytes.len())?.pack_into(writer)?;
+            writer.write_all(bytes)?;
+            written += bytes.len();
+        }
+
+        Ok(written)
+    }
+}
+
+impl Unpack for TlvRecord {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)?;
+        let mut fields = BTreeMap::new();
+
+        for _i in 0..len {
+            let tag = u16::unpack_from(reader)?;
+            let len = u32::unpack_from(reader)? as usize;
+
+            if fields.insert(tag, read_bytes(reader, len)?).is_some() {
+                return Err(Error::InvalidValue(format!("duplicate field {}", tag)));
+            }
+        }
+
+        Ok(TlvRecord { fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_in_any_order() {
+        let bytes = [
+            0x00, 0x00, 0x00, 0x02, // two fields
+            0x00, 0x07, 0x00, 0x00, 0x00, 0x01, 0x05, // tag 7
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x01, 0x02, // tag 1
+        ];
+        let record = TlvRecord::unpack_exact(&bytes).unwrap();
+        assert_eq!(record.require::<u16>(1).unwrap(), 0x0102);
+        assert_eq!(record.get::<u8>(7).unwrap(), Some(5));
+        assert_eq!(record.get::<u8>(3).unwrap(), None);
+        assert_eq!(record.tags().collect::<Vec<_>>(), [1, 7]);
+    }
+
+    #[test]
+    fn unknown_fields_survive() {
+        let mut record = TlvRecord::new();
+        record.insert(1, &2u16).unwrap();
+        record.insert(900, "extension").unwrap();
+
+        let bytes = record.pack_to_vec().unwrap();
+        let copy = TlvRecord::unpack_exact(&bytes).unwrap();
+        assert_eq!(copy, record);
+        assert_eq!(copy.pack_to_vec().unwrap(), bytes);
+    }
+
+    #[test]
+    fn invalid_fields() {
+        let mut record = TlvRecord::new();
+        record.insert(1, &2u16).unwrap();
+        assert!(matches!(
+            record.require::<u8>(2),
+            Err(Error::InvalidValue(_))
+        ));
+        assert!(matches!(record.get::<u8>(1), Err(Error::TrailingBytes(1))));
+
+        let bytes = [
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+        let result = TlvRecord::unpack_exact(&bytes);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,139 @@
+//! Instrumentation of the top-level pack and unpack methods
+//!
+//! With the `tracing` feature enabled, every value packed or unpacked as a
+//! whole (e.g. with `pack_to_vec` or `unpack_exact`) is wrapped in a `pack`
+//! or `unpack` span carrying its type name. When the operation completes, an
+//! event with the number of bytes and the elapsed time in nanoseconds is
+//! emitted at debug level, failures are emitted at warn level. Nested fields
+//! are not instrumented, so the overhead is paid once per message
+//!
+//! Without the feature, the operations are called directly
+
+use crate::pack;
+use crate::unpack::Result;
+
+/// Runs the packing of a whole value of type `T` and traces its outcome
+#[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+pub(crate) fn pack<T: ?Sized>(
+    operation: impl FnOnce() -> pack::Result<usize>,
+) -> pack::Result<usize> {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::debug_span!("pack", r#type = std::any::type_name::<T>());
+        let _entered = span.enter();
+        let start = std::time::Instant::now();
+        let result = operation();
+        finish(start, result.as_ref().map(|bytes| *bytes));
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    operation()
+}
+
+/// Runs the unpacking of a whole value of type `T` and traces its outcome
+///
+/// The operation returns the value together with the number of bytes it
+/// consumed
+#[cfg_attr(not(feature = "tracing"), allow(clippy::extra_unused_type_parameters))]
+pub(crate) fn unpack<T, R>(operation: impl FnOnce() -> Result<(R, usize)>) -> Result<(R, usize)> {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::debug_span!("unpack", r#type = std::any::type_name::<T>());
+        let _entered = span.enter();
+        let start = std::time::Instant::now();
+        let result = operation();
+        finish(start, result.as_ref().map(|(_, bytes)| *bytes));
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    operation()
+}
+
+#[cfg(feature = "tracing")]
+fn finish(start: std::time::Instant, result: std::result::Result<usize, &impl std::fmt::Display>) {
+    let duration_ns = start.elapsed().as_nanos() as u64;
+
+    match result {
+        Ok(bytes) => tracing::debug!(bytes, duration_ns, "completed"),
+        Err(error) => tracing::warn!(%error, duration_ns, "failed"),
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::pack::Pack;
+    use crate::unpack::Unpack;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Recorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let name = span.metadata().name();
+            self.0.lock().unwrap().push(format!("span {}", name));
+            span.record(&mut self.clone());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    fn record(operation: impl FnOnce()) -> Vec<String> {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), operation);
+        let lines = recorder.0.lock().unwrap().clone();
+        lines
+    }
+
+    #[test]
+    fn pack_emits_span_and_event() {
+        let lines = record(|| {
+            7u32.pack_to_vec().unwrap();
+        });
+        assert_eq!(lines[0], "span pack");
+        assert_eq!(lines[1], "type=\"u32\"");
+        assert_eq!(lines[2], "message=completed");
+        assert_eq!(lines[3], "bytes=4");
+        assert!(lines[4].starts_with("duration_ns="));
+    }
+
+    #[test]
+    fn unpack_emits_failure() {
+        let lines = record(|| {
+            assert!(u32::unpack_exact(&[1, 2]).is_err());
+        });
+        assert_eq!(lines[0], "span unpack");
+        assert_eq!(lines[2], "message=failed");
+        assert!(lines[3].starts_with("error="));
+    }
+}
@@ -0,0 +1,476 @@
+//! Blocking transport of packed values as length-prefixed frames
+//!
+//! Besides data frames, a [`MessageStream`] exchanges [`Control`] frames
+//! for keepalive and round trip measurements. Control frames use the
+//! otherwise invalid payload lengths from `0xFFFF_FFFD` upwards as marker,
+//! so the format of data frames is not affected
+
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{Error, Result, Unpack};
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, Read};
+use std::marker::PhantomData;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// Default limit for the payload length of a single frame (8 MiB)
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+const HEARTBEAT: u32 = 0xFFFF_FFFD;
+const PONG: u32 = 0xFFFF_FFFE;
+const PING: u32 = 0xFFFF_FFFF;
+
+/// Control frame exchanged by [`MessageStream`]s
+///
+/// Packed as its marker (`u32`), followed by the nonce (`u64`) for pings
+/// and pongs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Control {
+    /// Request to answer with a pong carrying the same nonce
+    Ping(u64),
+    /// Answer to a ping
+    Pong(u64),
+    /// Sign of life that is not answered
+    Heartbeat,
+}
+
+impl Pack for Control {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        match self {
+            Control::Ping(nonce) => Ok(PING.pack_into(writer)? + nonce.pack_into(writer)?),
+            Control::Pong(nonce) => Ok(PONG.pack_into(writer)? + nonce.pack_into(writer)?),
+            Control::Heartbeat => HEARTBEAT.pack_into(writer),
+        }
+    }
+}
+
+impl Unpack for Control {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        match u32::unpack_from(reader)? {
+            PING => Ok(Control::Ping(u64::unpack_from(reader)?)),
+            PONG => Ok(Control::Pong(u64::unpack_from(reader)?)),
+            HEARTBEAT => Ok(Control::Heartbeat),
+            marker => Err(Error::InvalidValue(format!(
+                "unknown control marker {:#X}",
+                marker
+            ))),
+        }
+    }
+}
+
+/// Connection sending and receiving values of type `T`
+///
+/// Every value is sent as a frame consisting of the payload length as `u32`
+/// followed by the packed value, the same format the `tokio-util` codec
+/// uses. Frames longer than the configured maximum are rejected with
+/// `ErrorKind::InvalidData` on both sides, incoming ones before any buffer
+/// is reserved for them
+///
+/// Control frames are handled by `recv` while it waits for the next value:
+/// pings are answered with a pong, pongs and heartbeats only update the
+/// time of the last received frame
+///
+/// A frame interrupted by a timeout is kept in a buffer and completed by the
+/// next call to `recv`, so timeouts never desynchronize the stream. After a
+/// frame was rejected for its length, every further call to `recv` fails
+/// the same way, because the rest of the stream can not be trusted anymore
+///
+/// ```no_run
+/// use serial_container::transport::MessageStream;
+/// use std::time::{Duration, Instant};
+///
+/// let mut stream = MessageStream::<u64>::connect("127.0.0.1:4000").unwrap();
+/// stream.set_timeout(Some(Duration::from_secs(5))).unwrap();
+/// stream.send(&42).unwrap();
+/// let reply = stream.recv().unwrap();
+/// ```
+pub struct MessageStream<T, S = TcpStream> {
+    stream: S,
+    max_frame_length: usize,
+    incoming: Vec<u8>,
+    last_received: Instant,
+    next_nonce: u64,
+    pending_ping: Option<(u64, Instant)>,
+    round_trip: Option<Duration>,
+    marker: PhantomData<fn(T) -> T>,
+}
+
+impl<T> MessageStream<T, TcpStream> {
+    /// Connects to the given address
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        TcpStream::connect(address).map(Self::new)
+    }
+
+    /// Sets the timeout for sending and receiving a single frame
+    ///
+    /// An operation exceeding the timeout fails with `ErrorKind::WouldBlock`
+    /// or `ErrorKind::TimedOut` depending on the platform. `None` blocks
+    /// indefinitely
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)?;
+        self.stream.set_write_timeout(timeout)
+    }
+}
+
+impl<T, S: io::Read + io::Write> MessageStream<T, S> {
+    /// Wraps a connection accepting frames up to
+    /// [`DEFAULT_MAX_FRAME_LENGTH`]
+    pub fn new(stream: S) -> Self {
+        Self::with_max_frame_length(stream, DEFAULT_MAX_FRAME_LENGTH)
+    }
+
+    /// Wraps a connection accepting frames up to the given payload length
+    ///
+    /// The length is capped below the markers of control frames
+    pub fn with_max_frame_length(stream: S, max_frame_length: usize) -> Self {
+        Self {
+            stream,
+            max_frame_length: max_frame_length.min(HEARTBEAT as usize - 1),
+            incoming: Vec::new(),
+            last_received: Instant::now(),
+            next_nonce: 0,
+            pending_ping: None,
+            round_trip: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the maximum payload length of a single frame
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+
+    /// Returns the time the last frame of any kind was received, or the
+    /// time the stream was created
+    pub fn last_received(&self) -> Instant {
+        self.last_received
+    }
+
+    /// Returns the round trip time measured by the last answered ping
+    pub fn round_trip(&self) -> Option<Duration> {
+        self.round_trip
+    }
+
+    /// Sends a ping and returns its nonce
+    ///
+    /// The pong is received by a later call to `recv`, which then updates
+    /// the round trip time
+    pub fn ping(&mut self) -> io::Result<u64> {
+        let nonce = self.next_nonce;
+        self.next_nonce += 1;
+        self.send_control(Control::Ping(nonce))?;
+        self.pending_ping = Some((nonce, Instant::now()));
+        Ok(nonce)
+    }
+
+    /// Sends a heartbeat, e.g. when nothing else was sent for a while
+    pub fn heartbeat(&mut self) -> io::Result<()> {
+        self.send_control(Control::Heartbeat)
+    }
+
+    fn send_control(&mut self, control: Control) -> io::Result<()> {
+        self.stream.write_all(&control.pack_to_vec()?)?;
+        self.stream.flush()
+    }
+
+    /// Handles a received control frame
+    fn handle_control(&mut self, control: Control) -> io::Result<()> {
+        match control {
+            Control::Ping(nonce) => self.send_control(Control::Pong(nonce)),
+            Control::Pong(nonce) => {
+                if let Some((pending, sent)) = self.pending_ping {
+                    if pending == nonce {
+                        self.round_trip = Some(sent.elapsed());
+                        self.pending_ping = None;
+                    }
+                }
+                Ok(())
+            }
+            Control::Heartbeat => Ok(()),
+        }
+    }
+
+    /// Returns a reference to the underlying connection
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the underlying connection
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Returns the underlying connection
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<T: Pack, S: io::Read + io::Write> MessageStream<T, S> {
+    /// Sends a value as a single frame and flushes the connection
+    pub fn send(&mut self, value: &T) -> pack::Result<()> {
+        let mut frame = vec![0x00; 4];
+        value.pack_into(&mut frame)?;
+
+        let len = frame.len() - 4;
+        if len > self.max_frame_length {
+            return Err(pack::Error::IO(frame_too_long(len, self.max_frame_length)));
+        }
+
+        frame[..4].copy_from_slice(&checked_len(len)?.to_be_bytes());
+        self.stream.write_all(&frame)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+}
+
+impl<T: Unpack, S: io::Read + io::Write> MessageStream<T, S> {
+    /// Receives the next data frame and unpacks its value
+    ///
+    /// Control frames received in the meantime are handled transparently.
+    /// Fails with `Error::TrailingBytes` if the value does not consume the
+    /// whole frame
+    pub fn recv(&mut self) -> Result<T> {
+        loop {
+            self.fill(4)?;
+            let len = u32::unpack_from(&mut &self.incoming[..4])?;
+            self.last_received = Instant::now();
+
+            let frame_len = match len {
+                PING | PONG => 12,
+                HEARTBEAT => 4,
+                len if len as usize > self.max_frame_length => {
+                    return Err(Error::IO(frame_too_long(
+                        len as usize,
+                        self.max_frame_length,
+                    )));
+                }
+                len => 4 + len as usize,
+            };
+
+            self.fill(frame_len)?;
+            let frame = self.incoming.drain(..frame_len).collect::<Vec<_>>();
+
+            match len {
+                PING | PONG | HEARTBEAT => self.handle_control(Control::unpack_exact(&frame)?)?,
+                _ => return T::unpack_exact(&frame[4..]),
+            }
+        }
+    }
+
+    /// Reads until `len` bytes of the current frame are buffered
+    ///
+    /// Bytes read before an error stay in the buffer for the next call
+    fn fill(&mut self, len: usize) -> Result<()> {
+        let missing = len.saturating_sub(self.incoming.len());
+        (&mut self.stream)
+            .take(missing as u64)
+            .read_to_end(&mut self.incoming)?;
+
+        if self.incoming.len() < len {
+            return Err(Error::IO(io::ErrorKind::UnexpectedEof.into()));
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, S: Debug> Debug for MessageStream<T, S> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_struct("MessageStream")
+            .field("stream", &self.stream)
+            .field("max_frame_length", &self.max_frame_length)
+            .field("last_received", &self.last_received)
+            .field("round_trip", &self.round_trip)
+            .finish()
+    }
+}
+
+fn frame_too_long(len: usize, max: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "frame of {} bytes exceeds the maximum of {} bytes",
+            len, max
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn send_and_recv_frames() {
+        let mut stream = MessageStream::<u16, _>::new(Cursor::new(Vec::new()));
+        stream.send(&2).unwrap();
+        stream.send(&3).unwrap();
+        assert_eq!(
+            stream.get_ref().get_ref(),
+            &[0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03]
+        );
+
+        stream.get_mut().set_position(0);
+        assert_eq!(stream.recv().unwrap(), 2);
+        assert_eq!(stream.recv().unwrap(), 3);
+        assert!(stream.recv().is_err());
+    }
+
+    #[test]
+    fn reject_long_frames() {
+        let mut stream =
+            MessageStream::<[u8; 4], _>::with_max_frame_length(Cursor::new(Vec::new()), 4);
+        let result = stream.send(&[1, 2, 3, 4]);
+        assert!(
+            matches!(result, Err(pack::Error::IO(error)) if error.kind() == io::ErrorKind::InvalidData)
+        );
+        assert!(stream.get_ref().get_ref().is_empty());
+
+        let bytes = vec![0xFF, 0xFF, 0xFF, 0xFF];
+        let mut stream = MessageStream::<u8, _>::with_max_frame_length(Cursor::new(bytes), 4);
+        assert!(matches!(stream.recv(), Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn tcp_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (connection, _) = listener.accept().unwrap();
+            let mut stream = MessageStream::<u32, _>::new(connection);
+            let value = stream.recv().unwrap();
+            stream.send(&(value + 1)).unwrap();
+        });
+
+        let mut stream = MessageStream::<u32>::connect(address).unwrap();
+        stream.set_timeout(Some(Duration::from_secs(5))).unwrap();
+        stream.send(&41).unwrap();
+        assert_eq!(stream.recv().unwrap(), 42);
+        server.join().unwrap();
+    }
+
+    struct Duplex {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl io::Read for Duplex {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buffer)
+        }
+    }
+
+    impl io::Write for Duplex {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            self.output.write(buffer)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Interrupted {
+        reads: Vec<io::Result<Vec<u8>>>,
+    }
+
+    impl io::Read for Interrupted {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            match self.reads.pop() {
+                Some(Ok(mut bytes)) => {
+                    let len = bytes.len().min(buffer.len());
+                    buffer[..len].copy_from_slice(&bytes[..len]);
+
+                    if len < bytes.len() {
+                        self.reads.push(Ok(bytes.split_off(len)));
+                    }
+
+                    Ok(len)
+                }
+                Some(Err(error)) => Err(error),
+                None => Ok(0),
+            }
+        }
+    }
+
+    impl io::Write for Interrupted {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            Ok(buffer.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn resume_after_timeout() {
+        let timeout = || Err(io::ErrorKind::WouldBlock.into());
+        let mut reads = vec![
+            Ok(vec![0x00, 0x00]),
+            timeout(),
+            Ok(vec![0x00, 0x02, 0x01]),
+            timeout(),
+            Ok(vec![0x02, 0x00, 0x00]),
+            Ok(vec![0x00, 0x02, 0x03, 0x04]),
+        ];
+        reads.reverse();
+        let mut stream = MessageStream::<u16, _>::new(Interrupted { reads });
+
+        assert!(matches!(stream.recv(), Err(Error::IO(_))));
+        assert!(matches!(stream.recv(), Err(Error::IO(_))));
+        assert_eq!(stream.recv().unwrap(), 0x0102);
+        assert_eq!(stream.recv().unwrap(), 0x0304);
+        assert!(matches!(stream.recv(), Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn control_frames() {
+        let input = [Control::Ping(7), Control::Heartbeat, Control::Pong(0)]
+            .iter()
+            .flat_map(|control| control.pack_to_vec().unwrap())
+            .chain([0x00, 0x00, 0x00, 0x01, 0x05])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            input[..12],
+            [0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 7]
+        );
+
+        let mut stream = MessageStream::<u8, _>::new(Duplex {
+            input: Cursor::new(input),
+            output: Vec::new(),
+        });
+        assert_eq!(stream.ping().unwrap(), 0);
+        assert_eq!(stream.recv().unwrap(), 5);
+        assert!(stream.round_trip().is_some());
+
+        let mut output = stream.get_ref().output.as_slice();
+        assert_eq!(Control::unpack_from(&mut output).unwrap(), Control::Ping(0));
+        assert_eq!(Control::unpack_exact(output).unwrap(), Control::Pong(7));
+    }
+
+    #[test]
+    fn ping_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (connection, _) = listener.accept().unwrap();
+            let mut stream = MessageStream::<u32, _>::new(connection);
+            assert_eq!(stream.recv().unwrap(), 1);
+            stream.send(&2).unwrap();
+        });
+
+        let mut stream = MessageStream::<u32>::connect(address).unwrap();
+        stream.ping().unwrap();
+        stream.send(&1).unwrap();
+        assert_eq!(stream.recv().unwrap(), 2);
+        assert!(stream.round_trip().is_some());
+        server.join().unwrap();
+    }
+}
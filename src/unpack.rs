@@ -1,3 +1,5 @@
+use crate::pack;
+use crate::trace;
 use std::collections::binary_heap::*;
 use std::collections::btree_map::*;
 use std::collections::btree_set::*;
@@ -5,9 +7,14 @@ use std::collections::hash_map::*;
 use std::collections::hash_set::*;
 use std::error;
 use std::fmt::{self, Display, Formatter};
-use std::io;
+use std::fs::File;
+use std::io::{self, Read};
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::num::*;
+use std::path::Path;
+use std::ptr;
 use std::rc::Rc;
+use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 use std::sync::Arc;
 
@@ -18,7 +25,19 @@ use std::sync::Arc;
 ///
 /// It is not possible to derive this trait, because deserialization may be
 /// sensitive to order and endianness. (Big endianness is assumed for all primitives)
+///
+/// With the `tracing` feature enabled, `unpack_from_slice`, `unpack_exact` and
+/// `unpack_framed` emit a span and an event with the type name, the number of
+/// bytes and the elapsed time
 pub trait Unpack {
+    /// Number of bytes every packed value of this type occupies, if that
+    /// number is fixed
+    ///
+    /// Sequences of such values are read with a single call and decoded
+    /// from memory instead of being read element by element
+    #[doc(hidden)]
+    const PACKED_SIZE: Option<usize> = None;
+
     /// Tries to deserialize this struct from a given sequence of bytes
     ///
     /// Deserialization may fail for one of these reasons:
@@ -28,19 +47,212 @@ pub trait Unpack {
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self>
     where
         Self: Sized;
+
+    /// Decodes consecutive values of a fixed packed size from memory
+    ///
+    /// Numeric types override this to swap the byte order of the whole
+    /// region in one pass, which the compiler can vectorize
+    #[doc(hidden)]
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let mut remaining = bytes;
+
+        while !remaining.is_empty() {
+            target.push(Self::unpack_from_buffered(&mut remaining)?);
+        }
+
+        Ok(())
+    }
+
+    /// Tries to deserialize this struct from a buffered bytesource
+    ///
+    /// Primitives are decoded directly from the buffer of the reader instead
+    /// of copying them out with a separate `read_exact` call per field, which
+    /// is considerably faster when unpacking many small values. The result is
+    /// always the same as with `unpack_from`
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::unpack_from(reader)
+    }
+
+    /// Tries to deserialize a struct from a given sequence of bytes into
+    /// this existing value
+    ///
+    /// Collections and strings override this method to clear and reuse
+    /// their allocated capacity, so decoding successive records into the
+    /// same value does not allocate per record. If deserialization fails,
+    /// the value is left in a valid but unspecified state
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *self = Self::unpack_from(reader)?;
+        Ok(())
+    }
+
+    /// Tries to deserialize this struct, or returns its default value if the
+    /// bytesource has already ended
+    ///
+    /// Ending in the middle of the struct still fails with `UnexpectedEof`.
+    /// Hand-written `unpack_from` implementations can read their trailing
+    /// fields with this method to tolerate records truncated between two
+    /// fields, e.g. when recovering a log after a crash or reading records
+    /// written before these fields were added
+    fn unpack_or_default(reader: &mut impl io::Read) -> Result<Self>
+    where
+        Self: Sized + Default,
+    {
+        let mut first = [0x00];
+
+        loop {
+            match reader.read(&mut first) {
+                Ok(0) => return Ok(Self::default()),
+                Ok(_) => return Self::unpack_from(&mut first.as_ref().chain(reader)),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(Error::IO(error)),
+            }
+        }
+    }
+
+    /// Tries to deserialize this struct from the start of a byte slice
+    ///
+    /// Returns the struct together with the bytes behind it, so multiple
+    /// values can be unpacked from a single buffer without a `Cursor`
+    fn unpack_from_slice(bytes: &[u8]) -> Result<(Self, &[u8])>
+    where
+        Self: Sized,
+    {
+        let mut remaining = bytes;
+        let (value, _) = trace::unpack::<Self, _>(|| {
+            let value = Self::unpack_from_buffered(&mut remaining)?;
+            Ok((value, bytes.len() - remaining.len()))
+        })?;
+        Ok((value, remaining))
+    }
+
+    /// Tries to deserialize this struct from a byte slice that has to
+    /// contain exactly this struct
+    ///
+    /// Deserialization fails if bytes are left over, which catches structs
+    /// silently ignoring trailing fields of a newer or corrupted format
+    fn unpack_exact(bytes: &[u8]) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let (value, remaining) = Self::unpack_from_slice(bytes)?;
+
+        if !remaining.is_empty() {
+            return Err(Error::TrailingBytes(remaining.len()));
+        }
+
+        Ok(value)
+    }
+
+    /// Tries to deserialize this struct from a frame written by `pack_framed`
+    ///
+    /// Exactly the number of bytes announced by the frame header is read.
+    /// Deserialization fails if the struct does not consume the whole frame
+    /// or if the bytesource ends before the frame is complete
+    fn unpack_framed(reader: &mut impl io::Read) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let (value, _) = trace::unpack::<Self, _>(|| {
+            let len = u32::unpack_from(reader)? as usize;
+            let bytes = read_bytes(reader, len)?;
+            let mut remaining = bytes.as_slice();
+            let value = Self::unpack_from(&mut remaining)?;
+
+            if !remaining.is_empty() {
+                return Err(Error::TrailingBytes(remaining.len()));
+            }
+
+            Ok((value, 4 + len))
+        })?;
+        Ok(value)
+    }
+
+    /// Tries to deserialize this struct from the file at the given path
+    ///
+    /// The file is read through a buffer and has to contain exactly one
+    /// packed struct. Deserialization fails if bytes are left over
+    fn unpack_from_file(path: impl AsRef<Path>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut reader = io::BufReader::new(File::open(path)?);
+        let value = Self::unpack_from_buffered(&mut reader)?;
+        let trailing = io::copy(&mut reader, &mut io::sink())?;
+
+        if trailing > 0 {
+            return Err(Error::TrailingBytes(trailing as usize));
+        }
+
+        Ok(value)
+    }
+
+    /// Tries to deserialize this struct from a buffer of the `bytes` crate
+    ///
+    /// The buffer is advanced past the consumed bytes, so consecutive calls
+    /// unpack consecutive values without copying them out first
+    #[cfg(feature = "bytes")]
+    fn unpack_from_buf(buffer: &mut impl bytes::Buf) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::unpack_from_buffered(&mut bytes::Buf::reader(buffer))
+    }
 }
 
 /// Error that may occur during deserialization
 ///
-/// There are three possible reasons deserialization may fail:
+/// These are the possible reasons deserialization may fail:
 /// - any IO-Error ocurred (ErrorKind::Interrupted is ignored)
 /// - a string contained invalid UTF8 contained
+/// - a borrowed string contained invalid UTF8 code
 /// - a custom error previously defined ocurred
+/// - bytes were left over after a value that should have consumed them all
+/// - a value announced more elements or bytes than its fixed capacity can
+///   hold
+/// - a fixed-size array announced a different number of elements
+/// - a checksum did not match the bytes it was computed over
+/// - a value was well-formed but not valid for its type
+/// - an encrypted value could not be authenticated
+/// - a signature did not match the signed value
+/// - a format version is not supported
+/// - a value tried to read more bytes than its budget allowed
+/// - a custom error carrying a numeric code for the wire occurred
 #[derive(Debug)]
 pub enum Error {
     IO(io::Error),
     UTF8(FromUtf8Error),
-    Custom(Box<dyn error::Error>),
+    UTF8Ref(Utf8Error),
+    Custom(Box<dyn error::Error + Send + Sync>),
+    TrailingBytes(usize),
+    CapacityExceeded {
+        len: usize,
+        capacity: usize,
+    },
+    LengthMismatch {
+        expected: usize,
+        found: usize,
+    },
+    ChecksumMismatch,
+    InvalidValue(String),
+    AuthenticationFailed,
+    SignatureMismatch,
+    UnsupportedVersion(u16),
+    LimitExceeded {
+        limit: usize,
+    },
+    Coded {
+        code: u16,
+        error: Box<dyn error::Error + Send + Sync>,
+    },
 }
 
 impl Display for Error {
@@ -49,313 +261,884 @@ impl Display for Error {
         match self {
             IO(error) => error.fmt(destination),
             UTF8(error) => error.fmt(destination),
+            UTF8Ref(error) => error.fmt(destination),
             Custom(error) => error.fmt(destination),
+            TrailingBytes(len) => write!(destination, "{} trailing bytes after value", len),
+            CapacityExceeded { len, capacity } => write!(
+                destination,
+                "length {} exceeds the capacity of {}",
+                len, capacity
+            ),
+            LengthMismatch { expected, found } => write!(
+                destination,
+                "expected {} elements but found {}",
+                expected, found
+            ),
+            ChecksumMismatch => write!(destination, "checksum does not match the payload"),
+            InvalidValue(reason) => write!(destination, "invalid value: {}", reason),
+            AuthenticationFailed => {
+                write!(destination, "encrypted value could not be authenticated")
+            }
+            SignatureMismatch => write!(destination, "signature does not match the payload"),
+            UnsupportedVersion(version) => write!(destination, "unsupported version {}", version),
+            LimitExceeded { limit } => {
+                write!(destination, "value exceeds the limit of {} bytes", limit)
+            }
+            Coded { code, error } => write!(destination, "error {}: {}", code, error),
+        }
+    }
+}
+
+impl Error {
+    /// Creates a custom error carrying a numeric code, e.g. the code of the
+    /// error response a protocol sends for this failure
+    pub fn coded(code: u16, error: impl Into<Box<dyn error::Error + Send + Sync>>) -> Self {
+        Error::Coded {
+            code,
+            error: error.into(),
+        }
+    }
+
+    /// Returns the numeric code of a coded error
+    pub fn code(&self) -> Option<u16> {
+        match self {
+            Error::Coded { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error if it is of the given type
+    ///
+    /// Looks into custom and coded errors, the inner error of IO errors and
+    /// the UTF8 errors, so callers do not have to match on the `Display`
+    /// output of an error
+    pub fn downcast_ref<E: error::Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Error::IO(error) => error.get_ref()?.downcast_ref(),
+            Error::UTF8(error) => (error as &dyn error::Error).downcast_ref(),
+            Error::UTF8Ref(error) => (error as &dyn error::Error).downcast_ref(),
+            Error::Custom(error) | Error::Coded { error, .. } => error.downcast_ref(),
+            _ => None,
         }
     }
 }
 
 impl error::Error for Error {}
 
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::IO(error)
+    }
+}
+
+impl From<pack::Error> for Error {
+    fn from(error: pack::Error) -> Self {
+        match error {
+            pack::Error::IO(error) => Error::IO(error),
+            pack::Error::Custom(error) => Error::Custom(error),
+            pack::Error::InvalidValue(reason) => Error::InvalidValue(reason),
+            error @ pack::Error::LengthOverflow(_) => Error::InvalidValue(error.to_string()),
+            pack::Error::Coded { code, error } => Error::Coded { code, error },
+        }
+    }
+}
+
 /// Wrapper for a deserialization result
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Tries to deserialize a packed sequence (e.g. a packed `Vec<T>`) and
+/// appends its elements to an existing collection
+///
+/// This allows merging many packed chunks into one collection without
+/// temporary vectors. Returns the number of appended elements. If
+/// deserialization fails, the elements decoded before the failure have
+/// already been appended
+pub fn unpack_extend<T: Unpack>(
+    reader: &mut impl io::Read,
+    target: &mut impl Extend<T>,
+) -> Result<usize> {
+    let len = u32::unpack_from(reader)? as usize;
+    let mut appended = 0;
+    let mut failure = None;
+
+    target.extend((0..len).map_while(|_| match T::unpack_from(reader) {
+        Ok(value) => {
+            appended += 1;
+            Some(value)
+        }
+        Err(error) => {
+            failure = Some(error);
+            None
+        }
+    }));
+
+    match failure {
+        Some(error) => Err(error),
+        None => Ok(appended),
+    }
+}
+
+/// Tries to deserialize a value that may read at most `max_bytes` bytes
+///
+/// Fails with `Error::LimitExceeded` as soon as the value tries to read
+/// beyond its budget, so a request handler never reads more than the budget
+/// of a runaway or malicious payload. Length prefixes are still checked
+/// element by element, so collections announcing more elements than fit into
+/// the budget fail once the budget is used up
+pub fn unpack_limited<T: Unpack>(reader: &mut impl io::Read, max_bytes: usize) -> Result<T> {
+    let mut limited = Limited {
+        inner: reader,
+        remaining: max_bytes,
+        exceeded: false,
+    };
+
+    match T::unpack_from(&mut limited) {
+        Err(_) if limited.exceeded => Err(Error::LimitExceeded { limit: max_bytes }),
+        result => result,
+    }
+}
+
+/// Reader failing once more than a given number of bytes is requested
+struct Limited<'a, R> {
+    inner: &'a mut R,
+    remaining: usize,
+    exceeded: bool,
+}
+
+impl<R: io::Read> io::Read for Limited<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 && !buffer.is_empty() {
+            self.exceeded = true;
+            return Err(io::Error::other("read limit exceeded"));
+        }
+
+        let len = buffer.len().min(self.remaining);
+        let read = self.inner.read(&mut buffer[..len])?;
+        self.remaining -= read;
+        Ok(read)
+    }
+}
+
+/// Upper bound in bytes for the capacity reserved up front for a length
+/// prefix, which is read from untrusted input
+const PREALLOCATION_LIMIT: usize = 64 * 1024;
+
+/// Returns the capacity to reserve for `len` elements announced by a length
+/// prefix, so a forged prefix cannot trigger a huge allocation before the
+/// elements have actually been read
+pub(crate) fn initial_capacity<T>(len: usize) -> usize {
+    len.min(PREALLOCATION_LIMIT / std::mem::size_of::<T>().max(1))
+}
+
+/// Maximum nesting depth of recursive values read from untrusted input
+pub(crate) const MAX_DEPTH: usize = 128;
+
+/// Fails with `Error::InvalidValue` if a value nested `depth` levels deep
+/// exceeds [`MAX_DEPTH`], so hostile input cannot overflow the stack
+pub(crate) fn check_depth(depth: usize) -> Result<()> {
+    if depth > MAX_DEPTH {
+        return Err(Error::InvalidValue(format!(
+            "value is nested deeper than {} levels",
+            MAX_DEPTH
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the error for a packed zero that should be a `NonZero` integer
+fn zero_error() -> Error {
+    Error::InvalidValue("zero is not a valid non-zero integer".into())
+}
+
+/// Reads a fixed number of bytes, directly from the buffer of the reader if
+/// it already holds enough bytes
+fn read_array<const N: usize>(reader: &mut impl io::BufRead) -> Result<[u8; N]> {
+    let mut bytes = [0x00; N];
+
+    match reader.fill_buf() {
+        Ok(buffer) if buffer.len() >= N => {
+            bytes.copy_from_slice(&buffer[..N]);
+            reader.consume(N);
+        }
+        _ => reader.read_exact(&mut bytes).map_err(Error::IO)?,
+    }
+
+    Ok(bytes)
+}
+
+/// Decodes a region of big endian words in one pass
+pub(crate) fn unpack_words<const N: usize, T>(
+    bytes: &[u8],
+    target: &mut Vec<T>,
+    decode: impl Fn([u8; N]) -> T,
+) -> Result<()> {
+    target.extend(bytes.chunks_exact(N).map(|chunk| {
+        let mut word = [0x00; N];
+        word.copy_from_slice(chunk);
+        decode(word)
+    }));
+    Ok(())
+}
+
+/// Reads exactly `len` bytes without allocating more than the bytesource
+/// actually provides
+pub(crate) fn read_bytes(reader: &mut impl io::Read, len: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    read_bytes_into(reader, len, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Appends exactly `len` bytes to `bytes`, reusing its capacity
+fn read_bytes_into(reader: &mut impl io::Read, len: usize, bytes: &mut Vec<u8>) -> Result<()> {
+    let read = reader
+        .by_ref()
+        .take(len as u64)
+        .read_to_end(bytes)
+        .map_err(Error::IO)?;
+
+    if read < len {
+        return Err(Error::IO(io::ErrorKind::UnexpectedEof.into()));
+    }
+
+    Ok(())
+}
+
 impl Unpack for bool {
+    const PACKED_SIZE: Option<usize> = Some(1);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(bytes[0] != 0xFF)
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array::<1>(reader).map(|bytes| bytes[0] != 0xFF)
+    }
 }
 
 impl Unpack for u8 {
+    const PACKED_SIZE: Option<usize> = Some(1);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(bytes[0])
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array::<1>(reader).map(|bytes| bytes[0])
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        target.extend_from_slice(bytes);
+        Ok(())
+    }
 }
 
 impl Unpack for NonZeroU8 {
+    const PACKED_SIZE: Option<usize> = Some(1);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroU8::new(bytes[0]).unwrap())
+        NonZeroU8::new(bytes[0]).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array::<1>(reader).and_then(|bytes| NonZeroU8::new(bytes[0]).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroU8> {
+    const PACKED_SIZE: Option<usize> = Some(1);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroU8::new(bytes[0]))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array::<1>(reader).map(|bytes| NonZeroU8::new(bytes[0]))
+    }
 }
 
 impl Unpack for u16 {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 2];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(u16::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(u16::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, u16::from_be_bytes)
+    }
 }
 
 impl Unpack for NonZeroU16 {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 2];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroU16::new(u16::from_be_bytes(bytes)).unwrap())
+        NonZeroU16::new(u16::from_be_bytes(bytes)).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader)
+            .and_then(|bytes| NonZeroU16::new(u16::from_be_bytes(bytes)).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroU16> {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 2];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroU16::new(u16::from_be_bytes(bytes)))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(|bytes| NonZeroU16::new(u16::from_be_bytes(bytes)))
+    }
 }
 
 impl Unpack for u32 {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 4];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(u32::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(u32::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, u32::from_be_bytes)
+    }
 }
 
 impl Unpack for NonZeroU32 {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 4];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroU32::new(u32::from_be_bytes(bytes)).unwrap())
+        NonZeroU32::new(u32::from_be_bytes(bytes)).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader)
+            .and_then(|bytes| NonZeroU32::new(u32::from_be_bytes(bytes)).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroU32> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 4];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroU32::new(u32::from_be_bytes(bytes)))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(|bytes| NonZeroU32::new(u32::from_be_bytes(bytes)))
+    }
 }
 
 impl Unpack for u64 {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 8];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(u64::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(u64::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, u64::from_be_bytes)
+    }
 }
 
 impl Unpack for NonZeroU64 {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 8];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroU64::new(u64::from_be_bytes(bytes)).unwrap())
+        NonZeroU64::new(u64::from_be_bytes(bytes)).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader)
+            .and_then(|bytes| NonZeroU64::new(u64::from_be_bytes(bytes)).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroU64> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 8];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroU64::new(u64::from_be_bytes(bytes)))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(|bytes| NonZeroU64::new(u64::from_be_bytes(bytes)))
+    }
 }
 
 impl Unpack for u128 {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 16];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(u128::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(u128::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, u128::from_be_bytes)
+    }
 }
 
 impl Unpack for NonZeroU128 {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 16];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroU128::new(u128::from_be_bytes(bytes)).unwrap())
+        NonZeroU128::new(u128::from_be_bytes(bytes)).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader)
+            .and_then(|bytes| NonZeroU128::new(u128::from_be_bytes(bytes)).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroU128> {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 16];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroU128::new(u128::from_be_bytes(bytes)))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(|bytes| NonZeroU128::new(u128::from_be_bytes(bytes)))
+    }
 }
 
 impl Unpack for i16 {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 2];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(i16::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(i16::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, i16::from_be_bytes)
+    }
 }
 
 impl Unpack for NonZeroI16 {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 2];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroI16::new(i16::from_be_bytes(bytes)).unwrap())
+        NonZeroI16::new(i16::from_be_bytes(bytes)).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader)
+            .and_then(|bytes| NonZeroI16::new(i16::from_be_bytes(bytes)).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroI16> {
+    const PACKED_SIZE: Option<usize> = Some(2);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 2];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroI16::new(i16::from_be_bytes(bytes)))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(|bytes| NonZeroI16::new(i16::from_be_bytes(bytes)))
+    }
 }
 
 impl Unpack for i32 {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 4];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(i32::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(i32::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, i32::from_be_bytes)
+    }
 }
 
 impl Unpack for NonZeroI32 {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 4];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroI32::new(i32::from_be_bytes(bytes)).unwrap())
+        NonZeroI32::new(i32::from_be_bytes(bytes)).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader)
+            .and_then(|bytes| NonZeroI32::new(i32::from_be_bytes(bytes)).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroI32> {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 4];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroI32::new(i32::from_be_bytes(bytes)))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(|bytes| NonZeroI32::new(i32::from_be_bytes(bytes)))
+    }
 }
 
 impl Unpack for i64 {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 8];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(i64::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(i64::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, i64::from_be_bytes)
+    }
 }
 
 impl Unpack for NonZeroI64 {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 8];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroI64::new(i64::from_be_bytes(bytes)).unwrap())
+        NonZeroI64::new(i64::from_be_bytes(bytes)).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader)
+            .and_then(|bytes| NonZeroI64::new(i64::from_be_bytes(bytes)).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroI64> {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 8];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroI64::new(i64::from_be_bytes(bytes)))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(|bytes| NonZeroI64::new(i64::from_be_bytes(bytes)))
+    }
 }
 
 impl Unpack for i128 {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 16];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(i128::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(i128::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, i128::from_be_bytes)
+    }
 }
 
 impl Unpack for NonZeroI128 {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 16];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
-        Ok(NonZeroI128::new(i128::from_be_bytes(bytes)).unwrap())
+        NonZeroI128::new(i128::from_be_bytes(bytes)).ok_or_else(zero_error)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader)
+            .and_then(|bytes| NonZeroI128::new(i128::from_be_bytes(bytes)).ok_or_else(zero_error))
     }
 }
 
 impl Unpack for Option<NonZeroI128> {
+    const PACKED_SIZE: Option<usize> = Some(16);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 16];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(NonZeroI128::new(i128::from_be_bytes(bytes)))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(|bytes| NonZeroI128::new(i128::from_be_bytes(bytes)))
+    }
 }
 
 impl Unpack for f32 {
+    const PACKED_SIZE: Option<usize> = Some(4);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 4];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(f32::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(f32::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, f32::from_be_bytes)
+    }
 }
 
 impl Unpack for f64 {
+    const PACKED_SIZE: Option<usize> = Some(8);
+
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let mut bytes = [0x00; 8];
         reader.read_exact(&mut bytes).map_err(Error::IO)?;
         Ok(f64::from_be_bytes(bytes))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        read_array(reader).map(f64::from_be_bytes)
+    }
+
+    fn unpack_packed(bytes: &[u8], target: &mut Vec<Self>) -> Result<()> {
+        unpack_words(bytes, target, f64::from_be_bytes)
+    }
 }
 
 impl Unpack for String {
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
-        let mut len = u32::unpack_from(reader)? as usize;
-        let mut bytes = Vec::with_capacity(len);
-        let mut buffer = [0x00; 512];
-
-        while len > 0 {
-            let _read = reader.read(&mut buffer).map_err(Error::IO)?;
-
-            len = if len > buffer.len() {
-                bytes.extend_from_slice(&buffer);
-                len - buffer.len()
-            } else {
-                bytes.extend(buffer.iter().take(len));
-                0
-            }
-        }
+        let len = u32::unpack_from(reader)? as usize;
+        let bytes = read_bytes(reader, len)?;
+        String::from_utf8(bytes).map_err(Error::UTF8)
+    }
 
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        let len = u32::unpack_from_buffered(reader)? as usize;
+        let bytes = read_bytes(reader, len)?;
         String::from_utf8(bytes).map_err(Error::UTF8)
     }
+
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()> {
+        let len = u32::unpack_from(reader)? as usize;
+        let mut bytes = std::mem::take(self).into_bytes();
+        bytes.clear();
+        read_bytes_into(reader, len, &mut bytes)?;
+        *self = String::from_utf8(bytes).map_err(Error::UTF8)?;
+        Ok(())
+    }
 }
 
 impl<T: Unpack> Unpack for Vec<T> {
-    fn unpack_from(mut reader: &mut impl io::Read) -> Result<Self> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let len = u32::unpack_from(reader)? as usize;
-        let mut result = Vec::with_capacity(len);
+        let mut result = Vec::with_capacity(initial_capacity::<T>(len));
+        unpack_elements(reader, len, &mut result)?;
+        Ok(result)
+    }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        let len = u32::unpack_from_buffered(reader)? as usize;
+        let mut result = Vec::with_capacity(initial_capacity::<T>(len));
+
+        if T::PACKED_SIZE.is_some() {
+            unpack_elements(reader, len, &mut result)?;
+            return Ok(result);
+        }
 
         for _i in 0..len {
-            result.push(T::unpack_from(&mut reader)?);
+            result.push(T::unpack_from_buffered(reader)?);
         }
 
         Ok(result)
     }
+
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()> {
+        let len = u32::unpack_from(reader)? as usize;
+        self.clear();
+        self.reserve(initial_capacity::<T>(len));
+        unpack_elements(reader, len, self)
+    }
+}
+
+/// Appends `len` consecutive values to `target`
+///
+/// Values of a fixed packed size are read with a single call and decoded
+/// from memory afterwards
+fn unpack_elements<T: Unpack>(
+    reader: &mut impl io::Read,
+    len: usize,
+    target: &mut Vec<T>,
+) -> Result<()> {
+    if let Some(size) = T::PACKED_SIZE {
+        let bytes = read_bytes(reader, len.saturating_mul(size))?;
+        return T::unpack_packed(&bytes, target);
+    }
+
+    for _i in 0..len {
+        target.push(T::unpack_from(reader)?);
+    }
+
+    Ok(())
+}
+
+impl<T: Unpack, const N: usize> Unpack for [T; N] {
+    const PACKED_SIZE: Option<usize> = match T::PACKED_SIZE {
+        Some(size) => Some(4 + N * size),
+        None => None,
+    };
+
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+
+        if len != N {
+            return Err(Error::LengthMismatch {
+                expected: N,
+                found: len,
+            });
+        }
+
+        let mut guard = PartialArray {
+            items: [const { MaybeUninit::uninit() }; N],
+            initialized: 0,
+        };
+
+        while guard.initialized < N {
+            guard.items[guard.initialized].write(T::unpack_from(reader)?);
+            guard.initialized += 1;
+        }
+
+        let guard = ManuallyDrop::new(guard);
+        // SAFETY: all N items have been initialized and the guard will not
+        // drop them anymore, so ownership moves into the returned array
+        Ok(unsafe { ptr::read(guard.items.as_ptr() as *const [T; N]) })
+    }
+}
+
+/// Array under construction that drops its initialized prefix on failure
+struct PartialArray<T, const N: usize> {
+    items: [MaybeUninit<T>; N],
+    initialized: usize,
+}
+
+impl<T, const N: usize> Drop for PartialArray<T, N> {
+    fn drop(&mut self) {
+        for item in &mut self.items[..self.initialized] {
+            // SAFETY: the first `initialized` items have been written
+            unsafe { item.assume_init_drop() };
+        }
+    }
 }
 
 impl<T: Unpack> Unpack for Box<T> {
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         T::unpack_from(reader).map(|x| Box::new(x))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        T::unpack_from_buffered(reader).map(|x| Box::new(x))
+    }
+
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()> {
+        T::unpack_into(self, reader)
+    }
 }
 
 impl<T: Unpack> Unpack for Rc<T> {
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         T::unpack_from(reader).map(|x| Rc::new(x))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        T::unpack_from_buffered(reader).map(|x| Rc::new(x))
+    }
 }
 
 impl<T: Unpack> Unpack for Arc<T> {
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         T::unpack_from(reader).map(|x| Arc::new(x))
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        T::unpack_from_buffered(reader).map(|x| Arc::new(x))
+    }
 }
 
 impl<K: Unpack + std::cmp::Eq + std::hash::Hash, V: Unpack> Unpack for HashMap<K, V> {
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let len = u32::unpack_from(reader)? as usize;
-        let mut result = HashMap::with_capacity(len);
+        let mut result = HashMap::with_capacity(initial_capacity::<(K, V)>(len));
 
         for _i in 0..len {
             let key = K::unpack_from(reader)?;
@@ -365,12 +1148,39 @@ impl<K: Unpack + std::cmp::Eq + std::hash::Hash, V: Unpack> Unpack for HashMap<K
 
         Ok(result)
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        let len = u32::unpack_from_buffered(reader)? as usize;
+        let mut result = HashMap::with_capacity(initial_capacity::<(K, V)>(len));
+
+        for _i in 0..len {
+            let key = K::unpack_from_buffered(reader)?;
+            let value = V::unpack_from_buffered(reader)?;
+            result.insert(key, value);
+        }
+
+        Ok(result)
+    }
+
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()> {
+        let len = u32::unpack_from(reader)? as usize;
+        self.clear();
+        self.reserve(initial_capacity::<(K, V)>(len));
+
+        for _i in 0..len {
+            let key = K::unpack_from(reader)?;
+            let value = V::unpack_from(reader)?;
+            self.insert(key, value);
+        }
+
+        Ok(())
+    }
 }
 
 impl<T: Unpack + std::cmp::Eq + std::hash::Hash> Unpack for HashSet<T> {
     fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
         let len = u32::unpack_from(reader)? as usize;
-        let mut result = HashSet::with_capacity(len);
+        let mut result = HashSet::with_capacity(initial_capacity::<T>(len));
 
         for _i in 0..len {
             let value = T::unpack_from(reader)?;
@@ -379,6 +1189,31 @@ impl<T: Unpack + std::cmp::Eq + std::hash::Hash> Unpack for HashSet<T> {
 
         Ok(result)
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        let len = u32::unpack_from_buffered(reader)? as usize;
+        let mut result = HashSet::with_capacity(initial_capacity::<T>(len));
+
+        for _i in 0..len {
+            let value = T::unpack_from_buffered(reader)?;
+            result.insert(value);
+        }
+
+        Ok(result)
+    }
+
+    fn unpack_into(&mut self, reader: &mut impl io::Read) -> Result<()> {
+        let len = u32::unpack_from(reader)? as usize;
+        self.clear();
+        self.reserve(initial_capacity::<T>(len));
+
+        for _i in 0..len {
+            let value = T::unpack_from(reader)?;
+            self.insert(value);
+        }
+
+        Ok(())
+    }
 }
 
 impl<K: Unpack + std::cmp::Ord, V: Unpack> Unpack for BTreeMap<K, V> {
@@ -394,6 +1229,19 @@ impl<K: Unpack + std::cmp::Ord, V: Unpack> Unpack for BTreeMap<K, V> {
 
         Ok(result)
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        let len = u32::unpack_from_buffered(reader)? as usize;
+        let mut result = BTreeMap::new();
+
+        for _i in 0..len {
+            let key = K::unpack_from_buffered(reader)?;
+            let value = V::unpack_from_buffered(reader)?;
+            result.insert(key, value);
+        }
+
+        Ok(result)
+    }
 }
 
 impl<T: Unpack + std::cmp::Ord> Unpack for BTreeSet<T> {
@@ -408,6 +1256,18 @@ impl<T: Unpack + std::cmp::Ord> Unpack for BTreeSet<T> {
 
         Ok(result)
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        let len = u32::unpack_from_buffered(reader)? as usize;
+        let mut result = BTreeSet::new();
+
+        for _i in 0..len {
+            let value = T::unpack_from_buffered(reader)?;
+            result.insert(value);
+        }
+
+        Ok(result)
+    }
 }
 
 impl<T: Unpack + std::cmp::Ord> Unpack for BinaryHeap<T> {
@@ -422,6 +1282,18 @@ impl<T: Unpack + std::cmp::Ord> Unpack for BinaryHeap<T> {
 
         Ok(result)
     }
+
+    fn unpack_from_buffered(reader: &mut impl io::BufRead) -> Result<Self> {
+        let len = u32::unpack_from_buffered(reader)? as usize;
+        let mut result = BinaryHeap::new();
+
+        for _i in 0..len {
+            let value = T::unpack_from_buffered(reader)?;
+            result.push(value);
+        }
+
+        Ok(result)
+    }
 }
 
 #[cfg(test)]
@@ -429,12 +1301,20 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn unpack_bool() {
         let bytes: [u8; 1] = [0xFF];
         let value = bool::unpack_from(&mut bytes.as_ref()).unwrap();
         assert_eq!(value, false);
     }
 
+    #[test]
+    fn unpack_bool_from_any_other_byte() {
+        for byte in [0x00, 0x01, 0x7F, 0xFE] {
+            assert!(bool::unpack_exact(&[byte]).unwrap());
+        }
+    }
+
     #[test]
     fn unpack_u8() {
         let bytes = [0xFF];
@@ -672,6 +1552,22 @@ mod tests {
         assert_eq!(value, "abc");
     }
 
+    #[test]
+    fn unpack_string_leaves_trailing_bytes() {
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0x61, 0x62];
+        let mut reader = bytes.as_ref();
+        let value = String::unpack_from(&mut reader).unwrap();
+        assert_eq!(value, "a");
+        assert_eq!(reader, [0x62]);
+    }
+
+    #[test]
+    fn unpack_string_truncated() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x61];
+        let result = String::unpack_from(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
     #[test]
     fn unpack_array() {
         type Array = Vec<u8>;
@@ -703,4 +1599,319 @@ mod tests {
         let value = Value::unpack_from(&mut bytes.as_ref()).unwrap();
         assert_eq!(value, Arc::new(2));
     }
+
+    #[test]
+    fn unpack_or_default() {
+        #[derive(Debug, PartialEq)]
+        struct Record {
+            id: u16,
+            name: String,
+            flags: u32,
+        }
+
+        impl Unpack for Record {
+            fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+                Ok(Record {
+                    id: u16::unpack_from(reader)?,
+                    name: String::unpack_or_default(reader)?,
+                    flags: u32::unpack_or_default(reader)?,
+                })
+            }
+        }
+
+        let record = Record::unpack_exact(&[0x00, 0x07]).unwrap();
+        assert_eq!((record.id, record.name.as_str(), record.flags), (7, "", 0));
+
+        let bytes = [0x00, 0x07, 0x00, 0x00, 0x00, 0x01, 0x61];
+        let record = Record::unpack_exact(&bytes).unwrap();
+        assert_eq!((record.name.as_str(), record.flags), ("a", 0));
+
+        let result = Record::unpack_exact(&[0x00, 0x07, 0x00, 0x00]);
+        assert!(matches!(result, Err(Error::IO(_))));
+        assert!(matches!(Record::unpack_exact(&[0x00]), Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn unpack_limited() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x61, 0x62, 0xFF];
+        let value: String = super::unpack_limited(&mut bytes.as_ref(), 6).unwrap();
+        assert_eq!(value, "ab");
+
+        let result = super::unpack_limited::<String>(&mut bytes.as_ref(), 5);
+        assert!(matches!(result, Err(Error::LimitExceeded { limit: 5 })));
+
+        let result = super::unpack_limited::<String>(&mut bytes[..5].as_ref(), 6);
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn downcast_error() {
+        let error = Error::Custom(Box::new(fmt::Error));
+        assert!(error.downcast_ref::<fmt::Error>().is_some());
+        assert!(error.downcast_ref::<Utf8Error>().is_none());
+        assert_eq!(error.code(), None);
+
+        let error = String::unpack_exact(&[0x00, 0x00, 0x00, 0x01, 0xFF]).unwrap_err();
+        assert!(error.downcast_ref::<FromUtf8Error>().is_some());
+
+        let error = Error::coded(404, "not found");
+        assert_eq!(error.code(), Some(404));
+        assert_eq!(error.to_string(), "error 404: not found");
+    }
+
+    #[test]
+    fn convert_pack_error() {
+        let error = Error::from(pack::Error::coded(404, "not found"));
+        assert_eq!(error.code(), Some(404));
+
+        let error = Error::from(pack::Error::Custom(Box::new(fmt::Error)));
+        assert!(error.downcast_ref::<fmt::Error>().is_some());
+    }
+
+    #[test]
+    fn unpack_framed() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0xFF];
+        let mut reader = bytes.as_ref();
+        let value = u16::unpack_framed(&mut reader).unwrap();
+        assert_eq!(value, 2);
+        assert_eq!(reader, [0xFF]);
+    }
+
+    #[test]
+    fn unpack_framed_trailing_bytes() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x00, 0x02, 0xFF];
+        let result = u16::unpack_framed(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::TrailingBytes(1))));
+    }
+
+    #[test]
+    fn unpack_framed_short_frame() {
+        let bytes = [0x00, 0x00, 0x00, 0x01, 0x00, 0x02];
+        let result = u16::unpack_framed(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn unpack_framed_truncated() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x00];
+        let result = u16::unpack_framed(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn unpack_buffered_u32() {
+        let bytes = [0x00, 0x00, 0x00, 0x02];
+        let value = u32::unpack_from_buffered(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, 2);
+    }
+
+    #[test]
+    fn unpack_buffered_across_buffer_boundary() {
+        let bytes = [0xFF, 0xFF, 0xFF, 0xFE];
+        let mut reader = io::BufReader::with_capacity(3, bytes.as_ref());
+        let value = i32::unpack_from_buffered(&mut reader).unwrap();
+        assert_eq!(value, -2);
+    }
+
+    #[test]
+    fn unpack_buffered_array() {
+        type Array = Vec<u16>;
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x02];
+        let mut reader = io::BufReader::with_capacity(5, bytes.as_ref());
+        let value = Array::unpack_from_buffered(&mut reader).unwrap();
+        assert_eq!(value, [1, 2]);
+    }
+
+    #[test]
+    fn unpack_buffered_truncated() {
+        let bytes = [0x00, 0x00, 0x00];
+        let result = u32::unpack_from_buffered(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn unpack_from_file() {
+        let path = std::env::temp_dir().join(format!("stacker-unpack-{}", std::process::id()));
+        std::fs::write(&path, [0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63]).unwrap();
+        let value = String::unpack_from_file(&path).unwrap();
+        assert_eq!(value, "abc");
+
+        let result = u16::unpack_from_file(&path);
+        assert!(matches!(result, Err(Error::TrailingBytes(5))));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn unpack_from_buf() {
+        let mut buffer = bytes::Bytes::from_static(&[0x00, 0x01, 0x00, 0x02, 0x00]);
+        assert_eq!(u16::unpack_from_buf(&mut buffer).unwrap(), 1);
+        assert_eq!(u16::unpack_from_buf(&mut buffer).unwrap(), 2);
+        assert!(u16::unpack_from_buf(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn unpack_from_slice() {
+        let bytes = [0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x61, 0xFF];
+        let (number, remaining) = u16::unpack_from_slice(&bytes).unwrap();
+        let (text, remaining) = String::unpack_from_slice(remaining).unwrap();
+        assert_eq!((number, text.as_str()), (1, "a"));
+        assert_eq!(remaining, [0xFF]);
+    }
+
+    #[test]
+    fn unpack_into_string_reuses_capacity() {
+        let bytes = [
+            0x00, 0x00, 0x00, 0x01, 0x61, 0x00, 0x00, 0x00, 0x02, 0x62, 0x63,
+        ];
+        let mut reader = bytes.as_ref();
+        let mut value = String::with_capacity(64);
+        value.unpack_into(&mut reader).unwrap();
+        assert_eq!(value, "a");
+        value.unpack_into(&mut reader).unwrap();
+        assert_eq!(value, "bc");
+        assert!(value.capacity() >= 64);
+    }
+
+    #[test]
+    fn unpack_into_array() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x02];
+        let mut value = vec![7u16; 16];
+        value.unpack_into(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, [1, 2]);
+        assert!(value.capacity() >= 16);
+    }
+
+    #[test]
+    fn unpack_into_primitive() {
+        let bytes = [0x00, 0x02];
+        let mut value = 5u16;
+        value.unpack_into(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, 2);
+    }
+
+    #[test]
+    fn unpack_extend_appends() {
+        let bytes = [
+            0x00, 0x00, 0x00, 0x01, 0x03, 0x00, 0x00, 0x00, 0x02, 0x04, 0x05,
+        ];
+        let mut reader = bytes.as_ref();
+        let mut values = vec![1u8, 2];
+        assert_eq!(unpack_extend::<u8>(&mut reader, &mut values).unwrap(), 1);
+        assert_eq!(unpack_extend::<u8>(&mut reader, &mut values).unwrap(), 2);
+        assert_eq!(values, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn unpack_extend_truncated() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x00, 0x01, 0x00];
+        let mut values: Vec<u16> = Vec::new();
+        let result = unpack_extend::<u16>(&mut bytes.as_ref(), &mut values);
+        assert!(matches!(result, Err(Error::IO(_))));
+        assert_eq!(values, [1]);
+    }
+
+    #[test]
+    fn unpack_fixed_size_array() {
+        type Array = Vec<NonZeroU16>;
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x01, 0x00];
+        let value = Array::unpack_from(&mut bytes.as_ref()).unwrap();
+        assert_eq!(
+            value,
+            [NonZeroU16::new(1).unwrap(), NonZeroU16::new(256).unwrap()]
+        );
+    }
+
+    #[test]
+    fn unpack_fixed_size_array_truncated() {
+        type Array = Vec<u32>;
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00];
+        let result = Array::unpack_from(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn unpack_large_f64_array() {
+        let values: Vec<f64> = (0..1000).map(|x| x as f64 * -0.5).collect();
+        let bytes = crate::pack::Pack::pack_to_vec(values.as_slice()).unwrap();
+        let result = Vec::<f64>::unpack_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(result, values);
+    }
+
+    #[test]
+    fn unpack_u32_array_word_wise() {
+        type Array = Vec<u32>;
+        let bytes = [
+            0x00, 0x00, 0x00, 0x02, 0x01, 0x02, 0x03, 0x04, 0xFF, 0x00, 0x00, 0x01,
+        ];
+        let value = Array::unpack_from_buffered(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, [0x0102_0304, 0xFF00_0001]);
+    }
+
+    #[test]
+    fn unpack_fixed_array() {
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03];
+        let value = <[u16; 3]>::unpack_from(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, [1, 2, 3]);
+    }
+
+    #[test]
+    fn unpack_fixed_array_length_mismatch() {
+        let bytes = [0x00, 0x00, 0x00, 0x02, 0x01, 0x02];
+        let result = <[u8; 3]>::unpack_from(&mut bytes.as_ref());
+        assert!(matches!(
+            result,
+            Err(Error::LengthMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn unpack_fixed_array_truncated_drops_items() {
+        thread_local!(static DROPPED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) });
+
+        struct Tracked;
+
+        impl Unpack for Tracked {
+            fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+                u8::unpack_from(reader).map(|_| Tracked)
+            }
+        }
+
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                DROPPED.with(|dropped| dropped.set(dropped.get() + 1));
+            }
+        }
+
+        let bytes = [0x00, 0x00, 0x00, 0x03, 0x01, 0x02];
+        let result = <[Tracked; 3]>::unpack_from(&mut bytes.as_ref());
+        assert!(result.is_err());
+        assert_eq!(DROPPED.with(|dropped| dropped.get()), 2);
+    }
+
+    #[test]
+    fn unpack_exact() {
+        assert_eq!(u16::unpack_exact(&[0x00, 0x02]).unwrap(), 2);
+
+        let result = u16::unpack_exact(&[0x00, 0x02, 0x00]);
+        assert!(matches!(result, Err(Error::TrailingBytes(1))));
+    }
+
+    #[test]
+    fn unpack_zero_as_non_zero() {
+        let result = NonZeroU16::unpack_from(&mut [0x00, 0x00].as_ref());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+        let result = NonZeroU16::unpack_from_buffered(&mut [0x00, 0x00].as_ref());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn unpack_forged_length_prefix() {
+        let result = Vec::<u64>::unpack_from(&mut [0xFF, 0xFF, 0xFF, 0xFF].as_ref());
+        assert!(matches!(result, Err(Error::IO(_))));
+        assert_eq!(initial_capacity::<u64>(u32::MAX as usize), 8192);
+    }
 }
@@ -0,0 +1,424 @@
+use crate::pack::{self, checked_len, Pack};
+use crate::schema::Schema;
+use crate::unpack::{check_depth, initial_capacity, read_bytes, Error, Result, Unpack};
+use std::io;
+
+/// Dynamically typed value decoded without compile-time knowledge of its
+/// type
+///
+/// Integers of every width are widened to `Int` and floats to `Float`.
+/// Sequences of bytes become `Bytes`, all other sequences become `List`.
+/// Structs become a `Map` from their field names to the field values.
+///
+/// A `Value` can also be packed on its own, prefixed with a type tag per
+/// node, e.g. to embed arbitrary metadata inside a statically typed struct
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+}
+
+/// Fails with `Error::InvalidValue` if a length prefix announces elements
+/// that occupy no bytes, since nothing in the input would bound their count
+pub(crate) fn check_elements(len: usize, zero_sized: bool) -> Result<()> {
+    if zero_sized && len > 0 {
+        return Err(Error::InvalidValue(format!(
+            "sequence of {} elements without any packed bytes",
+            len
+        )));
+    }
+    Ok(())
+}
+
+impl Value {
+    /// Tries to deserialize a value packed according to the given schema
+    ///
+    /// Fails with `Error::InvalidValue` for custom types, whose wire format
+    /// is unknown, for `u128` values exceeding the range of `i128` and for
+    /// schemas nested too deeply
+    pub fn unpack_with_schema(schema: &Schema, reader: &mut impl io::Read) -> Result<Self> {
+        Value::unpack_schema_nested(schema, reader, 0)
+    }
+
+    fn unpack_schema_nested(
+        schema: &Schema,
+        reader: &mut impl io::Read,
+        depth: usize,
+    ) -> Result<Self> {
+        check_depth(depth)?;
+        let value = match schema {
+            Schema::Bool => Value::Bool(bool::unpack_from(reader)?),
+            Schema::U8 => Value::Int(u8::unpack_from(reader)?.into()),
+            Schema::U16 => Value::Int(u16::unpack_from(reader)?.into()),
+            Schema::U32 => Value::Int(u32::unpack_from(reader)?.into()),
+            Schema::U64 => Value::Int(u64::unpack_from(reader)?.into()),
+            Schema::U128 => {
+                let value = u128::unpack_from(reader)?;
+                Value::Int(i128::try_from(value).map_err(|_| {
+                    Error::InvalidValue(format!("{} exceeds the range of i128", value))
+                })?)
+            }
+            Schema::I16 => Value::Int(i16::unpack_from(reader)?.into()),
+            Schema::I32 => Value::Int(i32::unpack_from(reader)?.into()),
+            Schema::I64 => Value::Int(i64::unpack_from(reader)?.into()),
+            Schema::I128 => Value::Int(i128::unpack_from(reader)?),
+            Schema::F32 => Value::Float(f32::unpack_from(reader)?.into()),
+            Schema::F64 => Value::Float(f64::unpack_from(reader)?),
+            Schema::String => Value::Str(String::unpack_from(reader)?),
+            Schema::Sequence(element) if **element == Schema::U8 => {
+                let len = u32::unpack_from(reader)? as usize;
+                Value::Bytes(read_bytes(reader, len)?)
+            }
+            Schema::Sequence(element) => {
+                let len = u32::unpack_from(reader)? as usize;
+                check_elements(len, element.is_zero_sized())?;
+                let mut items = Vec::with_capacity(initial_capacity::<Value>(len));
+
+                for _i in 0..len {
+                    items.push(Value::unpack_schema_nested(element, reader, depth + 1)?);
+                }
+
+                Value::List(items)
+            }
+            Schema::Map(key, value) => {
+                let len = u32::unpack_from(reader)? as usize;
+                check_elements(len, key.is_zero_sized() && value.is_zero_sized())?;
+                let mut entries = Vec::with_capacity(initial_capacity::<(Value, Value)>(len));
+
+                for _i in 0..len {
+                    let key = Value::unpack_schema_nested(key, reader, depth + 1)?;
+                    let value = Value::unpack_schema_nested(value, reader, depth + 1)?;
+                    entries.push((key, value));
+                }
+
+                Value::Map(entries)
+            }
+            Schema::Struct { fields, .. } => {
+                let mut entries = Vec::new();
+
+                for (name, schema) in fields {
+                    let value = Value::unpack_schema_nested(schema, reader, depth + 1)?;
+                    entries.push((Value::Str(name.clone()), value));
+                }
+
+                Value::Map(entries)
+            }
+            Schema::Custom(name) => {
+                return Err(Error::InvalidValue(format!(
+                    "custom type {} cannot be decoded without its implementation",
+                    name
+                )))
+            }
+        };
+        Ok(value)
+    }
+
+    /// Tries to deserialize a value written in the self-describing encoding
+    /// (see [`SelfDescribing`](crate::schema::SelfDescribing))
+    pub fn unpack_described(reader: &mut impl io::Read) -> Result<Self> {
+        let schema = Schema::unpack_from(reader)?;
+        Value::unpack_with_schema(&schema, reader)
+    }
+
+    /// Packs this value according to the given schema, producing the same
+    /// bytes as the statically typed implementation
+    ///
+    /// Integers are converted into the width required by the schema and
+    /// structs are taken from maps with their field names as keys. Fails
+    /// with `Error::InvalidValue` if the value does not fit the schema
+    pub fn pack_with_schema(&self, schema: &Schema, writer: &mut impl io::Write) -> Result<usize> {
+        fn int<T: TryFrom<i128> + Pack>(value: i128, writer: &mut impl io::Write) -> Result<usize> {
+            let value = T::try_from(value).map_err(|_| {
+                Error::InvalidValue(format!(
+                    "{} is out of range for {}",
+                    value,
+                    std::any::type_name::<T>()
+                ))
+            })?;
+            Ok(value.pack_into(writer)?)
+        }
+
+        let written = match (schema, self) {
+            (Schema::Bool, Value::Bool(value)) => value.pack_into(writer)?,
+            (Schema::U8, Value::Int(value)) => int::<u8>(*value, writer)?,
+            (Schema::U16, Value::Int(value)) => int::<u16>(*value, writer)?,
+            (Schema::U32, Value::Int(value)) => int::<u32>(*value, writer)?,
+            (Schema::U64, Value::Int(value)) => int::<u64>(*value, writer)?,
+            (Schema::U128, Value::Int(value)) => int::<u128>(*value, writer)?,
+            (Schema::I16, Value::Int(value)) => int::<i16>(*value, writer)?,
+            (Schema::I32, Value::Int(value)) => int::<i32>(*value, writer)?,
+            (Schema::I64, Value::Int(value)) => int::<i64>(*value, writer)?,
+            (Schema::I128, Value::Int(value)) => value.pack_into(writer)?,
+            (Schema::F32, Value::Float(value)) => (*value as f32).pack_into(writer)?,
+            (Schema::F32, Value::Int(value)) => (*value as f32).pack_into(writer)?,
+            (Schema::F64, Value::Float(value)) => value.pack_into(writer)?,
+            (Schema::F64, Value::Int(value)) => (*value as f64).pack_into(writer)?,
+            (Schema::String, Value::Str(value)) => value.pack_into(writer)?,
+            (Schema::Sequence(element), Value::Bytes(bytes)) if **element == Schema::U8 => {
+                bytes.pack_into(writer)?
+            }
+            (Schema::Sequence(element), Value::List(items)) => {
+                let mut written = checked_len(items.len())?.pack_into(writer)?;
+
+                for item in items {
+                    written += item.pack_with_schema(element, writer)?;
+                }
+
+                written
+            }
+            (Schema::Map(key, value), Value::Map(entries)) => {
+                let mut written = checked_len(entries.len())?.pack_into(writer)?;
+
+                for (entry_key, entry_value) in entries {
+                    written += entry_key.pack_with_schema(key, writer)?;
+                    written += entry_value.pack_with_schema(value, writer)?;
+                }
+
+                written
+            }
+            (Schema::Struct { fields, .. }, Value::Map(_)) => {
+                let mut written = 0;
+
+                for (name, schema) in fields {
+                    let value = self
+                        .get(name)
+                        .ok_or_else(|| Error::InvalidValue(format!("missing field {}", name)))?;
+                    written += value.pack_with_schema(schema, writer)?;
+                }
+
+                written
+            }
+            (schema, value) => {
+                return Err(Error::InvalidValue(format!(
+                    "{:?} does not match schema {:?}",
+                    value, schema
+                )))
+            }
+        };
+        Ok(written)
+    }
+
+    /// Returns the value of the field with the given name, if this value
+    /// is a map with string keys (e.g. a decoded struct)
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(key, _)| matches!(key, Value::Str(key) if key == name))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Int(_) => 2,
+            Value::Float(_) => 3,
+            Value::Str(_) => 4,
+            Value::Bytes(_) => 5,
+            Value::List(_) => 6,
+            Value::Map(_) => 7,
+        }
+    }
+}
+
+impl Pack for Value {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let mut written = self.tag().pack_into(writer)?;
+
+        match self {
+            Value::Null => {}
+            Value::Bool(value) => written += value.pack_into(writer)?,
+            Value::Int(value) => written += value.pack_into(writer)?,
+            Value::Float(value) => written += value.pack_into(writer)?,
+            Value::Str(value) => written += value.pack_into(writer)?,
+            Value::Bytes(value) => written += value.pack_into(writer)?,
+            Value::List(items) => {
+                written += checked_len(items.len())?.pack_into(writer)?;
+
+                for item in items {
+                    written += item.pack_into(writer)?;
+                }
+            }
+            Value::Map(entries) => {
+                written += checked_len(entries.len())?.pack_into(writer)?;
+
+                for (key, value) in entries {
+                    written += key.pack_into(writer)?;
+                    written += value.pack_into(writer)?;
+                }
+            }
+        }
+
+        Ok(written)
+    }
+}
+
+/// Fails with `Error::InvalidValue` if lists or maps are nested too deeply
+impl Unpack for Value {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        Value::unpack_nested(reader, 0)
+    }
+}
+
+impl Value {
+    fn unpack_nested(reader: &mut impl io::Read, depth: usize) -> Result<Self> {
+        check_depth(depth)?;
+        let value = match u8::unpack_from(reader)? {
+            0 => Value::Null,
+            1 => Value::Bool(bool::unpack_from(reader)?),
+            2 => Value::Int(i128::unpack_from(reader)?),
+            3 => Value::Float(f64::unpack_from(reader)?),
+            4 => Value::Str(String::unpack_from(reader)?),
+            5 => Value::Bytes(Vec::unpack_from(reader)?),
+            6 => {
+                let len = u32::unpack_from(reader)? as usize;
+                let mut items = Vec::new();
+
+                for _i in 0..len {
+                    items.push(Value::unpack_nested(reader, depth + 1)?);
+                }
+
+                Value::List(items)
+            }
+            7 => {
+                let len = u32::unpack_from(reader)? as usize;
+                let mut entries = Vec::new();
+
+                for _i in 0..len {
+                    let key = Value::unpack_nested(reader, depth + 1)?;
+                    let value = Value::unpack_nested(reader, depth + 1)?;
+                    entries.push((key, value));
+                }
+
+                Value::Map(entries)
+            }
+            tag => return Err(Error::InvalidValue(format!("unknown value tag {}", tag))),
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Describe;
+
+    #[test]
+    fn unpack_struct_with_schema() {
+        let schema = Schema::structure("Point")
+            .field("x", Schema::I16)
+            .field("name", Schema::String)
+            .field("raw", Vec::<u8>::schema());
+        let bytes = [
+            0xFF, 0xFE, 0x00, 0x00, 0x00, 0x01, 0x61, 0x00, 0x00, 0x00, 0x01, 0x07,
+        ];
+        let value = Value::unpack_with_schema(&schema, &mut bytes.as_ref()).unwrap();
+        assert_eq!(value.get("x"), Some(&Value::Int(-2)));
+        assert_eq!(value.get("name"), Some(&Value::Str("a".into())));
+        assert_eq!(value.get("raw"), Some(&Value::Bytes(vec![7])));
+    }
+
+    #[test]
+    fn reject_zero_sized_elements() {
+        let empty = Schema::structure("Empty");
+        let mut bytes = Schema::sequence(empty.clone()).pack_to_vec().unwrap();
+        u32::MAX.pack_into(&mut bytes).unwrap();
+        let result = Value::unpack_described(&mut bytes.as_slice());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+
+        let schema = Schema::map(empty.clone(), empty);
+        let bytes = u32::MAX.to_be_bytes();
+        let result = Value::unpack_with_schema(&schema, &mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn unpack_described_list() {
+        let mut bytes = Vec::<u16>::schema().pack_to_vec().unwrap();
+        [1u16, 2].pack_into(&mut bytes).unwrap();
+        let value = Value::unpack_described(&mut bytes.as_slice()).unwrap();
+        assert_eq!(value, Value::List(vec![Value::Int(1), Value::Int(2)]));
+    }
+
+    #[test]
+    fn unpack_custom_type() {
+        let schema = Schema::Custom("Opaque".into());
+        let result = Value::unpack_with_schema(&schema, &mut [0x00].as_ref());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn pack_and_unpack_tagged() {
+        let value = Value::Map(vec![
+            (Value::Str("id".into()), Value::Int(-1)),
+            (
+                Value::Str("tags".into()),
+                Value::List(vec![Value::Null, Value::Bool(true), Value::Float(0.5)]),
+            ),
+            (Value::Str("raw".into()), Value::Bytes(vec![1, 2])),
+        ]);
+        let bytes = value.pack_to_vec().unwrap();
+        assert_eq!(Value::unpack_exact(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn pack_null() {
+        assert_eq!(Value::Null.pack_to_vec().unwrap(), [0x00]);
+    }
+
+    #[test]
+    fn unpack_unknown_tag() {
+        let result = Value::unpack_exact(&[0x08]);
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn pack_with_schema_matches_static_layout() {
+        let schema = Schema::structure("Entry")
+            .field("id", Schema::U16)
+            .field("values", Schema::sequence(Schema::F32));
+        let value = Value::Map(vec![
+            (
+                Value::Str("values".into()),
+                Value::List(vec![Value::Float(0.5)]),
+            ),
+            (Value::Str("id".into()), Value::Int(3)),
+        ]);
+        let mut bytes = Vec::new();
+        value.pack_with_schema(&schema, &mut bytes).unwrap();
+
+        let mut expected = 3u16.pack_to_vec().unwrap();
+        [0.5f32].pack_into(&mut expected).unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn pack_with_schema_out_of_range() {
+        let result = Value::Int(256).pack_with_schema(&Schema::U8, &mut Vec::new());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    #[test]
+    fn unpack_deeply_nested_value() {
+        let nested: Vec<u8> = [6, 0, 0, 0, 1].repeat(100_000);
+        let result = Value::unpack_from(&mut nested.as_slice());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+
+        let mut schema = Schema::U8;
+        for _i in 0..200 {
+            schema = Schema::sequence(Schema::sequence(schema));
+        }
+        let bytes = [0, 0, 0, 1].repeat(400);
+        let result = Value::unpack_with_schema(&schema, &mut bytes.as_slice());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+}
@@ -0,0 +1,377 @@
+use crate::pack::{self, checked_len, Pack};
+use crate::unpack::{read_bytes, Error, Result, Unpack};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::marker::PhantomData;
+
+type DecodeFn<T> = Box<dyn Fn(&mut &mut dyn io::Read) -> Result<T> + Send + Sync>;
+
+/// Envelope format with a magic number and a format version
+///
+/// Values are packed as the 4 byte magic, the format version as `u16` and
+/// the packed value. On unpacking, the magic is checked and the payload is
+/// handed to the decode function registered for its version, so older
+/// versions of a format can still be read after the type has evolved
+///
+/// ```
+/// use serial_container::unpack::Unpack;
+/// use serial_container::version::VersionedFormat;
+///
+/// // version 1 stored a u8, version 2 stores a u16
+/// let format = VersionedFormat::<u16>::new(*b"DEMO", 2)
+///     .with_version(1, |reader| u8::unpack_from(reader).map(u16::from));
+///
+/// let mut bytes = Vec::new();
+/// format.pack_into(&300, &mut bytes).unwrap();
+/// assert_eq!(format.unpack_from(&mut bytes.as_slice()).unwrap(), 300);
+///
+/// let old = [b'D', b'E', b'M', b'O', 0x00, 0x01, 0x07];
+/// assert_eq!(format.unpack_from(&mut old.as_ref()).unwrap(), 7);
+/// ```
+pub struct VersionedFormat<T> {
+    magic: [u8; 4],
+    current: u16,
+    decoders: BTreeMap<u16, DecodeFn<T>>,
+}
+
+impl<T: Unpack> VersionedFormat<T> {
+    /// Creates a format whose current version is decoded with the `Unpack`
+    /// implementation of `T`
+    pub fn new(magic: [u8; 4], current: u16) -> Self {
+        Self::without_current(magic, current)
+            .with_version(current, |mut reader| T::unpack_from(&mut reader))
+    }
+}
+
+impl<T> VersionedFormat<T> {
+    /// Creates a format without any registered decode function, e.g. for
+    /// types that do not implement `Unpack` themselves
+    pub fn without_current(magic: [u8; 4], current: u16) -> Self {
+        Self {
+            magic,
+            current,
+            decoders: BTreeMap::new(),
+        }
+    }
+
+    /// Registers the decode function for a version, replacing a previously
+    /// registered one
+    pub fn with_version(
+        mut self,
+        version: u16,
+        decode: impl Fn(&mut &mut dyn io::Read) -> Result<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.decoders.insert(version, Box::new(decode));
+        self
+    }
+
+    /// Returns the magic number identifying the format
+    pub fn magic(&self) -> [u8; 4] {
+        self.magic
+    }
+
+    /// Returns the version values are packed with
+    pub fn current(&self) -> u16 {
+        self.current
+    }
+
+    /// Returns whether a decode function is registered for the version
+    pub fn supports(&self, version: u16) -> bool {
+        self.decoders.contains_key(&version)
+    }
+
+    /// Tries to serialize a value with the current version into a
+    /// bytestream
+    pub fn pack_into(&self, value: &T, writer: &mut impl io::Write) -> pack::Result<usize>
+    where
+        T: Pack,
+    {
+        let written = self.pack_header(writer)?;
+        Ok(written + value.pack_into(writer)?)
+    }
+
+    /// Writes the magic and the current version, which have to be followed
+    /// by a payload of the current version
+    pub fn pack_header(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        writer.write_all(&self.magic)?;
+        Ok(self.magic.len() + self.current.pack_into(writer)?)
+    }
+
+    /// Tries to deserialize a value of any supported version
+    ///
+    /// Fails with `Error::InvalidValue` if the magic does not match and
+    /// with `Error::UnsupportedVersion` if no decode function is registered
+    /// for the version
+    pub fn unpack_from(&self, reader: &mut impl io::Read) -> Result<T> {
+        let version = self.unpack_header(reader)?;
+        let decode = self
+            .decoders
+            .get(&version)
+            .ok_or(Error::UnsupportedVersion(version))?;
+        let mut reader: &mut dyn io::Read = reader;
+        decode(&mut reader)
+    }
+
+    /// Reads the magic and returns the version of the following payload
+    pub fn unpack_header(&self, reader: &mut impl io::Read) -> Result<u16> {
+        let mut magic = [0x00; 4];
+        reader.read_exact(&mut magic).map_err(Error::IO)?;
+
+        if magic != self.magic {
+            return Err(Error::InvalidValue(format!(
+                "expected magic {:02X?} but found {:02X?}",
+                self.magic, magic
+            )));
+        }
+
+        u16::unpack_from(reader)
+    }
+}
+
+impl<T> Debug for VersionedFormat<T> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_struct("VersionedFormat")
+            .field("magic", &self.magic)
+            .field("current", &self.current)
+            .field("versions", &self.decoders.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+type UpgradeFn = Box<dyn Fn(Box<dyn Any>) -> Result<Box<dyn Any>> + Send + Sync>;
+
+/// Registry of migrations upgrading old versions of a format to the
+/// current one
+///
+/// Every version is decoded into its own type, which is then upgraded step
+/// by step with the registered converters until the current version is
+/// reached. The envelope is the same as for [`VersionedFormat`]
+///
+/// ```
+/// use serial_container::version::Migrations;
+///
+/// // version 1 stored a u8, version 2 a u16 and version 3 a String
+/// let migrations = Migrations::<String>::new(*b"DEMO", 3)
+///     .with_version::<u8>(1)
+///     .with_version::<u16>(2)
+///     .with_upgrade(1, |old: u8| u16::from(old))
+///     .with_upgrade(2, |old: u16| old.to_string());
+///
+/// let old = [b'D', b'E', b'M', b'O', 0x00, 0x01, 0x07];
+/// assert_eq!(migrations.unpack_latest(&mut old.as_ref()).unwrap(), "7");
+/// ```
+pub struct Migrations<T> {
+    format: VersionedFormat<(u16, Box<dyn Any>)>,
+    upgrades: BTreeMap<u16, UpgradeFn>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Unpack + 'static> Migrations<T> {
+    /// Creates a registry whose current version is decoded with the
+    /// `Unpack` implementation of `T`
+    pub fn new(magic: [u8; 4], current: u16) -> Self {
+        Self {
+            format: VersionedFormat::without_current(magic, current),
+            upgrades: BTreeMap::new(),
+            marker: PhantomData,
+        }
+        .with_version::<T>(current)
+    }
+
+    /// Registers the type a version is decoded into
+    pub fn with_version<V: Unpack + 'static>(mut self, version: u16) -> Self {
+        self.format = self.format.with_version(version, move |reader| {
+            let value = V::unpack_from(reader)?;
+            Ok((version, Box::new(value) as Box<dyn Any>))
+        });
+        self
+    }
+
+    /// Registers the converter from the type of version `from` to the type
+    /// of version `from + 1`
+    pub fn with_upgrade<A: 'static, B: 'static>(
+        mut self,
+        from: u16,
+        upgrade: impl Fn(A) -> B + Send + Sync + 'static,
+    ) -> Self {
+        let upgrade = move |value: Box<dyn Any>| match value.downcast::<A>() {
+            Ok(value) => Ok(Box::new(upgrade(*value)) as Box<dyn Any>),
+            Err(_) => Err(Error::InvalidValue(format!(
+                "upgrade from version {} received a value of another type",
+                from
+            ))),
+        };
+        self.upgrades.insert(from, Box::new(upgrade));
+        self
+    }
+
+    /// Tries to serialize a value with the current version into a
+    /// bytestream
+    pub fn pack_into(&self, value: &T, writer: &mut impl io::Write) -> pack::Result<usize>
+    where
+        T: Pack,
+    {
+        let written = self.format.pack_header(writer)?;
+        Ok(written + value.pack_into(writer)?)
+    }
+
+    /// Tries to deserialize a value of any supported version and upgrades
+    /// it to the current version
+    ///
+    /// Fails with `Error::UnsupportedVersion` if the version of the payload
+    /// or one of the upgrade steps is not registered
+    pub fn unpack_latest(&self, reader: &mut impl io::Read) -> Result<T> {
+        let (mut version, mut value) = self.format.unpack_from(reader)?;
+
+        while version < self.format.current() {
+            let upgrade = self
+                .upgrades
+                .get(&version)
+                .ok_or(Error::UnsupportedVersion(version))?;
+            value = upgrade(value)?;
+            version += 1;
+        }
+
+        if version > self.format.current() {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        value.downcast::<T>().map(|value| *value).map_err(|_| {
+            Error::InvalidValue(String::from(
+                "upgrades did not produce a value of the current type",
+            ))
+        })
+    }
+}
+
+impl<T> Debug for Migrations<T> {
+    fn fmt(&self, destination: &mut Formatter<'_>) -> fmt::Result {
+        destination
+            .debug_struct("Migrations")
+            .field("format", &self.format)
+            .field("upgrades", &self.upgrades.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Wrapper that packs a value as a skippable frame
+///
+/// The value is preceded by its length as `u32`, like with `pack_framed`.
+/// On unpacking, exactly the announced number of bytes is read and bytes
+/// left over behind the value are skipped instead of rejected. A reader can
+/// therefore still decode a struct to which a newer version appended fields,
+/// as long as fields are only ever appended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Framed<T>(pub T);
+
+impl<T: Pack> Pack for Framed<T> {
+    fn pack_into(&self, writer: &mut impl io::Write) -> pack::Result<usize> {
+        let buffer = self.0.pack_to_vec()?;
+        let written = checked_len(buffer.len())?.pack_into(writer)?;
+        writer.write_all(&buffer)?;
+        Ok(written + buffer.len())
+    }
+}
+
+impl<T: Unpack> Unpack for Framed<T> {
+    fn unpack_from(reader: &mut impl io::Read) -> Result<Self> {
+        let len = u32::unpack_from(reader)? as usize;
+        let bytes = read_bytes(reader, len)?;
+        T::unpack_from(&mut bytes.as_slice()).map(Framed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format() -> VersionedFormat<u32> {
+        VersionedFormat::new(*b"TEST", 3)
+            .with_version(2, |reader| u16::unpack_from(reader).map(u32::from))
+    }
+
+    #[test]
+    fn pack_versioned() {
+        let mut bytes = Vec::new();
+        let written = format().pack_into(&1, &mut bytes).unwrap();
+        assert_eq!(written, 10);
+        assert_eq!(
+            bytes,
+            [b'T', b'E', b'S', b'T', 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]
+        );
+    }
+
+    #[test]
+    fn unpack_older_version() {
+        let bytes = [b'T', b'E', b'S', b'T', 0x00, 0x02, 0x00, 0x05];
+        let value = format().unpack_from(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, 5);
+    }
+
+    #[test]
+    fn unpack_unsupported_version() {
+        let bytes = [b'T', b'E', b'S', b'T', 0x00, 0x01, 0x05];
+        let result = format().unpack_from(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::UnsupportedVersion(1))));
+    }
+
+    #[test]
+    fn unpack_wrong_magic() {
+        let bytes = [b'T', b'E', b'X', b'T', 0x00, 0x03, 0x00, 0x00, 0x00, 0x01];
+        let result = format().unpack_from(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::InvalidValue(_))));
+    }
+
+    fn migrations() -> Migrations<String> {
+        Migrations::new(*b"TEST", 3)
+            .with_version::<u8>(1)
+            .with_version::<u16>(2)
+            .with_upgrade(1, |old: u8| old as u16 * 10)
+            .with_upgrade(2, |old: u16| format!("#{}", old))
+    }
+
+    #[test]
+    fn migrate_through_chain() {
+        let bytes = [b'T', b'E', b'S', b'T', 0x00, 0x01, 0x02];
+        let value = migrations().unpack_latest(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, "#20");
+    }
+
+    #[test]
+    fn migrate_current_version() {
+        let bytes = [
+            b'T', b'E', b'S', b'T', 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x61,
+        ];
+        let value = migrations().unpack_latest(&mut bytes.as_ref()).unwrap();
+        assert_eq!(value, "a");
+    }
+
+    #[test]
+    fn migrate_missing_upgrade() {
+        let migrations = Migrations::<u16>::new(*b"TEST", 2).with_version::<u8>(1);
+        let bytes = [b'T', b'E', b'S', b'T', 0x00, 0x01, 0x02];
+        let result = migrations.unpack_latest(&mut bytes.as_ref());
+        assert!(matches!(result, Err(Error::UnsupportedVersion(1))));
+    }
+
+    #[test]
+    fn framed_skips_appended_fields() {
+        let newer = Framed([1u16, 2, 3]).pack_to_vec().unwrap();
+        assert_eq!(newer.len(), 4 + 4 + 6);
+
+        let bytes = [newer.as_slice(), &[0x07]].concat();
+        let mut remaining = bytes.as_slice();
+        let older = Framed::<u32>::unpack_from(&mut remaining).unwrap();
+        assert_eq!(older.0, 3);
+        assert_eq!(u8::unpack_from(&mut remaining).unwrap(), 7);
+    }
+
+    #[test]
+    fn framed_missing_fields() {
+        let bytes = Framed(1u8).pack_to_vec().unwrap();
+        assert!(Framed::<u16>::unpack_exact(&bytes).is_err());
+    }
+}
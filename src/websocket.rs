@@ -0,0 +1,205 @@
+//! Packed values in binary WebSocket messages of `tungstenite`
+//!
+//! The adapters work on the `Message` type shared by `tungstenite` and
+//! `tokio-tungstenite`, so they can be used with both the blocking and the
+//! async client. Text messages are rejected, control messages are skipped
+
+use crate::pack::{self, Pack};
+use crate::transport::DEFAULT_MAX_FRAME_LENGTH;
+use crate::unpack::{Error, Result, Unpack};
+use std::io;
+use tungstenite::Message;
+
+/// Packs a value into a binary message
+pub fn pack_message<T: Pack + ?Sized>(value: &T) -> pack::Result<Message> {
+    Ok(Message::Binary(value.pack_to_vec()?.into()))
+}
+
+/// Unpacks a value from a binary message, which has to contain exactly
+/// this value
+///
+/// Returns `None` for ping, pong and close messages. Fails with
+/// `Error::InvalidValue` for text messages
+pub fn unpack_message<T: Unpack>(message: &Message) -> Result<Option<T>> {
+    match message {
+        Message::Binary(bytes) => T::unpack_exact(bytes).map(Some),
+        Message::Text(_) => Err(Error::InvalidValue(String::from(
+            "expected a binary message, found a text message",
+        ))),
+        _ => Ok(None),
+    }
+}
+
+/// Buffer reassembling values sent with `pack_framed` from binary messages
+///
+/// Senders that stream their frames may split a value across several
+/// messages or put several values into one. The buffer collects the
+/// payloads of binary messages and unpacks every value whose frame is
+/// complete. Frames announcing a payload longer than the configured maximum
+/// are rejected before they are buffered completely
+///
+/// A rejected or undecodable frame is skipped, so the buffer stays usable
+/// after an error. Values completed before the failing frame are kept and
+/// returned by the next call to [`push`](FrameBuffer::push)
+pub struct FrameBuffer<T> {
+    buffer: Vec<u8>,
+    max_frame_length: usize,
+    decoded: Vec<T>,
+    skipping: usize,
+}
+
+impl<T: Unpack> FrameBuffer<T> {
+    /// Creates a buffer accepting frames up to
+    /// [`DEFAULT_MAX_FRAME_LENGTH`]
+    pub fn new() -> Self {
+        Self::with_max_frame_length(DEFAULT_MAX_FRAME_LENGTH)
+    }
+
+    /// Creates a buffer accepting frames up to the given payload length
+    pub fn with_max_frame_length(max_frame_length: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            max_frame_length,
+            decoded: Vec::new(),
+            skipping: 0,
+        }
+    }
+
+    /// Adds the payload of a message and returns all values completed by it
+    ///
+    /// Non-binary messages are handled like by [`unpack_message`]
+    pub fn push(&mut self, message: &Message) -> Result<Vec<T>> {
+        match message {
+            Message::Binary(bytes) => self.buffer.extend_from_slice(bytes),
+            Message::Text(_) => {
+                return Err(Error::InvalidValue(String::from(
+                    "expected a binary message, found a text message",
+                )))
+            }
+            _ => return Ok(Vec::new()),
+        }
+
+        let skipped = self.skipping.min(self.buffer.len());
+        self.skipping -= skipped;
+        let mut start = skipped;
+        let mut result = Ok(());
+
+        while self.buffer.len() - start >= 4 {
+            let mut header = &self.buffer[start..start + 4];
+            let len = u32::unpack_from(&mut header)? as usize;
+
+            if len > self.max_frame_length {
+                let available = self.buffer.len() - start - 4;
+                self.skipping = len - len.min(available);
+                start += 4 + len.min(available);
+                result = Err(Error::IO(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "frame of {} bytes exceeds the maximum of {} bytes",
+                        len, self.max_frame_length
+                    ),
+                )));
+                break;
+            }
+
+            if self.buffer.len() - start - 4 < len {
+                break;
+            }
+
+            let frame = &self.buffer[start + 4..start + 4 + len];
+            start += 4 + len;
+
+            match T::unpack_exact(frame) {
+                Ok(value) => self.decoded.push(value),
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            }
+        }
+
+        self.buffer.drain(..start);
+        result.map(|()| std::mem::take(&mut self.decoded))
+    }
+
+    /// Returns the number of buffered bytes of incomplete frames
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+impl<T: Unpack> Default for FrameBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_round_trip() {
+        let message = pack_message("hello").unwrap();
+        assert!(message.is_binary());
+        let value: Option<String> = unpack_message(&message).unwrap();
+        assert_eq!(value.unwrap(), "hello");
+
+        let ping = Message::Ping(Vec::new().into());
+        assert!(unpack_message::<String>(&ping).unwrap().is_none());
+        let text = Message::text("hello");
+        assert!(matches!(
+            unpack_message::<String>(&text),
+            Err(Error::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn reassemble_split_frames() {
+        let mut bytes = Vec::new();
+        1u16.pack_framed(&mut bytes).unwrap();
+        2u16.pack_framed(&mut bytes).unwrap();
+        3u16.pack_framed(&mut bytes).unwrap();
+
+        let mut buffer = FrameBuffer::<u16>::new();
+        let first = Message::Binary(bytes[..3].to_vec().into());
+        assert!(buffer.push(&first).unwrap().is_empty());
+        let second = Message::Binary(bytes[3..15].to_vec().into());
+        assert_eq!(buffer.push(&second).unwrap(), [1, 2]);
+        assert_eq!(buffer.pending(), 3);
+        let third = Message::Binary(bytes[15..].to_vec().into());
+        assert_eq!(buffer.push(&third).unwrap(), [3]);
+        assert_eq!(buffer.pending(), 0);
+    }
+
+    #[test]
+    fn reject_long_frames() {
+        let mut buffer = FrameBuffer::<u8>::with_max_frame_length(2);
+        let message = Message::Binary(vec![0x00, 0x00, 0x00, 0x03].into());
+        assert!(matches!(buffer.push(&message), Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn recover_after_bad_frames() {
+        let mut bytes = Vec::new();
+        1u8.pack_framed(&mut bytes).unwrap();
+        2u16.pack_framed(&mut bytes).unwrap();
+        3u8.pack_framed(&mut bytes).unwrap();
+
+        let mut buffer = FrameBuffer::<u8>::new();
+        let message = Message::Binary(bytes.into());
+        assert!(matches!(
+            buffer.push(&message),
+            Err(Error::TrailingBytes(1))
+        ));
+        let empty = Message::Binary(Vec::new().into());
+        assert_eq!(buffer.push(&empty).unwrap(), [1, 3]);
+
+        let mut buffer = FrameBuffer::<u8>::with_max_frame_length(2);
+        let long = Message::Binary(vec![0x00, 0x00, 0x00, 0x03, 0xAA].into());
+        assert!(matches!(buffer.push(&long), Err(Error::IO(_))));
+        let rest = Message::Binary(vec![0xAA, 0xAA, 0x00, 0x00, 0x00, 0x01, 0x07].into());
+        assert_eq!(buffer.push(&rest).unwrap(), [7]);
+        assert_eq!(buffer.pending(), 0);
+    }
+}